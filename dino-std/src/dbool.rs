@@ -4,6 +4,7 @@ use crate::unique::Unique;
 use crate::outptr::OutPtr;
 use crate::runtime::alloc_static;
 use crate::dunit::DUnit;
+use crate::dint::DInt;
 
 // Avoid re-allocating the same values over and over again by reusing the two possible values of
 // this type
@@ -44,8 +45,24 @@ pub extern fn bool__not(x: &DBool, mut out: OutPtr<DBool>) {
     out.write(DBool::new(!x.0));
 }
 
+/// Exits the program with a non-zero exit code if the condition is false
 #[no_mangle]
-pub extern fn print_bool(x: &DBool, mut out: OutPtr<DUnit>) {
+pub extern fn assert(cond: &DBool, mut out: OutPtr<DUnit>) {
+    if cond.0 {
+        out.write(DUnit::new());
+        return;
+    }
+
+    let message = b"assertion failed\n";
+    unsafe {
+        libc::write(libc::STDERR_FILENO, message.as_ptr() as *const libc::c_void, message.len());
+        libc::exit(101);
+    }
+}
+
+/// Prints `true` or `false` followed by a newline
+#[no_mangle]
+pub extern fn println_bool(x: &DBool, mut out: OutPtr<DUnit>) {
     if x.0 {
         unsafe { super::printf(b"true\n\0" as *const u8); }
     } else {
@@ -54,3 +71,21 @@ pub extern fn print_bool(x: &DBool, mut out: OutPtr<DUnit>) {
 
     out.write(DUnit::new());
 }
+
+/// Prints `true` or `false` without a trailing newline
+#[no_mangle]
+pub extern fn print_bool(x: &DBool, mut out: OutPtr<DUnit>) {
+    if x.0 {
+        unsafe { super::printf(b"true\0" as *const u8); }
+    } else {
+        unsafe { super::printf(b"false\0" as *const u8); }
+    }
+
+    out.write(DUnit::new());
+}
+
+/// Converts a bool to an int: true is 1 and false is 0
+#[no_mangle]
+pub extern fn bool_to_int(x: &DBool, mut out: OutPtr<DInt>) {
+    out.write(DInt::new(x.0 as i64));
+}
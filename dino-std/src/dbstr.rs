@@ -10,6 +10,7 @@ use crate::runtime::{alloc_struct, __dino__alloc_value};
 use crate::dbool::DBool;
 use crate::dunit::DUnit;
 use crate::dint::DInt;
+use crate::doption::DOptionInt;
 
 /// The dino byte string type
 #[repr(C)]
@@ -70,6 +71,22 @@ impl DBStr {
 
         alloc_struct(Self {data, length})
     }
+
+    /// Returns the bytes of this string as a slice
+    fn as_slice(&self) -> &[c_char] {
+        //TODO: Guard against null/dangling data pointers
+        unsafe { slice::from_raw_parts(self.data.as_ptr(), self.length) }
+    }
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack` at or after `start`
+///
+/// `needle` must not be empty.
+fn find_from(haystack: &[c_char], needle: &[c_char], start: usize) -> Option<usize> {
+    debug_assert!(!needle.is_empty());
+    haystack[start..].windows(needle.len())
+        .position(|window| window == needle)
+        .map(|index| start + index)
 }
 
 /// Creates a new DBStr from a byte string literal
@@ -87,6 +104,25 @@ pub extern fn bstr_len(s: &DBStr, mut out: OutPtr<DInt>) {
     out.write(DInt::new(s.length as i64));
 }
 
+/// Hashes the bytes of the string using the 64-bit FNV-1a hash function
+///
+/// The hash only depends on the bytes of the string, so it is guaranteed to be the same across
+/// runs of the program and across platforms. The 64-bit hash value is reinterpreted as a (possibly
+/// negative) int.
+///
+/// See: http://www.isthe.com/chongo/tech/comp/fnv/index.html#FNV-1a
+#[no_mangle]
+pub extern fn bstr_hash(s: &DBStr, mut out: OutPtr<DInt>) {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let hash = s.as_slice().iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ (byte as u8 as u64)).wrapping_mul(FNV_PRIME)
+    });
+
+    out.write(DInt::new(hash as i64));
+}
+
 #[no_mangle]
 pub extern fn bstr_eq(s1: &DBStr, s2: &DBStr, mut out: OutPtr<DBool>) {
     out.write(DBool::new(s1 == s2));
@@ -130,6 +166,92 @@ pub extern fn bstr_concat(s1: &DBStr, s2: &DBStr, mut out: OutPtr<DBStr>) {
     out.write(alloc_struct(DBStr {data, length}));
 }
 
+#[no_mangle]
+pub extern fn bstr_replace(s: &DBStr, from: &DBStr, to: &DBStr, mut out: OutPtr<DBStr>) {
+    let s_data = s.as_slice();
+    let from_data = from.as_slice();
+    let to_data = to.as_slice();
+
+    // An empty pattern would match everywhere, so the string is left unchanged
+    if from_data.is_empty() {
+        // Safe because the pointer and length come from a valid DBStr
+        return out.write(unsafe { DBStr::copy_ptr(s.data.as_ptr(), s.length) });
+    }
+
+    // Count the non-overlapping matches first so the result can be allocated all at once
+    let mut matches = 0;
+    let mut pos = 0;
+    while let Some(index) = find_from(s_data, from_data, pos) {
+        matches += 1;
+        pos = index + from.length;
+    }
+
+    let length = s.length - matches * from.length + matches * to.length;
+    if length == 0 {
+        return out.write(DBStr::new());
+    }
+
+    let data = unsafe {
+        let data = __dino__alloc_value(length) as *mut c_char;
+        //TODO: Check if returned ptr is NULL
+        let mut written = 0;
+        let mut pos = 0;
+        while let Some(index) = find_from(s_data, from_data, pos) {
+            // Copy everything up to the match, then the replacement
+            ptr::copy(s_data.as_ptr().add(pos), data.add(written), index - pos);
+            written += index - pos;
+            ptr::copy(to_data.as_ptr(), data.add(written), to.length);
+            written += to.length;
+
+            pos = index + from.length;
+        }
+        // Copy everything after the last match
+        ptr::copy(s_data.as_ptr().add(pos), data.add(written), s.length - pos);
+
+        Unique::new_unchecked(data)
+    };
+
+    out.write(alloc_struct(DBStr {data, length}));
+}
+
+/// Concatenates the three strings, separated by `sep`
+///
+/// This is a stopgap until there is a list type that `bstr_join` can take instead.
+#[no_mangle]
+pub extern fn bstr_join3(a: &DBStr, b: &DBStr, c: &DBStr, sep: &DBStr, mut out: OutPtr<DBStr>) {
+    out.write(join(&[a, b, c], sep));
+}
+
+/// Concatenates the given pieces with `sep` between each consecutive pair
+///
+/// Joining no pieces produces an empty string and joining a single piece produces a copy of that
+/// piece without any separator.
+fn join(pieces: &[&DBStr], sep: &DBStr) -> Unique<DBStr> {
+    let separators = pieces.len().saturating_sub(1);
+    let length = pieces.iter().map(|piece| piece.length).sum::<usize>() + separators * sep.length;
+    if length == 0 {
+        return DBStr::new();
+    }
+
+    let data = unsafe {
+        let data = __dino__alloc_value(length) as *mut c_char;
+        //TODO: Check if returned ptr is NULL
+        let mut written = 0;
+        for (i, piece) in pieces.iter().enumerate() {
+            if i > 0 {
+                ptr::copy(sep.data.as_ptr(), data.add(written), sep.length);
+                written += sep.length;
+            }
+            ptr::copy(piece.data.as_ptr(), data.add(written), piece.length);
+            written += piece.length;
+        }
+
+        Unique::new_unchecked(data)
+    };
+
+    alloc_struct(DBStr {data, length})
+}
+
 #[no_mangle]
 pub extern fn bstr_slice(s: &DBStr, start: &DInt, end: &DInt, mut out: OutPtr<DBStr>) {
     //TODO: Bounds checking
@@ -147,14 +269,302 @@ pub extern fn bstr_get(s: &DBStr, index: &DInt, out: OutPtr<DBStr>) {
     bstr_slice(s, index, &index.map(|x| x + 1), out);
 }
 
+/// Returns true if the string contains the given byte
+///
+/// Exits the program with a non-zero exit code if the byte is not between 0 and 255 (inclusive).
+/// Masking the value instead would silently turn a mistake into a search for a different byte.
 #[no_mangle]
-pub extern fn print_bstr(s: &DBStr, mut out: OutPtr<DUnit>) {
+pub extern fn bstr_contains_byte(s: &DBStr, byte: &DInt, mut out: OutPtr<DBool>) {
+    let byte = byte.value();
+    if byte < 0 || byte > 255 {
+        let message = b"byte passed to `bstr_contains_byte` must be between 0 and 255\n";
+        unsafe {
+            libc::write(libc::STDERR_FILENO, message.as_ptr() as *const libc::c_void, message.len());
+            libc::exit(101);
+        }
+    }
+
+    let byte = byte as u8 as c_char;
+    out.write(DBool::new(s.as_slice().contains(&byte)));
+}
+
+/// Returns the index of the first occurrence of `needle` in the string, or -1 if the string does
+/// not contain `needle`
+///
+/// An empty needle is found at index 0 of every string, including the empty string. A needle that
+/// is longer than the string is never found.
+#[no_mangle]
+pub extern fn bstr_find(haystack: &DBStr, needle: &DBStr, mut out: OutPtr<DInt>) {
+    let index = if needle.length == 0 {
+        Some(0)
+    } else {
+        find_from(haystack.as_slice(), needle.as_slice(), 0)
+    };
+
+    out.write(DInt::new(index.map(|index| index as i64).unwrap_or(-1)));
+}
+
+/// Returns true if the string contains `needle`
+///
+/// Every string contains the empty string.
+#[no_mangle]
+pub extern fn bstr_contains(haystack: &DBStr, needle: &DBStr, mut out: OutPtr<DBool>) {
+    let found = needle.length == 0 || find_from(haystack.as_slice(), needle.as_slice(), 0).is_some();
+    out.write(DBool::new(found));
+}
+
+/// Returns the bytes of the string before the given index
+///
+/// Together with `bstr_after`, this splits a string in two. The index may be anywhere from 0 to
+/// the length of the string (inclusive). Exits the program with a non-zero exit code if the index
+/// is out of that range.
+#[no_mangle]
+pub extern fn bstr_before(s: &DBStr, index: &DInt, mut out: OutPtr<DBStr>) {
+    let index = split_index(s, index, b"bstr_before");
+    // Safe because the pointer comes from a valid DBStr and the index is within its bounds
+    out.write(unsafe { DBStr::copy_ptr(s.data.as_ptr(), index) });
+}
+
+/// Returns the bytes of the string at and after the given index
+///
+/// Together with `bstr_before`, this splits a string in two. The index may be anywhere from 0 to
+/// the length of the string (inclusive). Exits the program with a non-zero exit code if the index
+/// is out of that range.
+#[no_mangle]
+pub extern fn bstr_after(s: &DBStr, index: &DInt, mut out: OutPtr<DBStr>) {
+    let index = split_index(s, index, b"bstr_after");
+    // Safe because the pointer comes from a valid DBStr and the index is within its bounds
+    out.write(unsafe { DBStr::copy_ptr(s.data.as_ptr().add(index), s.length - index) });
+}
+
+/// Returns the given index if a string can be split at that index, otherwise exits the program
+/// after reporting that the index passed to the given function was out of bounds
+fn split_index(s: &DBStr, index: &DInt, func_name: &[u8]) -> usize {
+    let index = index.value();
+    if index >= 0 && index as u64 <= s.length as u64 {
+        return index as usize;
+    }
+
+    let messages: [&[u8]; 3] = [b"index out of bounds in `", func_name, b"`\n"];
+    unsafe {
+        for message in &messages {
+            libc::write(libc::STDERR_FILENO, message.as_ptr() as *const libc::c_void, message.len());
+        }
+        libc::exit(101);
+    }
+}
+
+/// Returns the smallest byte value (between 0 and 255) in the string
+///
+/// Exits the program with a non-zero exit code if the string is empty, since an empty string has
+/// no smallest byte.
+#[no_mangle]
+pub extern fn bstr_byte_min(s: &DBStr, mut out: OutPtr<DInt>) {
+    let min = bytes(s).min().unwrap_or_else(|| empty_bstr_error(b"bstr_byte_min"));
+    out.write(DInt::new(min as i64));
+}
+
+/// Returns the largest byte value (between 0 and 255) in the string
+///
+/// Exits the program with a non-zero exit code if the string is empty, since an empty string has
+/// no largest byte.
+#[no_mangle]
+pub extern fn bstr_byte_max(s: &DBStr, mut out: OutPtr<DInt>) {
+    let max = bytes(s).max().unwrap_or_else(|| empty_bstr_error(b"bstr_byte_max"));
+    out.write(DInt::new(max as i64));
+}
+
+/// Returns the bytes of the string as unsigned values, regardless of the signedness of `c_char`
+fn bytes(s: &DBStr) -> impl Iterator<Item=u8> + '_ {
+    s.as_slice().iter().map(|&byte| byte as u8)
+}
+
+/// Exits the program after reporting that the given function was passed an empty string
+fn empty_bstr_error(func_name: &[u8]) -> ! {
+    let messages: [&[u8]; 3] = [b"empty string passed to `", func_name, b"`\n"];
+    unsafe {
+        for message in &messages {
+            libc::write(libc::STDERR_FILENO, message.as_ptr() as *const libc::c_void, message.len());
+        }
+        libc::exit(101);
+    }
+}
+
+/// Formats the given integer in base 10
+#[no_mangle]
+pub extern fn bstr_from_int(value: &DInt, mut out: OutPtr<DBStr>) {
+    out.write(format_int(value.value(), 10));
+}
+
+/// Formats the given integer in the given base, using lowercase letters for digits above 9
+///
+/// Exits the program with a non-zero exit code if the radix is not between 2 and 36 (inclusive).
+#[no_mangle]
+pub extern fn bstr_from_int_radix(value: &DInt, radix: &DInt, mut out: OutPtr<DBStr>) {
+    let radix = radix.value();
+    if radix < 2 || radix > 36 {
+        let message = b"radix passed to `bstr_from_int_radix` must be between 2 and 36\n";
+        unsafe {
+            libc::write(libc::STDERR_FILENO, message.as_ptr() as *const libc::c_void, message.len());
+            libc::exit(101);
+        }
+    }
+
+    out.write(format_int(value.value(), radix as u64));
+}
+
+/// Parses a base 10 integer, returning an empty option if the string is not a valid integer
+///
+/// A valid integer is an optional leading `-` followed by one or more of the digits `0` to `9`.
+/// Any other bytes (including whitespace and a leading `+`) make the string invalid, as does a
+/// value that does not fit in an int. This is the inverse of `bstr_from_int`.
+#[no_mangle]
+pub extern fn bstr_to_int(s: &DBStr, mut out: OutPtr<DOptionInt>) {
+    out.write(match parse_int(bytes(s)) {
+        Some(value) => DOptionInt::some(value),
+        None => DOptionInt::none(),
+    });
+}
+
+fn parse_int(mut bytes: impl Iterator<Item=u8>) -> Option<i64> {
+    let mut first = bytes.next()?;
+    let is_negative = first == b'-';
+    if is_negative {
+        first = bytes.next()?;
+    }
+
+    // The value is accumulated as a negative number so that the most negative integer (which has
+    // no positive counterpart) can be parsed
+    let mut value = 0i64;
+    for byte in core::iter::once(first).chain(bytes) {
+        let digit = match byte {
+            b'0'..=b'9' => (byte - b'0') as i64,
+            _ => return None,
+        };
+        value = value.checked_mul(10)?.checked_sub(digit)?;
+    }
+
+    if is_negative {
+        Some(value)
+    } else {
+        value.checked_neg()
+    }
+}
+
+/// Formats the given integer in the given base (between 2 and 36)
+fn format_int(value: i64, radix: u64) -> Unique<DBStr> {
+    const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    debug_assert!(radix >= 2 && radix <= 36);
+
+    // Enough space for every binary digit of a 64-bit integer plus a minus sign
+    let mut buffer = [0u8; 65];
+    let mut start = buffer.len();
+
+    // Using the wrapping absolute value correctly handles the most negative integer, since its
+    // magnitude is still representable as a u64
+    let mut magnitude = value.wrapping_abs() as u64;
+    loop {
+        start -= 1;
+        buffer[start] = DIGITS[(magnitude % radix) as usize];
+        magnitude /= radix;
+        if magnitude == 0 {
+            break;
+        }
+    }
+
+    if value < 0 {
+        start -= 1;
+        buffer[start] = b'-';
+    }
+
+    let digits = &buffer[start..];
+    unsafe { DBStr::copy_ptr(digits.as_ptr() as *const c_char, digits.len()) }
+}
+
+/// Prints the byte string followed by a newline
+#[no_mangle]
+pub extern fn println_bstr(s: &DBStr, mut out: OutPtr<DUnit>) {
     // https://stackoverflow.com/questions/2239519/is-there-a-way-to-specify-how-many-characters-of-a-string-to-print-out-using-pri
     unsafe { super::printf(b"%*.*s\n\0" as *const u8, s.length, s.length, s.data); }
 
     out.write(DUnit::new());
 }
 
+/// Prints the byte string without a trailing newline
+///
+/// Use `print_bstr(b"\n")` or `println_bstr(b"")` to end the line.
+#[no_mangle]
+pub extern fn print_bstr(s: &DBStr, mut out: OutPtr<DUnit>) {
+    unsafe { super::printf(b"%*.*s\0" as *const u8, s.length, s.length, s.data); }
+
+    out.write(DUnit::new());
+}
+
+/// Prints the given message to stderr and then exits the program with a non-zero exit code
+#[no_mangle]
+pub extern fn panic(message: &DBStr, _out: OutPtr<DUnit>) -> ! {
+    let message = message.as_slice();
+    unsafe {
+        libc::write(libc::STDERR_FILENO, message.as_ptr() as *const libc::c_void, message.len());
+        libc::write(libc::STDERR_FILENO, b"\n" as *const u8 as *const libc::c_void, 1);
+
+        libc::exit(101);
+    }
+}
+
+/// Checks that the two byte strings are equal
+///
+/// If they are not equal, both strings are printed to stderr along with the index of the first
+/// byte where they differ, and then the program exits with a non-zero exit code.
+#[no_mangle]
+pub extern fn assert_eq_bstr(left: &DBStr, right: &DBStr, mut out: OutPtr<DUnit>) {
+    if left == right {
+        out.write(DUnit::new());
+        return;
+    }
+
+    let left = left.as_slice();
+    let right = right.as_slice();
+    // If one string is a prefix of the other, the first difference is just past the end of the
+    // shorter string
+    let index = left.iter().zip(right).position(|(l, r)| l != r)
+        .unwrap_or_else(|| left.len().min(right.len()));
+
+    // Large enough for any usize, plus the null terminator
+    let mut index_buf = [0u8; 24];
+    unsafe {
+        let index_len = libc::snprintf(index_buf.as_mut_ptr() as *mut c_char, index_buf.len(),
+            b"%zu\0" as *const u8 as *const c_char, index);
+
+        let write_stderr = |bytes: *const c_char, len: usize| {
+            libc::write(libc::STDERR_FILENO, bytes as *const libc::c_void, len);
+        };
+        let write_msg = |msg: &[u8]| write_stderr(msg.as_ptr() as *const c_char, msg.len());
+
+        write_msg(b"assertion failed: `left == right`\n  left: `");
+        write_stderr(left.as_ptr(), left.len());
+        write_msg(b"`\n right: `");
+        write_stderr(right.as_ptr(), right.len());
+        write_msg(b"`\nfirst difference at byte index ");
+        write_stderr(index_buf.as_ptr() as *const c_char, index_len as usize);
+        write_msg(b"\n");
+
+        libc::exit(101);
+    }
+}
+
+/// Reports that code the programmer declared impossible to reach was executed and then exits the
+/// program with a non-zero exit code
+#[no_mangle]
+pub extern fn unreachable(_out: OutPtr<DUnit>) -> ! {
+    let message = b"runtime error: entered unreachable code\n";
+    unsafe {
+        libc::write(libc::STDERR_FILENO, message.as_ptr() as *const libc::c_void, message.len());
+
+        libc::exit(101);
+    }
+}
+
 #[no_mangle]
 pub extern fn read_line_bstr(mut out: OutPtr<DBStr>) {
     // See: http://man7.org/linux/man-pages/man3/getline.3.html
@@ -185,3 +595,114 @@ pub extern fn read_line_bstr(mut out: OutPtr<DBStr>) {
         DBStr::copy_ptr(data as *mut c_char, length)
     });
 }
+
+/// Reads everything remaining on stdin, until the end of the input, into a single byte string
+///
+/// Unlike `read_line_bstr`, every newline is kept, including any trailing newline. If stdin is
+/// empty or has already been closed, this returns an empty byte string.
+#[no_mangle]
+pub extern fn read_all_bstr(mut out: OutPtr<DBStr>) {
+    //TODO: lock stdin
+    out.write(unsafe { read_to_end(super::stdin) });
+}
+
+/// Reads everything remaining in the given file into a single byte string
+///
+/// Safe as long as the file pointer is valid and open for reading.
+unsafe fn read_to_end(file: *mut libc::FILE) -> Unique<DBStr> {
+    let mut data: *mut c_char = ptr::null_mut();
+    let mut capacity = 0usize;
+    let mut length = 0usize;
+
+    loop {
+        if length == capacity {
+            capacity = if capacity == 0 { 4096 } else { capacity * 2 };
+            data = libc::realloc(data as *mut libc::c_void, capacity) as *mut c_char;
+            //TODO: Error handling
+            if data.is_null() {
+                libc::exit(1);
+            }
+        }
+
+        let requested = capacity - length;
+        let read = libc::fread(data.add(length) as *mut libc::c_void, 1, requested, file);
+        length += read;
+
+        // A short read means that either the end of the input was reached or an error occurred.
+        // Either way, there is nothing more to read.
+        if read < requested {
+            break;
+        }
+    }
+
+    // Safe as long as the pointer passed in is valid and the length is correct
+    let bstr = DBStr::copy_ptr(data, length);
+    libc::free(data as *mut libc::c_void);
+    bstr
+}
+
+/// Opens the file at the given path with the given null-terminated `fopen` mode
+///
+/// Returns a null pointer if the file could not be opened. The path is a byte string, so it is
+/// copied into a null-terminated buffer first. A path that contains a NUL byte cannot name any
+/// file, so it is never opened.
+fn open_file(path: &DBStr, mode: &[u8]) -> *mut libc::FILE {
+    let path = path.as_slice();
+    if path.contains(&0) {
+        return ptr::null_mut();
+    }
+
+    unsafe {
+        let c_path = libc::malloc(path.len() + 1) as *mut c_char;
+        //TODO: Error handling
+        if c_path.is_null() {
+            libc::exit(1);
+        }
+        ptr::copy_nonoverlapping(path.as_ptr(), c_path, path.len());
+        *c_path.add(path.len()) = 0;
+
+        let file = libc::fopen(c_path, mode.as_ptr() as *const c_char);
+        libc::free(c_path as *mut libc::c_void);
+        file
+    }
+}
+
+/// Reads the entire contents of the file at the given path into a byte string
+///
+/// The contents are read as raw bytes, so they may contain NUL bytes or invalid UTF-8. If the file
+/// cannot be opened (e.g. because it does not exist), this returns an empty byte string. That
+/// cannot be distinguished from reading an empty file.
+#[no_mangle]
+pub extern fn read_file(path: &DBStr, mut out: OutPtr<DBStr>) {
+    let file = open_file(path, b"rb\0");
+    if file.is_null() {
+        out.write(DBStr::new());
+        return;
+    }
+
+    out.write(unsafe { read_to_end(file) });
+    unsafe { libc::fclose(file); }
+}
+
+/// Writes the byte string to the file at the given path, replacing any previous contents
+///
+/// The file is created if it does not exist. Every byte is written as-is, including NUL bytes.
+/// Returns false if the file could not be opened (e.g. because its directory does not exist or it
+/// is not writable) or if not every byte could be written.
+#[no_mangle]
+pub extern fn write_file(path: &DBStr, contents: &DBStr, mut out: OutPtr<DBool>) {
+    let file = open_file(path, b"wb\0");
+    if file.is_null() {
+        out.write(DBool::new(false));
+        return;
+    }
+
+    let contents = contents.as_slice();
+    let written = unsafe {
+        libc::fwrite(contents.as_ptr() as *const libc::c_void, 1, contents.len(), file)
+    };
+    // Closing the file flushes anything still buffered, so it can also fail
+    let closed = unsafe { libc::fclose(file) } == 0;
+
+    out.write(DBool::new(written == contents.len() && closed));
+}
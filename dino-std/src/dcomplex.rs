@@ -2,7 +2,7 @@ use crate::unique::Unique;
 use crate::outptr::OutPtr;
 use crate::runtime::alloc_struct;
 use crate::dunit::DUnit;
-use crate::dreal::DReal;
+use crate::dreal::{DReal, REAL_BUF_LEN, format_real};
 
 /// The dino complex number type
 ///
@@ -14,6 +14,13 @@ pub struct DComplex {
 }
 
 impl DComplex {
+    fn new(real: f64, imag: f64) -> Unique<Self> {
+        alloc_struct(DComplex {
+            real: real.into(),
+            imag: imag.into(),
+        })
+    }
+
     pub fn real(&self) -> &DReal {
         // This is safe assuming that the memory is well aligned
         unsafe {
@@ -104,14 +111,89 @@ pub extern fn sub_complex_real(x: &DComplex, y: &DReal, mut out: OutPtr<DComplex
     }));
 }
 
+/// Multiplies two complex numbers: `(a + bi)(c + di) = (ac - bd) + (ad + bc)i`
+#[no_mangle]
+pub extern fn mul_complex(x: &DComplex, y: &DComplex, mut out: OutPtr<DComplex>) {
+    let (a, b) = (x.real().value(), x.imag().value());
+    let (c, d) = (y.real().value(), y.imag().value());
+    out.write(DComplex::new(a*c - b*d, a*d + b*c));
+}
+
+#[no_mangle]
+pub extern fn mul_real_complex(x: &DReal, y: &DComplex, mut out: OutPtr<DComplex>) {
+    out.write(alloc_struct(DComplex {
+        real: x * y.real(),
+        imag: x * y.imag(),
+    }));
+}
+
+#[no_mangle]
+pub extern fn mul_complex_real(x: &DComplex, y: &DReal, mut out: OutPtr<DComplex>) {
+    out.write(alloc_struct(DComplex {
+        real: x.real() * y,
+        imag: x.imag() * y,
+    }));
+}
+
+/// Divides two complex numbers: `(a + bi)/(c + di) = ((ac + bd) + (bc - ad)i)/(c^2 + d^2)`
+///
+/// Like `div_real`, this follows IEEE 754 semantics, so dividing by zero produces infinite or NaN
+/// parts instead of exiting the program.
+#[no_mangle]
+pub extern fn div_complex(x: &DComplex, y: &DComplex, mut out: OutPtr<DComplex>) {
+    let (a, b) = (x.real().value(), x.imag().value());
+    let (c, d) = (y.real().value(), y.imag().value());
+    let denom = c*c + d*d;
+    out.write(DComplex::new((a*c + b*d) / denom, (b*c - a*d) / denom));
+}
+
+/// Divides a real number by a complex number: `x/(c + di) = (xc - xdi)/(c^2 + d^2)`
+#[no_mangle]
+pub extern fn div_real_complex(x: &DReal, y: &DComplex, mut out: OutPtr<DComplex>) {
+    let x = x.value();
+    let (c, d) = (y.real().value(), y.imag().value());
+    let denom = c*c + d*d;
+    out.write(DComplex::new(x*c / denom, -x*d / denom));
+}
+
+#[no_mangle]
+pub extern fn div_complex_real(x: &DComplex, y: &DReal, mut out: OutPtr<DComplex>) {
+    out.write(alloc_struct(DComplex {
+        real: x.real() / y,
+        imag: x.imag() / y,
+    }));
+}
+
+/// Prints the complex number followed by a newline
+#[no_mangle]
+pub extern fn println_complex(x: &DComplex, mut out: OutPtr<DUnit>) {
+    // Both parts are printed so that they round-trip, just like with `println_real`
+    let mut real = [0; REAL_BUF_LEN];
+    format_real(x.real().value(), &mut real);
+    let mut imag = [0; REAL_BUF_LEN];
+    format_real(x.imag().value(), &mut imag);
+
+    unsafe {
+        super::printf(b"%s + %si\n\0" as *const u8, real.as_ptr(), imag.as_ptr());
+    }
+
+    out.write(DUnit::new());
+}
+
+/// Prints the complex number without a trailing newline
+///
+/// The number is formatted the same way as in `println_complex`.
 #[no_mangle]
 pub extern fn print_complex(x: &DComplex, mut out: OutPtr<DUnit>) {
-    let real = x.real().value();
-    let imag = x.imag().value();
+    let mut real = [0; REAL_BUF_LEN];
+    format_real(x.real().value(), &mut real);
+    let mut imag = [0; REAL_BUF_LEN];
+    format_real(x.imag().value(), &mut imag);
 
     unsafe {
-        super::printf(b"%g + %gi\n\0" as *const u8, real, imag);
+        super::printf(b"%s + %si\0" as *const u8, real.as_ptr(), imag.as_ptr());
     }
 
     out.write(DUnit::new());
 }
+
@@ -1,6 +1,6 @@
 use crate::unique::Unique;
 use crate::outptr::OutPtr;
-use crate::runtime::alloc_no_ptr;
+use crate::runtime::{alloc_no_ptr, alloc_struct};
 use crate::dunit::DUnit;
 use crate::dbool::DBool;
 
@@ -71,14 +71,61 @@ pub extern fn int__mul(x: &DInt, y: &DInt, mut out: OutPtr<DInt>) {
     out.write(DInt::new(x.0 * y.0));
 }
 
+/// Exits the program with a non-zero exit code if the given divisor is zero
+///
+/// Dividing by zero would otherwise panic, and panicking halts the program forever instead of
+/// exiting.
+fn check_divisor(divisor: i64, message: &[u8]) {
+    if divisor == 0 {
+        unsafe {
+            libc::write(libc::STDERR_FILENO, message.as_ptr() as *const libc::c_void, message.len());
+            libc::exit(101);
+        }
+    }
+}
+
+/// Divides two integers, rounding the quotient towards zero (e.g. `-7 / 2 == -3`)
+///
+/// Exits the program with a non-zero exit code if the divisor is zero. Dividing the smallest
+/// integer by `-1` wraps around to the smallest integer.
 #[no_mangle]
 pub extern fn int__div(x: &DInt, y: &DInt, mut out: OutPtr<DInt>) {
-    out.write(DInt::new(x.0 / y.0));
+    check_divisor(y.0, b"attempt to divide by zero in `int__div`\n");
+    out.write(DInt::new(x.0.wrapping_div(y.0)));
 }
 
+/// Returns the remainder left over from `int__div`, so `x == (x / y) * y + x % y`
+///
+/// The remainder always has the same sign as `x` (e.g. `-7 % 2 == -1`). Exits the program with a
+/// non-zero exit code if the divisor is zero.
 #[no_mangle]
 pub extern fn int__rem(x: &DInt, y: &DInt, mut out: OutPtr<DInt>) {
-    out.write(DInt::new(x.0 % y.0));
+    check_divisor(y.0, b"attempt to divide by zero in `int__rem`\n");
+    out.write(DInt::new(x.0.wrapping_rem(y.0)));
+}
+
+/// The quotient and remainder of an integer division, as produced by `int__divmod`
+///
+/// The fields are read directly by the generated code, so their names must match the fields of
+/// the `divmod_int` type in the compiler's prelude.
+#[repr(C)]
+pub struct DDivModInt {
+    quotient: Unique<DInt>,
+    remainder: Unique<DInt>,
+}
+
+/// Divides two integers, returning both the quotient and the remainder
+///
+/// The results are the same as calling `int__div` and `int__rem` with the same arguments, so the
+/// quotient is rounded towards zero and the remainder has the same sign as `x`. Exits the program
+/// with a non-zero exit code if the divisor is zero.
+#[no_mangle]
+pub extern fn int__divmod(x: &DInt, y: &DInt, mut out: OutPtr<DDivModInt>) {
+    check_divisor(y.0, b"attempt to divide by zero in `int__divmod`\n");
+    out.write(alloc_struct(DDivModInt {
+        quotient: DInt::new(x.0.wrapping_div(y.0)),
+        remainder: DInt::new(x.0.wrapping_rem(y.0)),
+    }));
 }
 
 #[no_mangle]
@@ -86,11 +133,37 @@ pub extern fn int__neg(x: &DInt, mut out: OutPtr<DInt>) {
     out.write(DInt::new(-x.0));
 }
 
+/// Returns the absolute value of the integer
+///
+/// The absolute value of the smallest integer cannot be represented, so it wraps around to the
+/// smallest integer.
 #[no_mangle]
-pub extern fn print_int(x: &DInt, mut out: OutPtr<DUnit>) {
+pub extern fn int_abs(x: &DInt, mut out: OutPtr<DInt>) {
+    out.write(DInt::new(x.0.wrapping_abs()));
+}
+
+/// Prints the int followed by a newline
+#[no_mangle]
+pub extern fn println_int(x: &DInt, mut out: OutPtr<DUnit>) {
     unsafe {
         super::printf(b"%lld\n\0" as *const u8, x.0);
     }
 
     out.write(DUnit::new());
 }
+
+/// Prints the int without a trailing newline so that more can be printed on the same line
+#[no_mangle]
+pub extern fn print_int(x: &DInt, mut out: OutPtr<DUnit>) {
+    unsafe {
+        super::printf(b"%lld\0" as *const u8, x.0);
+    }
+
+    out.write(DUnit::new());
+}
+
+/// Converts an int to a bool: any nonzero value is true and zero is false
+#[no_mangle]
+pub extern fn int_to_bool(x: &DInt, mut out: OutPtr<DBool>) {
+    out.write(DBool::new(x.0 != 0));
+}
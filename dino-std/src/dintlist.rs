@@ -0,0 +1,128 @@
+use core::mem;
+use core::ptr;
+use core::slice;
+
+use crate::unique::Unique;
+use crate::outptr::OutPtr;
+use crate::runtime::{alloc_struct, __dino__alloc_value};
+use crate::dint::DInt;
+
+/// A list of dino integers
+///
+/// This will be replaced by a generic list type once the language supports generics. Like every
+/// other extern type, a list is never modified in place. Every operation that changes a list
+/// produces a new list instead.
+#[repr(C)]
+pub struct DIntList {
+    items: Unique<i64>,
+    length: usize,
+}
+
+impl DIntList {
+    pub fn new() -> Unique<Self> {
+        alloc_struct(DIntList {
+            items: Unique::empty(),
+            length: 0,
+        })
+    }
+
+    /// Returns a new list with the given items followed by `extra` more items that are
+    /// initialized to zero
+    fn copy_with_extra(values: &[i64], extra: usize) -> Unique<Self> {
+        let length = values.len() + extra;
+        if length == 0 {
+            return DIntList::new();
+        }
+
+        let items = unsafe {
+            // The items are never pointers, so they do not need to be scanned by the GC
+            let data = __dino__alloc_value(length * mem::size_of::<i64>()) as *mut i64;
+            //TODO: Check if returned ptr is NULL
+            ptr::copy(values.as_ptr(), data, values.len());
+            ptr::write_bytes(data.add(values.len()), 0, extra);
+            Unique::new_unchecked(data)
+        };
+
+        alloc_struct(Self {items, length})
+    }
+
+    /// Returns the items of this list as a slice
+    fn as_slice(&self) -> &[i64] {
+        //TODO: Guard against null/dangling data pointers
+        unsafe { slice::from_raw_parts(self.items.as_ptr(), self.length) }
+    }
+
+    /// Returns a copy of this list with its items modified by the given function
+    fn map_items(&self, extra: usize, f: impl FnOnce(&mut [i64])) -> Unique<Self> {
+        let list = DIntList::copy_with_extra(self.as_slice(), extra);
+
+        // The copy is not shared with anything yet, so it is safe to modify it in place
+        unsafe {
+            let DIntList {items, length} = &*list.as_ptr();
+            f(slice::from_raw_parts_mut(items.as_ptr(), *length));
+        }
+
+        list
+    }
+}
+
+/// Creates a new empty list
+#[no_mangle]
+pub extern fn int_list_new(mut out: OutPtr<DIntList>) {
+    out.write(DIntList::new());
+}
+
+/// Returns a new list with the given value added after all the items of the list
+#[no_mangle]
+pub extern fn int_list_push(list: &DIntList, value: &DInt, mut out: OutPtr<DIntList>) {
+    let value = value.value();
+    out.write(list.map_items(1, |items| items[items.len() - 1] = value));
+}
+
+#[no_mangle]
+pub extern fn int_list_len(list: &DIntList, mut out: OutPtr<DInt>) {
+    out.write(DInt::new(list.length as i64));
+}
+
+/// Returns the item at the given index or exits the program with a non-zero exit code if the
+/// index is out of bounds
+#[no_mangle]
+pub extern fn int_list_get(list: &DIntList, index: &DInt, mut out: OutPtr<DInt>) {
+    let index = index.value();
+    if index < 0 || index as u64 >= list.length as u64 {
+        let message = b"index out of bounds in `int_list_get`\n";
+        unsafe {
+            libc::write(libc::STDERR_FILENO, message.as_ptr() as *const libc::c_void, message.len());
+            libc::exit(101);
+        }
+    }
+
+    out.write(DInt::new(list.as_slice()[index as usize]));
+}
+
+/// Returns the item at the given index without checking that the index is in bounds
+///
+/// Only called by generated code that has already proven that the index is in bounds. This is
+/// not part of the prelude, so it cannot be called directly from a program.
+#[no_mangle]
+pub extern fn int_list_get_unchecked(list: &DIntList, index: &DInt, mut out: OutPtr<DInt>) {
+    let index = index.value() as usize;
+    out.write(DInt::new(unsafe { *list.as_slice().get_unchecked(index) }));
+}
+
+/// Returns a new list with the items of the list sorted in ascending order
+///
+/// Uses pattern-defeating quicksort, which falls back to heapsort to guarantee O(n log n) time in
+/// the worst case. Sorting never fails.
+#[no_mangle]
+pub extern fn int_list_sort(list: &DIntList, mut out: OutPtr<DIntList>) {
+    out.write(list.map_items(0, |items| items.sort_unstable()));
+}
+
+/// Returns a new list with the items of the list sorted in descending order
+///
+/// Uses the same algorithm as `int_list_sort`.
+#[no_mangle]
+pub extern fn int_list_sort_desc(list: &DIntList, mut out: OutPtr<DIntList>) {
+    out.write(list.map_items(0, |items| items.sort_unstable_by(|a, b| b.cmp(a))));
+}
@@ -0,0 +1,55 @@
+use crate::unique::Unique;
+use crate::outptr::OutPtr;
+use crate::runtime::alloc_no_ptr;
+use crate::dbool::DBool;
+use crate::dint::DInt;
+
+/// An optional dino integer
+///
+/// Either contains an integer or represents the absence of a value. The value is only meaningful
+/// if `is_some` is true.
+#[repr(C)]
+pub struct DOptionInt {
+    is_some: bool,
+    value: i64,
+}
+
+impl DOptionInt {
+    pub fn some(value: i64) -> Unique<Self> {
+        alloc_no_ptr(DOptionInt {is_some: true, value})
+    }
+
+    pub fn none() -> Unique<Self> {
+        alloc_no_ptr(DOptionInt {is_some: false, value: 0})
+    }
+}
+
+#[no_mangle]
+pub extern fn some_int(value: &DInt, mut out: OutPtr<DOptionInt>) {
+    out.write(DOptionInt::some(value.value()));
+}
+
+#[no_mangle]
+pub extern fn none_int(mut out: OutPtr<DOptionInt>) {
+    out.write(DOptionInt::none());
+}
+
+#[no_mangle]
+pub extern fn option_int__is_some(opt: &DOptionInt, mut out: OutPtr<DBool>) {
+    out.write(DBool::new(opt.is_some));
+}
+
+/// Returns the value in the option or exits the program with a non-zero exit code if there is no
+/// value
+#[no_mangle]
+pub extern fn option_int__unwrap_int(opt: &DOptionInt, mut out: OutPtr<DInt>) {
+    if !opt.is_some {
+        let message = b"called `unwrap_int` on an empty `option_int`\n";
+        unsafe {
+            libc::write(libc::STDERR_FILENO, message.as_ptr() as *const libc::c_void, message.len());
+            libc::exit(101);
+        }
+    }
+
+    out.write(DInt::new(opt.value));
+}
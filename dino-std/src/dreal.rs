@@ -1,11 +1,15 @@
-use core::ops::{Add, Sub};
+use core::ptr;
+use core::ops::{Add, Sub, Mul, Div};
 
+use libc::{c_char, c_int};
 use lazy_static::lazy_static;
 
 use crate::unique::Unique;
 use crate::outptr::OutPtr;
 use crate::runtime::{alloc_no_ptr, alloc_static};
 use crate::dunit::DUnit;
+use crate::dbool::DBool;
+use crate::dint::DInt;
 
 lazy_static! {
     static ref ZERO: Unique<DReal> = alloc_static(DReal(0.0));
@@ -60,6 +64,22 @@ impl<'a> Sub for &'a DReal {
     }
 }
 
+impl<'a> Mul for &'a DReal {
+    type Output = Unique<DReal>;
+
+    fn mul(self, other: Self) -> Unique<DReal> {
+        DReal::new(self.0 * other.0)
+    }
+}
+
+impl<'a> Div for &'a DReal {
+    type Output = Unique<DReal>;
+
+    fn div(self, other: Self) -> Unique<DReal> {
+        DReal::new(self.0 / other.0)
+    }
+}
+
 /// Creates a new DReal from an integer literal
 #[no_mangle]
 pub extern fn __dino__DReal_from_int_literal(value: i64, mut out: OutPtr<DReal>) {
@@ -82,11 +102,201 @@ pub extern fn sub_real(x: &DReal, y: &DReal, mut out: OutPtr<DReal>) {
     out.write(x - y);
 }
 
+#[no_mangle]
+pub extern fn mul_real(x: &DReal, y: &DReal, mut out: OutPtr<DReal>) {
+    out.write(x * y);
+}
+
+/// Divides the first real number by the second
+///
+/// Division follows IEEE 754 semantics and never exits the program. Dividing a non-zero number by
+/// zero produces positive or negative infinity (depending on the signs of both numbers) and
+/// dividing zero by zero produces NaN.
+#[no_mangle]
+pub extern fn div_real(x: &DReal, y: &DReal, mut out: OutPtr<DReal>) {
+    out.write(x / y);
+}
+
+/// Returns true if the real numbers are equal
+///
+/// Follows IEEE 754 semantics: `0.0` is equal to `-0.0` and NaN is not equal to anything,
+/// including itself. The same applies to the other comparisons.
+#[no_mangle]
+pub extern fn real_eq(x: &DReal, y: &DReal, mut out: OutPtr<DBool>) {
+    out.write(DBool::new(x.0 == y.0));
+}
+
+#[no_mangle]
+pub extern fn real_lt(x: &DReal, y: &DReal, mut out: OutPtr<DBool>) {
+    out.write(DBool::new(x.0 < y.0));
+}
+
+#[no_mangle]
+pub extern fn real_lte(x: &DReal, y: &DReal, mut out: OutPtr<DBool>) {
+    out.write(DBool::new(x.0 <= y.0));
+}
+
+#[no_mangle]
+pub extern fn real_gt(x: &DReal, y: &DReal, mut out: OutPtr<DBool>) {
+    out.write(DBool::new(x.0 > y.0));
+}
+
+#[no_mangle]
+pub extern fn real_gte(x: &DReal, y: &DReal, mut out: OutPtr<DBool>) {
+    out.write(DBool::new(x.0 >= y.0));
+}
+
+/// Returns the negation of the real number
+///
+/// The same as the `neg` method, which is used for the unary `-` operator.
+#[no_mangle]
+pub extern fn real_neg(x: &DReal, mut out: OutPtr<DReal>) {
+    out.write(DReal::new(-x.0));
+}
+
+#[no_mangle]
+pub extern fn real__neg(x: &DReal, mut out: OutPtr<DReal>) {
+    out.write(DReal::new(-x.0));
+}
+
+/// Returns the absolute value of the real number
+///
+/// Only the sign bit is cleared, so `-0.0` becomes `0.0` and NaN stays NaN.
+#[no_mangle]
+pub extern fn real_abs(x: &DReal, mut out: OutPtr<DReal>) {
+    // `f64::abs` is not available without std
+    out.write(DReal::new(f64::from_bits(x.0.to_bits() & !(1 << 63))));
+}
+
+/// Returns -1 if the real number is negative, 1 if it is positive, and 0 otherwise
+///
+/// Both `0.0` and `-0.0` have a sign of 0. NaN is neither negative nor positive, so it also has
+/// a sign of 0.
+#[no_mangle]
+pub extern fn real_sign(x: &DReal, mut out: OutPtr<DInt>) {
+    let sign = if x.0 > 0.0 {
+        1
+    } else if x.0 < 0.0 {
+        -1
+    } else {
+        0
+    };
+    out.write(DInt::new(sign));
+}
+
+/// Returns the square root of the real number
+///
+/// Follows IEEE 754 like the C `sqrt` function: the square root of a negative number is NaN and
+/// the square root of `-0.0` is `-0.0`.
+#[no_mangle]
+pub extern fn real_sqrt(x: &DReal, mut out: OutPtr<DReal>) {
+    out.write(DReal::new(unsafe { super::sqrt(x.0) }));
+}
+
+/// Returns the first real number raised to the power of the second
+///
+/// Follows the C `pow` function: a negative base with a non-integer exponent produces NaN and
+/// raising zero to a negative power produces infinity. Anything raised to the power of zero is
+/// one, even NaN.
+#[no_mangle]
+pub extern fn real_pow(x: &DReal, y: &DReal, mut out: OutPtr<DReal>) {
+    out.write(DReal::new(unsafe { super::pow(x.0, y.0) }));
+}
+
+/// Returns the largest integral value that is not greater than the real number
+///
+/// The result is still a real number. Infinity and NaN are returned unchanged.
+#[no_mangle]
+pub extern fn real_floor(x: &DReal, mut out: OutPtr<DReal>) {
+    out.write(DReal::new(unsafe { super::floor(x.0) }));
+}
+
+/// Returns the smallest integral value that is not less than the real number
+///
+/// The result is still a real number. Infinity and NaN are returned unchanged.
+#[no_mangle]
+pub extern fn real_ceil(x: &DReal, mut out: OutPtr<DReal>) {
+    out.write(DReal::new(unsafe { super::ceil(x.0) }));
+}
+
+/// Returns the sine of the real number, which is an angle in radians
+///
+/// The sine of infinity is NaN.
+#[no_mangle]
+pub extern fn real_sin(x: &DReal, mut out: OutPtr<DReal>) {
+    out.write(DReal::new(unsafe { super::sin(x.0) }));
+}
+
+/// Returns the cosine of the real number, which is an angle in radians
+///
+/// The cosine of infinity is NaN.
+#[no_mangle]
+pub extern fn real_cos(x: &DReal, mut out: OutPtr<DReal>) {
+    out.write(DReal::new(unsafe { super::cos(x.0) }));
+}
+
+/// Returns the natural logarithm of the real number
+///
+/// Follows the C `log` function: the logarithm of zero is negative infinity and the logarithm of
+/// a negative number is NaN.
+#[no_mangle]
+pub extern fn real_ln(x: &DReal, mut out: OutPtr<DReal>) {
+    out.write(DReal::new(unsafe { super::log(x.0) }));
+}
+
+/// The size of a buffer large enough for any real number formatted by `format_real`, including
+/// the null terminator
+pub(crate) const REAL_BUF_LEN: usize = 32;
+
+/// Writes the shortest representation of `value` that parses back to exactly the same value
+/// into `buf` as a null-terminated string
+///
+/// The value is formatted like `%g`, but with as many significant digits as necessary (up to 17)
+/// for the output to round-trip. Starting at the default `%g` precision (6) means that values
+/// which already round-trip are printed the same way `%g` would print them. 17 significant
+/// digits are always enough to round-trip any finite `f64`. NaN never compares equal to itself,
+/// so it is always printed with the maximum precision (which is still just `nan`).
+pub(crate) fn format_real(value: f64, buf: &mut [u8; REAL_BUF_LEN]) {
+    for precision in 6..=17 {
+        unsafe {
+            libc::snprintf(buf.as_mut_ptr() as *mut c_char, REAL_BUF_LEN,
+                b"%.*g\0" as *const u8 as *const c_char, precision as c_int, value);
+
+            if libc::strtod(buf.as_ptr() as *const c_char, ptr::null_mut()) == value {
+                break;
+            }
+        }
+    }
+}
+
+/// Prints the real number followed by a newline
+///
+/// The number is printed with enough digits that parsing the output produces exactly the same
+/// value (see `format_real`). The output is always the same for the same value.
+#[no_mangle]
+pub extern fn println_real(x: &DReal, mut out: OutPtr<DUnit>) {
+    let mut buf = [0; REAL_BUF_LEN];
+    format_real(x.0, &mut buf);
+
+    unsafe {
+        super::printf(b"%s\n\0" as *const u8, buf.as_ptr());
+    }
+
+    out.write(DUnit::new());
+}
+
+/// Prints the real number without a trailing newline
+///
+/// The number is formatted the same way as in `println_real`.
 #[no_mangle]
 pub extern fn print_real(x: &DReal, mut out: OutPtr<DUnit>) {
+    let mut buf = [0; REAL_BUF_LEN];
+    format_real(x.0, &mut buf);
+
     unsafe {
-        super::printf(b"%g\n\0" as *const u8, x.0);
+        super::printf(b"%s\0" as *const u8, buf.as_ptr());
     }
 
     out.write(DUnit::new());
 }
+
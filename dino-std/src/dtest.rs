@@ -0,0 +1,78 @@
+//! Support for the test harness generated for functions marked with `#[test]`
+//!
+//! Each test is run in a separate (forked) process. That way, a test that traps or aborts only
+//! takes down its own process and can be reported as a failure while the rest of the tests
+//! continue to run.
+
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of tests that have passed so far
+static PASSED: AtomicUsize = AtomicUsize::new(0);
+/// The number of tests that have failed so far
+static FAILED: AtomicUsize = AtomicUsize::new(0);
+
+/// Starts running the test with the given (null-terminated) name
+///
+/// Returns true in the process that should run the test. That process must call
+/// `__dino__test_pass` if the test completes successfully. Returns false in the harness process
+/// once the test has finished running and its result has been recorded.
+#[no_mangle]
+pub extern fn __dino__test_start(name: *const u8) -> bool {
+    unsafe {
+        // Any buffered output must be flushed before forking, otherwise it would be written out
+        // by both processes
+        libc::fflush(ptr::null_mut());
+
+        let pid = libc::fork();
+        if pid == 0 {
+            return true;
+        }
+
+        let passed = if pid < 0 {
+            // Could not create a process to run the test
+            false
+        } else {
+            let mut status = 0;
+            libc::waitpid(pid, &mut status, 0) == pid
+                && libc::WIFEXITED(status)
+                && libc::WEXITSTATUS(status) == 0
+        };
+
+        if passed {
+            PASSED.fetch_add(1, Ordering::SeqCst);
+            super::printf(b"test %s ... ok\n\0" as *const u8, name);
+        } else {
+            FAILED.fetch_add(1, Ordering::SeqCst);
+            super::printf(b"test %s ... FAILED\n\0" as *const u8, name);
+        }
+    }
+
+    false
+}
+
+/// Ends the process running the current test, marking the test as passed
+#[no_mangle]
+pub extern fn __dino__test_pass() {
+    // exit() flushes any buffered output before the process ends
+    unsafe { libc::exit(0); }
+}
+
+/// Prints the number of tests that passed and failed
+///
+/// Exits the program with a non-zero exit code if any test failed.
+#[no_mangle]
+pub extern fn __dino__test_summary() {
+    let passed = PASSED.load(Ordering::SeqCst);
+    let failed = FAILED.load(Ordering::SeqCst);
+    let result: &[u8] = if failed == 0 { b"ok\0" } else { b"FAILED\0" };
+
+    unsafe {
+        super::printf(b"\ntest result: %s. %zu passed; %zu failed\n\0" as *const u8,
+            result.as_ptr(), passed, failed);
+
+        if failed > 0 {
+            libc::exit(1);
+        }
+    }
+}
@@ -28,11 +28,23 @@ pub extern fn unit__eq(_x: &DUnit, _y: &DUnit, mut out: OutPtr<DBool>) {
     out.write(DBool::new(true));
 }
 
+/// Prints `()` followed by a newline
 #[no_mangle]
-pub extern fn print_unit(_x: &DUnit, mut out: OutPtr<DUnit>) {
+pub extern fn println_unit(_x: &DUnit, mut out: OutPtr<DUnit>) {
     unsafe {
         super::printf(b"()\n\0" as *const u8);
     }
 
     out.write(DUnit::new());
 }
+
+/// Prints `()` without a trailing newline
+#[no_mangle]
+pub extern fn print_unit(_x: &DUnit, mut out: OutPtr<DUnit>) {
+    unsafe {
+        super::printf(b"()\0" as *const u8);
+    }
+
+    out.write(DUnit::new());
+}
+
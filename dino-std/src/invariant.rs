@@ -0,0 +1,19 @@
+//! Support for checking the invariants declared on structs using `requires`
+
+/// Reports that a value of the struct with the given (null-terminated) name was constructed
+/// without satisfying its invariant and then exits the program with a non-zero exit code
+#[no_mangle]
+pub extern fn __dino__invariant_violated(type_name: *const u8) -> ! {
+    unsafe {
+        let type_name_len = libc::strlen(type_name as *const libc::c_char);
+        write_stderr(b"invariant of `");
+        libc::write(libc::STDERR_FILENO, type_name as *const libc::c_void, type_name_len);
+        write_stderr(b"` violated\n");
+
+        libc::exit(101);
+    }
+}
+
+unsafe fn write_stderr(data: &[u8]) {
+    libc::write(libc::STDERR_FILENO, data.as_ptr() as *const libc::c_void, data.len());
+}
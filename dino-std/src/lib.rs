@@ -3,6 +3,7 @@
 mod unique;
 mod runtime;
 mod outptr;
+mod invariant;
 
 mod dunit;
 mod dbool;
@@ -10,6 +11,9 @@ mod dint;
 mod dreal;
 mod dcomplex;
 mod dbstr;
+mod doption;
+mod dintlist;
+mod dtest;
 
 pub use dunit::*;
 pub use dbool::*;
@@ -17,6 +21,9 @@ pub use dint::*;
 pub use dreal::*;
 pub use dcomplex::*;
 pub use dbstr::*;
+pub use doption::*;
+pub use dintlist::*;
+pub use dtest::*;
 
 // Needed to define #[panic_handler]
 #[allow(unused_imports)]
@@ -31,4 +38,13 @@ pub extern fn rust_eh_personality() {}
 extern {
     pub static mut stdin: *mut libc::FILE;
     pub fn printf(format: *const u8, ...) -> i32;
+
+    // From math.h, linked in with `-lm`
+    pub fn sqrt(x: f64) -> f64;
+    pub fn pow(x: f64, y: f64) -> f64;
+    pub fn floor(x: f64) -> f64;
+    pub fn ceil(x: f64) -> f64;
+    pub fn sin(x: f64) -> f64;
+    pub fn cos(x: f64) -> f64;
+    pub fn log(x: f64) -> f64;
 }
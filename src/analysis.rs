@@ -0,0 +1,5 @@
+//! Analyses over the typed IR that are shared by lints and external tooling.
+
+mod call_graph;
+
+pub use call_graph::*;
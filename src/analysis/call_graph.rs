@@ -0,0 +1,291 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::{self, IdentPath, visit::{self, Visitor}};
+
+/// A mapping from each function in a program to the functions that it calls
+///
+/// Functions are identified by the path used to call them: `name` for free functions,
+/// `Type::name` for methods, and `module::name` for functions in inline modules. Only functions
+/// defined in the program are part of the graph. Calls to extern functions are ignored since they
+/// cannot call back into the program.
+#[derive(Debug, Default, Clone)]
+pub struct CallGraph<'a> {
+    calls: HashMap<IdentPath<'a>, HashSet<IdentPath<'a>>>,
+}
+
+impl<'a> CallGraph<'a> {
+    /// Builds the call graph for the given program
+    pub fn new(prog: &ir::Program<'a>) -> Self {
+        let ir::Program {top_level_module} = prog;
+        // Static initializers are constants, so they never call any functions
+        let ir::Module {types, functions, module_functions, statics: _} = top_level_module;
+
+        // Collect every call made in each function body, whether or not it calls a function
+        // defined in the program
+        let mut calls = HashMap::new();
+        for func in functions {
+            calls.insert(IdentPath::from(func.name), calls_in_block(&func.body));
+        }
+        for ir::Struct {name: type_name, methods, ..} in types {
+            for (&method_name, method) in methods {
+                let path = IdentPath::from(vec![*type_name, method_name]);
+                calls.insert(path, calls_in_block(&method.body));
+            }
+        }
+        for (module_name, func) in module_functions {
+            let path = IdentPath::from(vec![*module_name, func.name]);
+            calls.insert(path, calls_in_block(&func.body));
+        }
+
+        // Only keep the calls to functions that are part of the graph
+        let defined: HashSet<_> = calls.keys().cloned().collect();
+        for callees in calls.values_mut() {
+            callees.retain(|callee| defined.contains(callee));
+        }
+
+        Self {calls}
+    }
+
+    /// Iterates over all of the functions in the graph, in no particular order
+    pub fn functions(&self) -> impl Iterator<Item = &IdentPath<'a>> {
+        self.calls.keys()
+    }
+
+    /// Returns the functions called directly by the given function, or None if the function is
+    /// not part of the graph
+    pub fn callees(&self, func: &IdentPath<'a>) -> Option<&HashSet<IdentPath<'a>>> {
+        self.calls.get(func)
+    }
+
+    /// Returns every function that may be called (directly or indirectly) when the given function
+    /// is called, including the function itself
+    ///
+    /// Returns an empty set if the function is not part of the graph.
+    pub fn reachable_from(&self, root: &IdentPath<'a>) -> HashSet<IdentPath<'a>> {
+        let mut reachable = HashSet::new();
+        if !self.calls.contains_key(root) {
+            return reachable;
+        }
+
+        let mut pending = vec![root];
+        reachable.insert(root.clone());
+        while let Some(func) = pending.pop() {
+            for callee in &self.calls[func] {
+                if reachable.insert(callee.clone()) {
+                    pending.push(callee);
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Returns every function that may be called when the program runs, starting from `main`
+    pub fn reachable_from_main(&self) -> HashSet<IdentPath<'a>> {
+        self.reachable_from(&IdentPath::from("main"))
+    }
+
+    /// Returns each group of functions that call each other recursively
+    ///
+    /// Each group is a strongly connected component of the graph. A function that only calls
+    /// itself forms a group on its own. Functions that are not recursive are not part of any
+    /// group. The groups (and the functions within each group) are in no particular order.
+    pub fn recursive_cycles(&self) -> Vec<Vec<IdentPath<'a>>> {
+        let mut finder = SccFinder {
+            graph: self,
+            next_index: 0,
+            indexes: HashMap::new(),
+            lowlinks: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            components: Vec::new(),
+        };
+
+        for func in self.calls.keys() {
+            if !finder.indexes.contains_key(func) {
+                finder.visit(func);
+            }
+        }
+
+        finder.components.into_iter().filter(|component| match &component[..] {
+            [func] => self.calls[*func].contains(*func),
+            _ => true,
+        }).map(|component| component.into_iter().cloned().collect()).collect()
+    }
+}
+
+/// Finds the strongly connected components of a call graph using Tarjan's algorithm
+struct SccFinder<'g, 'a> {
+    graph: &'g CallGraph<'a>,
+    /// The next index to assign to a function when it is first visited
+    next_index: usize,
+    /// The order in which each function was visited
+    indexes: HashMap<&'g IdentPath<'a>, usize>,
+    /// The smallest index of any function known to be reachable from each function, including
+    /// the function itself
+    lowlinks: HashMap<&'g IdentPath<'a>, usize>,
+    /// The functions visited so far that have not been assigned to a component yet
+    stack: Vec<&'g IdentPath<'a>>,
+    on_stack: HashSet<&'g IdentPath<'a>>,
+    components: Vec<Vec<&'g IdentPath<'a>>>,
+}
+
+impl<'g, 'a> SccFinder<'g, 'a> {
+    fn visit(&mut self, func: &'g IdentPath<'a>) {
+        let index = self.next_index;
+        self.next_index += 1;
+        self.indexes.insert(func, index);
+        self.lowlinks.insert(func, index);
+        self.stack.push(func);
+        self.on_stack.insert(func);
+
+        let graph = self.graph;
+        for callee in &graph.calls[func] {
+            if !self.indexes.contains_key(callee) {
+                self.visit(callee);
+                let lowlink = self.lowlinks[func].min(self.lowlinks[callee]);
+                self.lowlinks.insert(func, lowlink);
+
+            } else if self.on_stack.contains(callee) {
+                let lowlink = self.lowlinks[func].min(self.indexes[callee]);
+                self.lowlinks.insert(func, lowlink);
+            }
+        }
+
+        // This function is the root of a component, so everything above it on the stack is part
+        // of that component
+        if self.lowlinks[func] == index {
+            let mut component = Vec::new();
+            loop {
+                let member = self.stack.pop()
+                    .expect("bug: function was not on the stack when its component was completed");
+                self.on_stack.remove(member);
+                component.push(member);
+
+                if member == func {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+fn calls_in_block<'a>(block: &ir::Block<'a>) -> HashSet<IdentPath<'a>> {
+    let mut collector = CallCollector {calls: HashSet::new()};
+    collector.visit_block(block);
+    collector.calls
+}
+
+/// Collects the path of every function called
+struct CallCollector<'a> {
+    calls: HashSet<IdentPath<'a>>,
+}
+
+impl<'a> Visitor<'a> for CallCollector<'a> {
+    fn visit_expr(&mut self, expr: &ir::Expr<'a>) {
+        if let ir::Expr::Call(ir::CallExpr {func_name, args: _}, _) = expr {
+            self.calls.insert(func_name.clone());
+        }
+
+        visit::walk_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tycheck::check_source;
+
+    fn path(name: &str) -> IdentPath {
+        IdentPath::from(name.split("::").collect::<Vec<_>>())
+    }
+
+    fn graph<'a>(edges: &[(&'a str, &[&'a str])]) -> CallGraph<'a> {
+        let calls = edges.iter().map(|&(func, callees)| {
+            (path(func), callees.iter().map(|&callee| path(callee)).collect())
+        }).collect();
+
+        CallGraph {calls}
+    }
+
+    #[test]
+    fn from_program() {
+        let source = "
+            struct Point { x: int, y: int }
+            impl Point {
+                fn sum(self) -> int { self.x + self.y }
+            }
+
+            mod math {
+                fn double(x: int) -> int { x * 2 }
+                fn quadruple(x: int) -> int { double(double(x)) }
+            }
+
+            fn unused() {}
+
+            fn main() {
+                print_int(math::quadruple(Point {x: 1, y: 2}.sum()));
+            }
+        ";
+        let program_ir = check_source(source).unwrap().program;
+        let graph = CallGraph::new(&program_ir);
+
+        // Method calls and calls within an inline module are recorded with their full paths, and
+        // calls to extern functions (e.g. `print_int` and `int::add`) are left out
+        let main_callees: HashSet<_> = ["Point::sum", "math::quadruple"].iter()
+            .map(|&name| path(name))
+            .collect();
+        assert_eq!(graph.callees(&path("main")), Some(&main_callees));
+        let quadruple_callees: HashSet<_> = vec![path("math::double")].into_iter().collect();
+        assert_eq!(graph.callees(&path("math::quadruple")), Some(&quadruple_callees));
+        assert_eq!(graph.callees(&path("Point::sum")), Some(&HashSet::new()));
+
+        let reachable = graph.reachable_from_main();
+        assert_eq!(reachable.len(), 4);
+        assert!(!reachable.contains(&path("unused")));
+    }
+
+    #[test]
+    fn reachability() {
+        let graph = graph(&[
+            ("main", &["foo", "Point::new"]),
+            ("foo", &["bar"]),
+            ("bar", &[]),
+            ("Point::new", &[]),
+            ("unused", &["foo"]),
+        ]);
+
+        let reachable = graph.reachable_from_main();
+        let expected: HashSet<_> = ["main", "foo", "bar", "Point::new"].iter()
+            .map(|&name| path(name))
+            .collect();
+        assert_eq!(reachable, expected);
+
+        assert!(graph.reachable_from(&path("missing")).is_empty());
+    }
+
+    #[test]
+    fn recursion_cycles() {
+        let graph = graph(&[
+            ("main", &["is_even", "fact", "helper"]),
+            ("is_even", &["is_odd"]),
+            ("is_odd", &["is_even"]),
+            ("fact", &["fact"]),
+            ("helper", &[]),
+        ]);
+
+        let mut cycles: Vec<Vec<String>> = graph.recursive_cycles().into_iter().map(|cycle| {
+            let mut cycle: Vec<_> = cycle.iter().map(|func| func.to_string()).collect();
+            cycle.sort();
+            cycle
+        }).collect();
+        cycles.sort();
+
+        assert_eq!(cycles, vec![
+            vec!["fact".to_string()],
+            vec!["is_even".to_string(), "is_odd".to_string()],
+        ]);
+    }
+}
@@ -29,6 +29,9 @@ pub struct ImportPath {
     pub path: Vec<Ident>,
     /// The items selected from the path
     pub selection: ImportSelection,
+    /// True if the selected items are re-exported (`pub use ...;`), making them visible to every
+    /// module that imports this one
+    pub is_pub: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -103,6 +106,7 @@ pub struct Block {
 pub enum Stmt {
     Cond(Cond),
     WhileLoop(WhileLoop),
+    ForLoop(ForLoop),
     VarDecl(VarDecl),
     Expr(Expr),
 }
@@ -115,6 +119,21 @@ pub struct WhileLoop {
     pub body: Block,
 }
 
+/// A loop in the form `for <ident> in <start>..<end> { ... }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForLoop {
+    /// The span of the `for` keyword
+    pub for_span: Span,
+    /// The loop variable, only in scope within the body of the loop
+    pub var: Ident,
+    /// The first value of the loop variable
+    pub start: Expr,
+    /// The value that the loop variable stops before (exclusive)
+    pub end: Expr,
+    /// The body of the loop, executed once for each value of the loop variable
+    pub body: Block,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct VarDecl {
     /// The identifier to assign a value to
@@ -128,6 +147,7 @@ pub struct VarDecl {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Assign(Box<Assign>),
+    CompoundAssign(Box<CompoundAssign>),
     Range(Box<Range>),
     BoolOp(Box<Binary<BoolOp>>),
     CompareOp(Box<Binary<CompareOp>>),
@@ -161,6 +181,7 @@ impl Expr {
         use Expr::*;
         match self {
             Assign(assign) => assign.span(),
+            CompoundAssign(assign) => assign.span(),
             Range(range) => range.span(),
             BoolOp(bin) => bin.span(),
             CompareOp(bin) => bin.span(),
@@ -387,6 +408,26 @@ impl Assign {
     }
 }
 
+/// A compound assignment expression in the form `<lvalue> <op>= <value>`, e.g. `x += 1`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompoundAssign {
+    /// The left-hand expression to update the value of
+    pub lhs: Expr,
+    /// The operator applied to the current value of the left-hand side and the right-hand side.
+    /// The span of the operator covers the entire `<op>=` token.
+    pub op: NumericOp,
+    /// The expression for the right-hand side of the operator
+    pub rhs: Expr,
+}
+
+impl CompoundAssign {
+    pub fn span(&self) -> Span {
+        let Self {lhs, op: _, rhs} = self;
+
+        lhs.span().to(rhs.span())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct CastAs {
     pub expr: Expr,
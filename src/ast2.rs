@@ -3,18 +3,28 @@
 //! This is the closest representation to the actual syntax.
 
 use std::fmt;
+use std::path::Path;
 
 mod parser;
 
 pub use parser::Error as ParseError;
 
+/// The name of the associated function generated to check the invariant of a struct
+pub const INVARIANT_METHOD_NAME: &str = "__invariant";
+
+/// The name of the built-in function that prints a value of any type for debugging
+///
+/// This is only built-in if no function with this name has been declared.
+pub const DEBUG_PRINT_FUNC_NAME: &str = "debug_print";
+
 #[derive(Debug, PartialEq)]
 pub struct Program<'a> {
     pub top_level_module: Module<'a>,
 }
 
 impl<'a> Program<'a> {
-    pub fn parse(input: &'a str) -> Result<Self, ParseError> {
+    /// Parses the given program, returning every syntax error found if the program is invalid
+    pub fn parse(input: &'a str) -> Result<Self, Vec<ParseError>> {
         Ok(Program {
             top_level_module: parser::parse_module(input)?,
         })
@@ -26,11 +36,36 @@ pub struct Module<'a> {
     pub decls: Vec<Decl<'a>>,
 }
 
+impl<'a> Module<'a> {
+    /// Records the given path as the file that every function in this module was declared in
+    pub fn set_source_path(&mut self, path: &'a Path) {
+        let Module {decls} = self;
+
+        for decl in decls {
+            let functions = match decl {
+                Decl::Import(_) |
+                Decl::Static(_) => continue,
+                Decl::Module(ModuleDecl {name: _, functions}) => functions.iter_mut().collect(),
+                Decl::Struct(Struct {invariant, ..}) => invariant.iter_mut().collect(),
+                Decl::Impl(Impl {self_ty: _, methods, consts: _}) => methods.iter_mut().collect(),
+                Decl::Function(func) => vec![func],
+            };
+
+            for func in functions {
+                func.source_path = Some(path);
+            }
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Decl<'a> {
+    Import(ImportPath<'a>),
+    Module(ModuleDecl<'a>),
     Struct(Struct<'a>),
     Impl(Impl<'a>),
     Function(Function<'a>),
+    Static(Static<'a>),
 }
 
 #[derive(Debug, PartialEq)]
@@ -39,12 +74,84 @@ pub struct Struct<'a> {
     pub name: Ident<'a>,
     /// The fields of the struct
     pub fields: Vec<StructField<'a>>,
+    /// The invariant declared with `requires <expr>` (if any)
+    ///
+    /// The invariant is represented as an associated function of the struct (named
+    /// `INVARIANT_METHOD_NAME`) that takes each field as a parameter and returns `bool`. That way,
+    /// the invariant can only refer to the fields of the struct and it can be checked whenever a
+    /// value of the struct is constructed.
+    pub invariant: Option<Function<'a>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StructField<'a> {
     pub name: Ident<'a>,
     pub ty: Ty<'a>,
+    /// The value used for this field when a struct literal does not specify one (if any)
+    pub default: Option<Expr<'a>>,
+}
+
+/// An import of items from another module of the current package:
+/// `use package::path::to::module::*;` or `use package::path::to::module::{name as alias, ...};`
+///
+/// The path is absolute and is always relative to the directory of the program being compiled.
+/// For example, `use package::shapes::circle::*;` imports `shapes/circle.dino`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportPath<'a> {
+    /// The names of each directory leading to the module, followed by the name of the module
+    pub path: Vec<Ident<'a>>,
+    /// The items selected from the module
+    pub selection: ImportSelection<'a>,
+    /// True if the selected items are re-exported (`pub use ...;`), making them visible to every
+    /// module that imports this one
+    pub is_pub: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportSelection<'a> {
+    /// A specific list of names being imported
+    Names(Vec<ImportName<'a>>),
+    /// A wildcard import (all items)
+    All,
+}
+
+/// A single item imported by name: `name` or `name as alias`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportName<'a> {
+    /// The name of the item in the imported module
+    pub name: Ident<'a>,
+    /// The name that the item is bound to in the importing module instead of its own name (if any)
+    pub alias: Option<Ident<'a>>,
+}
+
+impl<'a> ImportName<'a> {
+    /// Returns the name that the item is bound to in the importing module
+    pub fn local_name(&self) -> Ident<'a> {
+        self.alias.unwrap_or(self.name)
+    }
+}
+
+/// A module declared inline within a file: `mod name { ... }`
+///
+/// The functions in the module are called from outside of it as `name::function(...)`. Only
+/// functions may be declared in an inline module for now.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleDecl<'a> {
+    /// The name of the module
+    pub name: Ident<'a>,
+    /// The functions declared in the module
+    pub functions: Vec<Function<'a>>,
+}
+
+/// A global variable declared with `static mut NAME: ty = value;`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Static<'a> {
+    /// The name of the global variable
+    pub name: Ident<'a>,
+    /// The declared type of the global variable
+    pub ty: Ty<'a>,
+    /// The initial value of the global variable. Must be a constant.
+    pub init: Expr<'a>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -53,6 +160,19 @@ pub struct Impl<'a> {
     pub self_ty: Ty<'a>,
     /// The method decls of this impl block
     pub methods: Vec<Function<'a>>,
+    /// The associated constants declared in this impl block
+    pub consts: Vec<AssocConst<'a>>,
+}
+
+/// An associated constant declared in an impl block with `const NAME: ty = value;`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssocConst<'a> {
+    /// The name of the constant
+    pub name: Ident<'a>,
+    /// The declared type of the constant
+    pub ty: Ty<'a>,
+    /// The value of the constant. Must be a constant and is inlined wherever it is used.
+    pub value: Expr<'a>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -61,10 +181,22 @@ pub struct Function<'a> {
     pub name: Ident<'a>,
     /// The type signature of the function
     pub sig: FuncSig<'a>,
-    /// The body of the function. Not used if `is_extern` is true.
+    /// The body of the function. Not used if `is_extern` is true or `c_body` is set.
     pub body: Block<'a>,
     /// True if the function is meant to be linked in externally
     pub is_extern: bool,
+    /// Raw C code to use as the body of the function, from `extern "C" fn ... = "...";`
+    ///
+    /// This code is emitted verbatim and is never checked. Only the signature of the function is
+    /// type checked.
+    pub c_body: Option<String>,
+    /// True if the function is marked with the `#[test]` attribute
+    pub is_test: bool,
+    /// True if the function is marked with the `#[pure]` attribute, which asserts that calling it
+    /// has no side effects
+    pub is_pure: bool,
+    /// The path of the file that the function was declared in (if known)
+    pub source_path: Option<&'a Path>,
 }
 
 /// The type signature of a free function
@@ -83,13 +215,16 @@ pub struct FuncParam<'a> {
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct Block<'a> {
     pub stmts: Vec<Stmt<'a>>,
+    /// The line (starting at 1) of the start of each statement in `stmts`, followed by the line
+    /// of `ret` (if any)
+    pub stmt_lines: Vec<usize>,
     /// The final statement of the block, used as the return value of the block
     pub ret: Option<Expr<'a>>,
 }
 
 impl<'a> Block<'a> {
     pub fn is_empty(&self) -> bool {
-        let Block {stmts, ret} = self;
+        let Block {stmts, stmt_lines: _, ret} = self;
         stmts.is_empty() && ret.is_none()
     }
 }
@@ -98,6 +233,12 @@ impl<'a> Block<'a> {
 pub enum Stmt<'a> {
     Cond(Cond<'a>),
     WhileLoop(WhileLoop<'a>),
+    /// Exits the innermost loop
+    Break,
+    /// Skips to the next iteration of the innermost loop
+    Continue,
+    /// A nested block, with its own scope for the variables declared within it
+    Block(Block<'a>),
     VarDecl(VarDecl<'a>),
     Expr(Expr<'a>),
 }
@@ -129,6 +270,8 @@ pub enum Expr<'a> {
     Call(CallExpr<'a>),
     Return(Option<Box<Expr<'a>>>),
     StructLiteral(StructLiteral<'a>),
+    OffsetOf(OffsetOf<'a>),
+    AssocConst(AssocConstPath<'a>),
     BStrLiteral(Vec<u8>),
     IntegerLiteral(IntegerLiteral<'a>),
     RealLiteral(f64),
@@ -166,6 +309,26 @@ pub struct MethodCall<'a> {
     pub args: Vec<Expr<'a>>,
 }
 
+/// The compile-time builtin `offset_of(<ty>, <ident>)`
+///
+/// Evaluates to the offset in bytes of the field from the start of the struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OffsetOf<'a> {
+    /// The struct type containing the field
+    pub self_ty: Ty<'a>,
+    /// The field whose offset is being computed
+    pub field: Ident<'a>,
+}
+
+/// A reference to an associated constant in the form `<ty> :: <ident>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssocConstPath<'a> {
+    /// The type that the constant is associated with
+    pub self_ty: Ty<'a>,
+    /// The name of the constant
+    pub name: Ident<'a>,
+}
+
 /// A field access in the form `<expr> . <ident>`
 #[derive(Debug, Clone, PartialEq)]
 pub struct FieldAccess<'a> {
@@ -189,7 +352,19 @@ pub struct Cond<'a> {
 #[derive(Debug, Clone, PartialEq)]
 pub struct CallExpr<'a> {
     pub func_name: IdentPath<'a>,
+    /// The positional arguments, which always come before any named arguments
     pub args: Vec<Expr<'a>>,
+    /// The arguments passed by parameter name
+    pub named_args: Vec<NamedArg<'a>>,
+}
+
+/// An argument passed by parameter name in the form `<ident> : <expr>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedArg<'a> {
+    /// The name of the parameter this argument is for
+    pub name: Ident<'a>,
+    /// The expression being passed to the parameter
+    pub value: Expr<'a>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -226,6 +401,8 @@ pub enum Ty<'a> {
     Unit,
     SelfType,
     Named(Ident<'a>),
+    /// The never type `!`, used as the return type of functions that never return
+    Never,
 }
 
 impl<'a> From<NamedTy<'a>> for Ty<'a> {
@@ -237,7 +414,7 @@ impl<'a> From<NamedTy<'a>> for Ty<'a> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct IdentPath<'a> {
     /// There is guaranteed to be at least one component
     pub components: Vec<Ident<'a>>,
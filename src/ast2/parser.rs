@@ -1,18 +1,20 @@
 use std::iter::once;
+use std::cell::RefCell;
 
 use snafu::Snafu;
 use nom::{
-    InputLength,
-    error::VerboseError,
+    error::{VerboseError, VerboseErrorKind},
     branch::alt,
     number::complete::double,
     character::complete::{char, digit1, one_of},
-    combinator::{all_consuming, map, map_res, recognize, opt, not},
-    bytes::complete::{tag, take_while1, take_while, take_till, take_till1, escaped_transform},
+    combinator::{map, map_res, recognize, opt, not, peek, value},
+    bytes::complete::{tag, take_while1, take_while, take_till, take_till1, take_while_m_n},
     sequence::{tuple, pair, delimited, terminated, preceded},
-    multi::{many0, fold_many0, separated_list, separated_nonempty_list},
+    multi::{many0, many1, fold_many0, separated_list, separated_nonempty_list},
 };
 
+use crate::span::Span;
+
 use super::*;
 
 type Input<'a> = &'a str;
@@ -21,50 +23,314 @@ type IResult<'a, O> = nom::IResult<Input<'a>, O, VerboseError<Input<'a>>>;
 /// Represents errors that can occur during parsing
 #[derive(Debug, Snafu)]
 pub enum Error {
-    //TODO: Figure out what different error cases we can return here
+    #[snafu(display("{}:{}: {}\n{}\n{}", line, column, message, source_line,
+        underline(source_line, *column, span.end - span.start)))]
+    InvalidSyntax {
+        /// The byte offsets of the input that could not be parsed
+        span: Span,
+        /// The line of the start of the span (starting at 1)
+        line: usize,
+        /// The column of the start of the span (starting at 1)
+        column: usize,
+        /// A description of what was expected and what was found instead
+        message: String,
+        /// The full line of the input containing the start of the span
+        source_line: String,
+    },
+}
+
+/// Returns the line and column (both starting at 1) of the given byte offset into the input
+pub fn line_col(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+
+    (line, column)
 }
 
-impl From<nom::Err<VerboseError<Input<'_>>>> for Error {
-    fn from(err: nom::Err<VerboseError<Input>>) -> Self {
-        panic!("Error: {:?}", err);
+/// Returns a line of carets (`^`) underneath the given number of bytes of the line, starting at
+/// the given column
+///
+/// At least one caret is always produced so that errors at the end of the input are still
+/// pointed at.
+fn underline(source_line: &str, column: usize, len: usize) -> String {
+    // Keep any tabs so that the carets line up the same way the line does
+    let mut underline: String = source_line.chars().take(column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    let underlined = source_line.chars().skip(column - 1)
+        .scan(0, |bytes, c| {
+            *bytes += c.len_utf8();
+            Some(*bytes)
+        })
+        .take_while(|&bytes| bytes <= len)
+        .count();
+    for _ in 0..underlined.max(1) {
+        underline.push('^');
     }
+
+    underline
 }
 
-/// Attempts to parse the given input module
-pub fn parse_module(input: &str) -> Result<Module, Error> {
-    let (inp, module) = match all_consuming(module)(input) {
-        Ok((inp, module)) => (inp, module),
-        Err(nom::Err::Error(err)) | Err(nom::Err::Failure(err)) => {
-            //TODO: Convert to Error type above
-            panic!("Error: {}", nom::error::convert_error(input, err));
-        },
-        // This should not be reachable because we are using the 'complete' versions of all parsers
-        Err(nom::Err::Incomplete(_)) => unreachable!(),
-    };
+impl Error {
+    fn new(input: &str, err: &RawError) -> Self {
+        let &RawError {remaining_len, ref kind} = err;
+        let start = input.len() - remaining_len;
+        let rest = &input[start..];
+
+        // Underline the identifier or the single character that could not be parsed
+        let found_len = match rest.chars().next() {
+            None => 0,
+            Some(c) if c.is_alphanumeric() || c == '_' => {
+                rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len())
+            },
+            Some(c) => c.len_utf8(),
+        };
+        let found = match found_len {
+            0 => "end of file".to_string(),
+            _ => format!("`{}`", &rest[..found_len]),
+        };
+
+        let message = match kind {
+            VerboseErrorKind::Char(c) => format!("expected `{}`, found {}", c, found),
+            VerboseErrorKind::Context(ctx) => format!("expected {}, found {}", ctx, found),
+            VerboseErrorKind::Nom(_) => format!("unexpected {}", found),
+        };
+
+        let (line, column) = line_col(input, start);
+        let line_start = input[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = input[start..].find('\n').map(|i| start + i).unwrap_or(input.len());
+
+        Error::InvalidSyntax {
+            span: Span {start, end: start + found_len},
+            line,
+            column,
+            message,
+            source_line: input[line_start..line_end].to_string(),
+        }
+    }
+}
+
+/// A parse error recorded without a reference to the input so that it can outlive the input
+#[derive(Debug, Clone, PartialEq)]
+struct RawError {
+    /// The length of the input remaining at the point where the error occurred
+    remaining_len: usize,
+    kind: VerboseErrorKind,
+}
+
+impl RawError {
+    fn new(err: nom::Err<VerboseError<Input>>) -> Self {
+        let err = match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => err,
+            // This should not be reachable because we are using the 'complete' versions of all
+            // parsers
+            nom::Err::Incomplete(_) => unreachable!(),
+        };
+
+        // The first error is the one that occurred furthest into the input
+        let (remaining, kind) = err.errors.into_iter().next()
+            .expect("bug: parse errors should always have at least one entry");
+        Self {remaining_len: remaining.len(), kind}
+    }
+}
+
+thread_local! {
+    /// The errors that the parser recovered from while parsing the current module
+    ///
+    /// Parsers are only functions of their input, so the errors they recover from (and therefore
+    /// do not return) are recorded here instead.
+    static RECOVERED_ERRORS: RefCell<Vec<RawError>> = const { RefCell::new(Vec::new()) };
+}
+
+thread_local! {
+    /// The length of the module currently being parsed and the byte offset of the start of each
+    /// of its lines
+    ///
+    /// Parsers only see the input that is left to parse, so the offset of any point in the module
+    /// is found from the length of the remaining input.
+    static LINE_STARTS: RefCell<(usize, Vec<usize>)> = const { RefCell::new((0, Vec::new())) };
+}
+
+/// Returns the line (starting at 1) of the start of the given remaining input in the module
+/// currently being parsed
+fn line_of(input: Input) -> usize {
+    LINE_STARTS.with(|line_starts| {
+        let (input_len, line_starts) = &*line_starts.borrow();
+        let offset = input_len.saturating_sub(input.len());
+        // The line of the offset is the last line that starts at or before the offset
+        let line = match line_starts.binary_search(&offset) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        };
+        // No lines are known if the parser is being used outside of `parse_module`
+        line.max(1)
+    })
+}
+
+/// Records an error that the parser recovered from
+fn record_error(err: RawError) {
+    RECOVERED_ERRORS.with(|errors| {
+        let mut errors = errors.borrow_mut();
+        // The same input may be parsed more than once when backtracking
+        if !errors.contains(&err) {
+            errors.push(err);
+        }
+    });
+}
 
-    if inp.input_len() != 0 {
-        // The parser should have ensured that all input is consumed
-        unreachable!()
+/// Attempts to parse the given input module
+///
+/// Parsing continues after a syntax error at the next statement or declaration, so every error
+/// in the input is returned, in the order that they occur.
+pub fn parse_module(input: &str) -> Result<Module, Vec<Error>> {
+    RECOVERED_ERRORS.with(|errors| errors.borrow_mut().clear());
+    LINE_STARTS.with(|line_starts| {
+        let starts = once(0).chain(input.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        line_starts.replace((input.len(), starts));
+    });
+
+    let (_, module) = module(input)
+        .expect("bug: the module parser should always recover from errors");
+
+    let mut errors = RECOVERED_ERRORS.with(|errors| errors.replace(Vec::new()));
+    if errors.is_empty() {
+        return Ok(module);
     }
 
-    Ok(module)
+    errors.sort_by_key(|err| input.len() - err.remaining_len);
+    Err(errors.iter().map(|err| Error::new(input, err)).collect())
 }
 
+/// Parses declarations until the end of the input, recording and then skipping any declarations
+/// that cannot be parsed
 fn module(input: Input) -> IResult<Module> {
-    map(
-        preceded(wsc0, many0(terminated(decl, wsc0))),
-        |decls| Module {decls},
-    )(input)
+    let mut decls = Vec::new();
+
+    let (mut input, _) = wsc0(input)?;
+    while !input.is_empty() {
+        input = match decl(input) {
+            Ok((input, decl)) => {
+                decls.push(decl);
+                input
+            },
+            Err(err) => {
+                record_error(RawError::new(err));
+                skip_decl(input)
+            },
+        };
+
+        input = wsc0(input)?.0;
+    }
+
+    Ok((input, Module {decls}))
 }
 
 fn decl(input: Input) -> IResult<Decl> {
     alt((
+        map(import_decl, Decl::Import),
+        map(module_decl, Decl::Module),
         map(struct_decl, Decl::Struct),
         map(impl_block, Decl::Impl),
+        map(test_function, Decl::Function),
+        map(pure_function(FuncType::Function), Decl::Function),
+        map(c_function, Decl::Function),
+        map(static_decl, Decl::Static),
+        // Must be last since the error from the last alternative is the one that gets reported.
+        // A malformed function is by far the most common syntax error in a declaration.
         map(function(FuncType::Function), Decl::Function),
     ))(input)
 }
 
+/// An import of items from a module: `use package::path::to::module::*;`,
+/// `use package::path::to::module::name;`, or `use package::path::to::module::{name as alias};`
+///
+/// Only absolute paths are supported for now. Any of these may start with `pub` to re-export the
+/// imported items.
+fn import_decl(input: Input) -> IResult<ImportPath> {
+    map(tuple((
+        opt(terminated(kw_pub, wsc0)),
+        kw_use,
+        wsc0,
+        kw_package,
+        wsc0,
+        tag("::"),
+        wsc0,
+        // Every name followed by `::` is part of the path to the module. The name of an imported
+        // item is never followed by `::`, so it is left for `import_selection`.
+        many1(terminated(ident, tuple((wsc0, tag("::"), wsc0)))),
+        import_selection,
+        wsc0,
+        char(';'),
+    )), |(is_pub, _, _, _, _, _, _, path, selection, _, _)| ImportPath {
+        path,
+        selection,
+        is_pub: is_pub.is_some(),
+    })(input)
+}
+
+/// The items selected by an import: `*`, `name`, or `{name, name as alias, ...}`
+fn import_selection(input: Input) -> IResult<ImportSelection> {
+    alt((
+        value(ImportSelection::All, char('*')),
+        map(delimited_wsc0(char('{'), comma_separated(import_name), char('}')), ImportSelection::Names),
+        map(import_name, |name| ImportSelection::Names(vec![name])),
+    ))(input)
+}
+
+fn import_name(input: Input) -> IResult<ImportName> {
+    map(tuple((
+        ident,
+        opt(preceded(tuple((wsc0, kw_as, wsc0)), ident)),
+    )), |(name, alias)| ImportName {name, alias})(input)
+}
+
+/// An inline module: `mod name { fn ... }`
+fn module_decl(input: Input) -> IResult<ModuleDecl> {
+    map(
+        tuple((
+            kw_mod,
+            wsc0,
+            ident,
+            wsc0,
+            delimited_wsc0(
+                char('{'),
+                many0(preceded(wsc0, alt((
+                    pure_function(FuncType::Function),
+                    function(FuncType::Function),
+                )))),
+                char('}'),
+            ),
+        )),
+        |(_, _, name, _, functions)| ModuleDecl {name, functions},
+    )(input)
+}
+
+/// A global variable: `static mut NAME: ty = value;`
+///
+/// Only mutable globals are supported, so `mut` is required.
+fn static_decl(input: Input) -> IResult<Static> {
+    map(tuple((
+        kw_static,
+        wsc0,
+        kw_mut,
+        wsc0,
+        ident,
+        wsc0,
+        char(':'),
+        wsc0,
+        ty,
+        wsc0,
+        char('='),
+        wsc0,
+        expr,
+        wsc0,
+        char(';'),
+    )), |(_, _, _, _, name, _, _, _, ty, _, _, _, init, _, _)| Static {name, ty, init})(input)
+}
+
 fn struct_decl(input: Input) -> IResult<Struct> {
     map(tuple((
         kw_struct,
@@ -72,7 +338,36 @@ fn struct_decl(input: Input) -> IResult<Struct> {
         ident,
         wsc0,
         struct_fields,
-    )), |(_, _, name, _, fields)| Struct {name, fields})(input)
+        opt(preceded(tuple((wsc0, kw_requires, wsc0)), expr)),
+    )), |(_, _, name, _, fields, invariant)| {
+        let invariant = invariant.map(|invariant| invariant_method(&fields, invariant));
+        Struct {name, fields, invariant}
+    })(input)
+}
+
+/// Creates the associated function that checks the given struct invariant
+fn invariant_method<'a>(fields: &[StructField<'a>], invariant: Expr<'a>) -> Function<'a> {
+    Function {
+        name: INVARIANT_METHOD_NAME,
+        sig: FuncSig {
+            return_type: Ty::Named("bool"),
+            // Only the fields of the struct are in scope in the invariant
+            params: fields.iter().map(|&StructField {name, ref ty, ..}| FuncParam {
+                name,
+                ty: ty.clone(),
+            }).collect(),
+        },
+        body: Block {
+            stmts: Vec::new(),
+            stmt_lines: Vec::new(),
+            ret: Some(invariant),
+        },
+        is_extern: false,
+        c_body: None,
+        is_test: false,
+        is_pure: false,
+        source_path: None,
+    }
 }
 
 fn struct_fields(input: Input) -> IResult<Vec<StructField>> {
@@ -86,10 +381,16 @@ fn struct_field(input: Input) -> IResult<StructField> {
         char(':'),
         wsc0,
         ty,
-    )), |(name, _, _, _, ty)| StructField {name, ty})(input)
+        opt(preceded(tuple((wsc0, char('='), wsc0)), expr)),
+    )), |(name, _, _, _, ty, default)| StructField {name, ty, default})(input)
 }
 
 fn impl_block(input: Input) -> IResult<Impl> {
+    enum ImplItem<'a> {
+        Method(Function<'a>),
+        Const(AssocConst<'a>),
+    }
+
     map(
         tuple((
             kw_impl,
@@ -98,14 +399,48 @@ fn impl_block(input: Input) -> IResult<Impl> {
             wsc0,
             delimited_wsc0(
                 char('{'),
-                many0(preceded(wsc0, function(FuncType::Method))),
+                many0(preceded(wsc0, alt((
+                    map(pure_function(FuncType::Method), ImplItem::Method),
+                    map(function(FuncType::Method), ImplItem::Method),
+                    map(assoc_const, ImplItem::Const),
+                )))),
                 char('}'),
             ),
         )),
-        |(_, _, self_ty, _, methods)| Impl {self_ty, methods},
+        |(_, _, self_ty, _, items)| {
+            let mut methods = Vec::new();
+            let mut consts = Vec::new();
+            for item in items {
+                match item {
+                    ImplItem::Method(method) => methods.push(method),
+                    ImplItem::Const(assoc_const) => consts.push(assoc_const),
+                }
+            }
+
+            Impl {self_ty, methods, consts}
+        },
     )(input)
 }
 
+/// An associated constant in an impl block: `const NAME: ty = value;`
+fn assoc_const(input: Input) -> IResult<AssocConst> {
+    map(tuple((
+        kw_const,
+        wsc0,
+        ident,
+        wsc0,
+        char(':'),
+        wsc0,
+        ty,
+        wsc0,
+        char('='),
+        wsc0,
+        expr,
+        wsc0,
+        char(';'),
+    )), |(_, _, name, _, _, _, ty, _, _, _, value, _, _)| AssocConst {name, ty, value})(input)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum FuncType {
     Function,
@@ -122,7 +457,7 @@ fn function(func_type: FuncType) -> impl Fn(Input) -> IResult<Function> {
             FuncType::Function => function_params,
             FuncType::Method => method_params,
         },
-        opt(tuple((wsc0, tag("->"), wsc0, ty))),
+        opt(tuple((wsc0, tag("->"), wsc0, return_ty))),
         wsc0,
         block,
     )), |(_, _, name, _, params, return_ty, _, body)| Function {
@@ -134,9 +469,85 @@ fn function(func_type: FuncType) -> impl Fn(Input) -> IResult<Function> {
         },
         body,
         is_extern: false,
+        c_body: None,
+        is_test: false,
+        is_pure: false,
+        source_path: None,
+    })(input)
+}
+
+/// A function whose body is raw C code: `extern "C" fn name(params) -> ret = "C code";`
+///
+/// The C code is emitted verbatim as the body of the generated function and is never checked.
+/// Each parameter is a pointer to the runtime representation of its type (e.g. `DInt*` for `int`)
+/// and the code must return a pointer of the same kind, so C operators can't be used on the values
+/// directly. For example, `return x * 2;` does not compile, but `return int__add(x, x);` does.
+fn c_function(input: Input) -> IResult<Function> {
+    map(tuple((
+        kw_extern,
+        wsc0,
+        tag("\"C\""),
+        wsc0,
+        kw_fn,
+        wsc0,
+        ident,
+        wsc0,
+        function_params,
+        opt(tuple((wsc0, tag("->"), wsc0, return_ty))),
+        wsc0,
+        char('='),
+        wsc0,
+        str_literal,
+        wsc0,
+        char(';'),
+    )), |(_, _, _, _, _, _, name, _, params, return_ty, _, _, _, c_body, _, _)| Function {
+        name,
+        sig: FuncSig {
+            // The default return type is unit
+            return_type: return_ty.map(|(_, _, _, ty)| ty).unwrap_or(Ty::Unit),
+            params,
+        },
+        body: Block {
+            stmts: Vec::new(),
+            stmt_lines: Vec::new(),
+            ret: None,
+        },
+        is_extern: false,
+        c_body: Some(c_body),
+        is_test: false,
+        is_pure: false,
+        source_path: None,
     })(input)
 }
 
+/// A function marked with the `#[test]` attribute
+fn test_function(input: Input) -> IResult<Function> {
+    map(
+        tuple((attribute("test"), wsc0, function(FuncType::Function))),
+        |(_, _, func)| Function {is_test: true, ..func},
+    )(input)
+}
+
+/// A function or method marked with the `#[pure]` attribute
+fn pure_function(func_type: FuncType) -> impl Fn(Input) -> IResult<Function> {
+    move |input| map(
+        tuple((attribute("pure"), wsc0, function(func_type))),
+        |(_, _, func)| Function {is_pure: true, ..func},
+    )(input)
+}
+
+/// An attribute with the given name and no arguments: `#[name]`
+fn attribute(name: &'static str) -> impl Fn(Input) -> IResult<()> {
+    move |input| map(
+        tuple((
+            char('#'),
+            wsc0,
+            delimited_wsc0(char('['), terminated(tag(name), not(ident)), char(']')),
+        )),
+        |_| (),
+    )(input)
+}
+
 fn method_params(input: Input) -> IResult<Vec<FuncParam>> {
     static SELF_PARAM: FuncParam = FuncParam {
         name: "self",
@@ -180,15 +591,8 @@ fn param_list(input: Input) -> IResult<Vec<FuncParam>> {
 
 fn block(input: Input) -> IResult<Block> {
     map(
-        delimited_wsc0(
-            char('{'),
-            tuple((
-                many0(preceded(wsc0, stmt)),
-                opt(preceded(wsc0, expr)),
-            )),
-            char('}'),
-        ),
-        |(mut stmts, ret)| {
+        preceded(tuple((char('{'), wsc0)), block_contents),
+        |(mut stmts, stmt_lines, ret)| {
             // There is an ambiguity here because certain expressions can also be written in
             // statment position. When that is the case, we need to be sure to pull those
             // statements into the return expression instead of leaving them in the statements
@@ -198,9 +602,15 @@ fn block(input: Input) -> IResult<Block> {
                     // Since semi-colons after conditionals are optional even in statement
                     // position, they may sometimes be seen as statements when the user actually
                     // intended them to be the return expression
+                    // The line of the statement becomes the line of the return expression
                     Stmt::Cond(cond) => Some(Expr::Cond(Box::new(cond))),
                     // Cannot currently promote a while loop to an expression
                     stmt@Stmt::WhileLoop(_) |
+                    // These never produce a value, so they are left as statements
+                    stmt@Stmt::Break |
+                    stmt@Stmt::Continue |
+                    // Nested blocks are only allowed in statement position
+                    stmt@Stmt::Block(_) |
                     // Cannot promote var declaration to expression
                     stmt@Stmt::VarDecl(_) |
                     // Expressions cannot be promoted because for them to be parsed as statements
@@ -219,22 +629,85 @@ fn block(input: Input) -> IResult<Block> {
                 ret
             };
 
-            Block {stmts, ret}
+            Block {stmts, stmt_lines, ret}
         },
     )(input)
 }
 
+/// Parses the statements and return expression of a block, up to and including its closing `}`,
+/// along with the line of each statement
+///
+/// A statement that cannot be parsed is recorded as an error and skipped so that parsing can
+/// continue with the next statement. The only error returned is reaching the end of the input
+/// before the end of the block, since there is nothing left to recover with in that case.
+fn block_contents(input: Input) -> IResult<(Vec<Stmt>, Vec<usize>, Option<Expr>)> {
+    let mut stmts = Vec::new();
+    let mut stmt_lines = Vec::new();
+
+    let mut input = input;
+    loop {
+        if let Ok((input, _)) = char::<_, VerboseError<_>>('}')(input) {
+            return Ok((input, (stmts, stmt_lines, None)));
+        }
+
+        let stmt_err = match terminated(stmt, wsc0)(input) {
+            Ok((next_input, stmt)) => {
+                stmts.push(stmt);
+                stmt_lines.push(line_of(input));
+                input = next_input;
+                continue;
+            },
+            Err(err) => RawError::new(err),
+        };
+
+        // Not a statement, so this must be the return expression at the end of the block
+        let ret_err = match terminated(expr, tuple((wsc0, char('}'))))(input) {
+            Ok((next_input, ret)) => {
+                stmt_lines.push(line_of(input));
+                return Ok((next_input, (stmts, stmt_lines, Some(ret))));
+            },
+            Err(err) => RawError::new(err),
+        };
+
+        // Report whichever error got further into the input, preferring the statement error since
+        // it is more likely that a statement was intended
+        let err = if ret_err.remaining_len < stmt_err.remaining_len { ret_err } else { stmt_err };
+
+        let next_input = skip_stmt(input);
+        if next_input.is_empty() {
+            // Return the error since there is no way to recover from reaching the end of input
+            let remaining = &input[input.len() - err.remaining_len..];
+            return Err(nom::Err::Error(VerboseError {errors: vec![(remaining, err.kind)]}));
+        }
+
+        record_error(err);
+        input = wsc0(next_input)?.0;
+    }
+}
+
 fn stmt(input: Input) -> IResult<Stmt> {
     alt((
         // Need to explicitly ensure that there is so semi-colon following this since that means it
         // was intended as an expression, not a statement
         map(tuple((cond, not(char(';')))), |(cond, _)| Stmt::Cond(cond)),
         map(while_loop, Stmt::WhileLoop),
+        value(Stmt::Break, terminated(kw_break, loop_control_end)),
+        value(Stmt::Continue, terminated(kw_continue, loop_control_end)),
+        map(block, Stmt::Block),
         map(var_decl, Stmt::VarDecl),
+        compound_assign_stmt,
         map(tuple((expr, wsc0, char(';'))), |(expr, _, _)| Stmt::Expr(expr)),
     ))(input)
 }
 
+/// The end of a `break` or `continue`, which is either a semi-colon or the end of the block
+///
+/// `break` and `continue` never produce a value, so the semi-colon is optional when they are the
+/// last thing in a block (e.g. `if done { break }`).
+fn loop_control_end(input: Input) -> IResult<()> {
+    preceded(wsc0, alt((value((), char(';')), value((), peek(char('}'))))))(input)
+}
+
 fn while_loop(input: Input) -> IResult<WhileLoop> {
     map(
         tuple((kw_while, wsc0, expr, wsc0, block)),
@@ -285,22 +758,125 @@ fn precedence0(input: Input) -> IResult<Expr> {
             infix(lvalue_expr, char('='), expr),
             |(lhs, expr)| Expr::VarAssign(Box::new(VarAssign {lhs, expr})),
         ),
+        compound_assign,
 
         // If nothing above parses, we can use the next upper level of precedence
         precedence1,
     ))(input)
 }
 
+/// A compound assignment (e.g. `x += 1`), which is desugared into an assignment and a method call
+/// (e.g. `x = x.add(1)`)
+///
+/// The left-hand side is evaluated twice, so only variables and the fields of variables are allowed
+/// since those can never have side effects. The field of any other value can only be assigned to
+/// in statement position (see `compound_assign_stmt`).
+fn compound_assign(input: Input) -> IResult<Expr> {
+    use nom::error::{ParseError, ErrorKind};
+
+    let (rest, (lhs, method_name, expr)) = tuple((
+        lvalue_expr,
+        preceded(wsc0, compound_assign_op),
+        preceded(wsc0, expr),
+    ))(input)?;
+
+    let current = match &lhs {
+        LValueExpr::FieldAccess(access) => Expr::FieldAccess(Box::new(access.clone())),
+        &LValueExpr::Var(name) => Expr::Var(name),
+    };
+    if !is_place(&current) {
+        //TODO: Replace this with a proper error about which left-hand sides are allowed
+        return Err(nom::Err::Error(VerboseError::from_error_kind(input, ErrorKind::Verify)));
+    }
+
+    let expr = Expr::MethodCall(Box::new(MethodCall {lhs: current, method_name, args: vec![expr]}));
+    Ok((rest, Expr::VarAssign(Box::new(VarAssign {lhs, expr}))))
+}
+
+/// A compound assignment statement to the field of a value that is not a place (e.g.
+/// `next_point().x += 1;`)
+///
+/// The value is evaluated exactly once into a hidden variable declared in a nested block, so the
+/// example above becomes:
+///
+/// ```text
+/// {
+///     let compound$base = next_point();
+///     compound$base.x = compound$base.x.add(1);
+/// }
+/// ```
+///
+/// The hidden variable is not a valid identifier, so it can never conflict with (or be shadowed
+/// by) a variable in the program.
+fn compound_assign_stmt(input: Input) -> IResult<Stmt> {
+    use nom::error::{ParseError, ErrorKind};
+
+    let line = line_of(input);
+    let (rest, (lhs, method_name, expr, _)) = tuple((
+        lvalue_expr,
+        preceded(wsc0, compound_assign_op),
+        preceded(wsc0, expr),
+        preceded(wsc0, char(';')),
+    ))(input)?;
+
+    let FieldAccess {lhs: base, field} = match lhs {
+        LValueExpr::FieldAccess(access) if !is_place(&access.lhs) => access,
+        // Places are handled by `compound_assign`
+        _ => return Err(nom::Err::Error(VerboseError::from_error_kind(input, ErrorKind::Verify))),
+    };
+
+    let base_decl = VarDecl {ident: COMPOUND_BASE_VAR, ty: None, expr: base};
+    let access = FieldAccess {lhs: Expr::Var(COMPOUND_BASE_VAR), field};
+    let assign = VarAssign {
+        expr: Expr::MethodCall(Box::new(MethodCall {
+            lhs: Expr::FieldAccess(Box::new(access.clone())),
+            method_name,
+            args: vec![expr],
+        })),
+        lhs: LValueExpr::FieldAccess(access),
+    };
+
+    Ok((rest, Stmt::Block(Block {
+        stmts: vec![Stmt::VarDecl(base_decl), Stmt::Expr(Expr::VarAssign(Box::new(assign)))],
+        stmt_lines: vec![line, line],
+        ret: None,
+    })))
+}
+
+/// The name of the hidden variable declared by `compound_assign_stmt`
+const COMPOUND_BASE_VAR: &str = "compound$base";
+
+/// The operator of a compound assignment, producing the name of the method it calls
+fn compound_assign_op(input: Input) -> IResult<&'static str> {
+    alt((
+        value("add", tag("+=")),
+        value("sub", tag("-=")),
+        value("mul", tag("*=")),
+        value("div", tag("/=")),
+        value("rem", tag("%=")),
+    ))(input)
+}
+
+/// Returns true if the given expression is a variable or a (possibly nested) field of a variable
+fn is_place(expr: &Expr) -> bool {
+    match expr {
+        Expr::Var(_) | Expr::SelfLiteral => true,
+        Expr::FieldAccess(access) => is_place(&access.lhs),
+        _ => false,
+    }
+}
+
 fn precedence1(input: Input) -> IResult<Expr> {
     bin_op_opt1(
         precedence2,
         alt((
             tag("=="),
             tag("!="),
-            tag("<"),
-            tag(">"),
+            // `<=` and `>=` must come before `<` and `>` since those are prefixes of them
             tag("<="),
             tag(">="),
+            tag("<"),
+            tag(">"),
         )),
         precedence2,
         |lhs, op, rhs| Expr::MethodCall(Box::new(MethodCall {
@@ -311,8 +887,8 @@ fn precedence1(input: Input) -> IResult<Expr> {
                 "!=" => "ne",
                 "<" => "lt",
                 ">" => "gt",
-                "<=" => "le",
-                ">=" => "ge",
+                "<=" => "lte",
+                ">=" => "gte",
                 _ => unreachable!(),
             },
             args: vec![rhs],
@@ -325,16 +901,32 @@ fn precedence2(input: Input) -> IResult<Expr> {
         precedence3,
         one_of("+-"),
         precedence3,
-        |lhs, op, rhs| Expr::MethodCall(Box::new(MethodCall {
-            lhs,
-            //TODO: Should be using trait methods
-            method_name: match op {
-                '+' => "add",
-                '-' => "sub",
-                _ => unreachable!(),
-            },
-            args: vec![rhs],
-        })),
+        |lhs, op, rhs| match (lhs, rhs) {
+            // A real number literal plus or minus an imaginary literal (e.g. `4.5 - 5i`) is
+            // written as a single complex number, so we produce that complex number directly.
+            // Integer literals are allowed too as long as they are not explicitly `int`.
+            (lhs @ Expr::IntegerLiteral(IntegerLiteral {type_hint: None, ..}), rhs @ Expr::ComplexLiteral(_)) |
+            (lhs @ Expr::RealLiteral(_), rhs @ Expr::ComplexLiteral(_)) => Expr::Call(CallExpr {
+                func_name: IdentPath::from(match op {
+                    '+' => "add_real_complex",
+                    '-' => "sub_real_complex",
+                    _ => unreachable!(),
+                }),
+                args: vec![lhs, rhs],
+                named_args: Vec::new(),
+            }),
+
+            (lhs, rhs) => Expr::MethodCall(Box::new(MethodCall {
+                lhs,
+                //TODO: Should be using trait methods
+                method_name: match op {
+                    '+' => "add",
+                    '-' => "sub",
+                    _ => unreachable!(),
+                },
+                args: vec![rhs],
+            })),
+        },
     )(input)
 }
 
@@ -435,7 +1027,11 @@ fn precedence6(input: Input) -> IResult<Expr> {
     alt((
         group,
         map(cond, |cond| Expr::Cond(Box::new(cond))),
+        // Must be parsed before func_call since it looks like a function call
+        map(offset_of, Expr::OffsetOf),
         map(func_call, Expr::Call),
+        // Must be parsed after func_call since a call to an associated function has the same prefix
+        map(assoc_const_path, Expr::AssocConst),
         map(return_expr, |ret_expr| Expr::Return(ret_expr.map(Box::new))),
         map(struct_literal, Expr::StructLiteral),
         map(bstr_literal, Expr::BStrLiteral),
@@ -478,8 +1074,31 @@ fn cond(input: Input) -> IResult<Cond> {
 
 fn func_call(input: Input) -> IResult<CallExpr> {
     map(
-        tuple((ident_path, wsc0, func_args)),
-        |(func_name, _, args)| CallExpr {func_name, args},
+        tuple((ident_path, wsc0, call_args)),
+        |(func_name, _, (args, named_args))| CallExpr {func_name, args, named_args},
+    )(input)
+}
+
+fn assoc_const_path(input: Input) -> IResult<AssocConstPath> {
+    map(
+        tuple((named_ty, wsc0, tag("::"), wsc0, ident)),
+        |(self_ty, _, _, _, name)| AssocConstPath {self_ty: Ty::from(self_ty), name},
+    )(input)
+}
+
+fn offset_of(input: Input) -> IResult<OffsetOf> {
+    map(
+        tuple((
+            // Must not be the start of a longer identifier
+            terminated(tag("offset_of"), not(ident)),
+            wsc0,
+            delimited_wsc0(
+                char('('),
+                tuple((ty, wsc0, char(','), wsc0, ident, opt(tuple((wsc0, char(',')))))),
+                char(')'),
+            ),
+        )),
+        |(_, _, (self_ty, _, _, _, field, _))| OffsetOf {self_ty, field},
     )(input)
 }
 
@@ -487,6 +1106,50 @@ fn func_args(input: Input) -> IResult<Vec<Expr>> {
     delimited_wsc0(char('('), comma_separated(expr), char(')'))(input)
 }
 
+/// A single argument in a function call
+#[derive(Debug, Clone)]
+enum CallArg<'a> {
+    Positional(Expr<'a>),
+    Named(NamedArg<'a>),
+}
+
+/// Parses the arguments of a function call, which may include named arguments after any
+/// positional arguments
+fn call_args(input: Input) -> IResult<(Vec<Expr>, Vec<NamedArg>)> {
+    map_res(
+        delimited_wsc0(char('('), comma_separated(call_arg), char(')')),
+        |call_args| {
+            let mut args = Vec::new();
+            let mut named_args = Vec::new();
+            for arg in call_args {
+                match arg {
+                    // Positional arguments are not allowed after a named argument
+                    CallArg::Positional(_) if !named_args.is_empty() => return Err(()),
+                    CallArg::Positional(arg) => args.push(arg),
+                    CallArg::Named(arg) => named_args.push(arg),
+                }
+            }
+
+            Ok((args, named_args))
+        },
+    )(input)
+}
+
+fn call_arg(input: Input) -> IResult<CallArg> {
+    alt((
+        map(named_arg, CallArg::Named),
+        map(expr, CallArg::Positional),
+    ))(input)
+}
+
+fn named_arg(input: Input) -> IResult<NamedArg> {
+    map(
+        // Need to make sure this isn't the start of a path (e.g. `foo::bar()`)
+        tuple((ident, wsc0, char(':'), not(char(':')), wsc0, expr)),
+        |(name, _, _, _, _, value)| NamedArg {name, value},
+    )(input)
+}
+
 fn return_expr(input: Input) -> IResult<Option<Expr>> {
     map(
         tuple((kw_return, wsc0, opt(expr))),
@@ -526,17 +1189,49 @@ fn struct_field_value(input: Input) -> IResult<StructFieldValue> {
 }
 
 fn bstr_literal(input: Input) -> IResult<Vec<u8>> {
-    map(delimited(
-        tag("b\""),
-        opt(escaped_transform(take_till1(|c| c == '"' || c == '\\'), '\\', |inp| alt((
-            map(char('\\'), |_| "\\"),
-            map(char('"'), |_| "\""),
-            map(char('n'), |_| "\n"),
-            map(char('r'), |_| "\r"),
-            map(char('t'), |_| "\t"),
-        ))(inp))),
+    preceded(char('b'), str_contents)(input)
+}
+
+/// A string literal with the same escapes as a byte string literal that must be valid UTF-8
+fn str_literal(input: Input) -> IResult<String> {
+    map_res(str_contents, String::from_utf8)(input)
+}
+
+/// Parses a double-quoted string, producing the bytes it represents
+fn str_contents(input: Input) -> IResult<Vec<u8>> {
+    delimited(
         char('"'),
-    ), |s| s.unwrap_or_default().into_bytes())(input)
+        fold_many0(
+            alt((
+                map(take_till1(|c| c == '"' || c == '\\'), |text: Input| text.as_bytes().to_vec()),
+                map(bstr_escape, |byte| vec![byte]),
+            )),
+            Vec::new(),
+            |mut bytes, piece| {
+                bytes.extend(piece);
+                bytes
+            },
+        ),
+        char('"'),
+    )(input)
+}
+
+/// Parses an escape sequence in a byte string literal, producing the byte it represents
+fn bstr_escape(input: Input) -> IResult<u8> {
+    preceded(char('\\'), alt((
+        value(b'\\', char('\\')),
+        value(b'"', char('"')),
+        value(b'\'', char('\'')),
+        value(b'\n', char('n')),
+        value(b'\r', char('r')),
+        value(b'\t', char('t')),
+        value(b'\0', char('0')),
+        // A byte escape with exactly two hex digits, e.g. `\x7F`
+        map_res(
+            preceded(char('x'), take_while_m_n(2, 2, |c: char| c.is_ascii_hexdigit())),
+            |digits: Input| u8::from_str_radix(digits, 16),
+        ),
+    )))(input)
 }
 
 fn integer_literal(input: Input) -> IResult<IntegerLiteral> {
@@ -572,8 +1267,16 @@ fn integer_literal(input: Input) -> IResult<IntegerLiteral> {
 
 fn real_or_complex_literal(input: Input) -> IResult<Expr> {
     map(
-        tuple((double, opt(one_of("jJiI")))),
-        |(value, complex)| if complex.is_some() {
+        tuple((
+            // `double` also accepts `inf` and `nan`, but those are identifiers in this language, so
+            // a real number literal must start with a digit
+            peek(pair(opt(one_of("+-")), digit1)),
+            double,
+            opt(one_of("jJiI")),
+            // Prevents a literal from being immediately followed by an identifier (e.g. `1.5foo`)
+            not(take_while1(|c: char| c.is_alphanumeric() || c == '_')),
+        )),
+        |(_, value, complex, _)| if complex.is_some() {
             Expr::ComplexLiteral(value)
         } else {
             Expr::RealLiteral(value)
@@ -624,6 +1327,14 @@ fn ty(input: Input) -> IResult<Ty> {
     ))(input)
 }
 
+/// The return type of a function, which may also be the never type `!`
+fn return_ty(input: Input) -> IResult<Ty> {
+    alt((
+        map(char('!'), |_| Ty::Never),
+        ty,
+    ))(input)
+}
+
 fn named_ty(input: Input) -> IResult<NamedTy> {
     alt((
         map(kw_selftype, |_| NamedTy::SelfType),
@@ -650,6 +1361,98 @@ fn comment(input: Input) -> IResult<()> {
     )(input)
 }
 
+/// Skips the rest of a statement that could not be parsed, up to and including the next `;`
+///
+/// Stops before a `}` that would close the surrounding block.
+fn skip_stmt(input: Input) -> Input {
+    skip_to_sync_point(input, |input, depth| match input.chars().next() {
+        Some(';') if depth == 0 => Some(&input[1..]),
+        Some('}') if depth == 0 => Some(input),
+        _ => None,
+    })
+}
+
+/// Skips the rest of a declaration that could not be parsed, up to and including the next `;` or
+/// `}` that isn't nested in any brackets
+///
+/// Stops before any `fn` keyword that isn't nested in any brackets, since that is likely to be the
+/// start of the next declaration.
+fn skip_decl(orig_input: Input) -> Input {
+    skip_to_sync_point(orig_input, |input, depth| match input.chars().next() {
+        Some(';') if depth == 0 => Some(&input[1..]),
+        Some('}') if depth == 1 => Some(&input[1..]),
+        // The declaration itself may start with `fn`
+        Some(_) if depth == 0 && input.len() < orig_input.len() && kw_fn(input).is_ok() => {
+            Some(input)
+        },
+        _ => None,
+    })
+}
+
+/// Skips input until `sync_point` returns the input to continue from
+///
+/// `sync_point` is given the remaining input and the depth of brackets at the start of that input.
+/// Brackets in comments and string literals are ignored. If the end of input is reached
+/// without finding a sync point, the returned input is empty.
+fn skip_to_sync_point<'a>(
+    input: Input<'a>,
+    sync_point: impl Fn(Input<'a>, usize) -> Option<Input<'a>>,
+) -> Input<'a> {
+    let mut depth = 0usize;
+
+    let mut input = input;
+    while let Some(c) = input.chars().next() {
+        // Identifiers are skipped as a whole so that keywords are only found at word boundaries
+        if c.is_alphanumeric() || c == '_' {
+            if let Some(next_input) = sync_point(input, depth) {
+                return next_input;
+            }
+            let ident_len = input.find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(input.len());
+            input = &input[ident_len..];
+            continue;
+        }
+
+        // Skip the contents of string literals (e.g. `b"..."`) so their contents are ignored
+        if c == '"' {
+            input = skip_str_contents(&input[1..]);
+            continue;
+        }
+
+        if input.starts_with("//") {
+            input = input.find('\n').map(|i| &input[i..]).unwrap_or("");
+            continue;
+        }
+
+        if let Some(next_input) = sync_point(input, depth) {
+            return next_input;
+        }
+
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+        input = &input[c.len_utf8()..];
+    }
+
+    input
+}
+
+/// Skips the contents of a string literal, up to and including its closing `"`
+fn skip_str_contents(input: Input) -> Input {
+    let mut chars = input.char_indices();
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '\\' => { chars.next(); },
+            '"' => return chars.as_str(),
+            _ => {},
+        }
+    }
+
+    ""
+}
+
 /// Parses comma separated values, allowing for a trailing comma at the end
 ///
 /// The returned Vec is allowed to be empty
@@ -796,8 +1599,10 @@ keywords! {
     kw_mod : mod
     kw_move : move
     kw_mut : mut
+    kw_package : package
     kw_pub : pub
     kw_ref : ref
+    kw_requires : requires
     kw_return : return
     kw_selfvalue : self
     kw_selftype : Self
@@ -874,4 +1679,419 @@ mod tests {
         // No space between `fn` and `foo`
         test_parser!(func("fnfoo(){}") -> err);
     }
+
+    #[test]
+    fn func_call_parser() {
+        test_parser!(func_call("foo()") -> ok);
+        test_parser!(func_call("foo(1, 2)") -> ok);
+        test_parser!(func_call("foo(width: 3, height: 4)") -> ok);
+        test_parser!(func_call("foo(1, height : 4,)") -> ok);
+        test_parser!(func_call("foo(a::b(), c: a::b())") -> ok);
+
+        // Positional arguments must come before named arguments
+        test_parser!(func_call("foo(width: 3, 4)") -> err);
+    }
+
+    #[test]
+    fn test_function_parser() {
+        test_parser!(test_function("#[test] fn foo() {}") -> ok);
+        test_parser!(test_function("#[test]\nfn foo() -> int { 1 }") -> ok);
+        test_parser!(test_function("# [ test ] fn foo() {}") -> ok);
+
+        test_parser!(test_function("fn foo() {}") -> err);
+        test_parser!(test_function("#[tests] fn foo() {}") -> err);
+        test_parser!(test_function("#[test] struct Foo {}") -> err);
+    }
+
+    #[test]
+    fn pure_function_parser() {
+        let pure_func = pure_function(FuncType::Function);
+        let pure_method = pure_function(FuncType::Method);
+        test_parser!(pure_func("#[pure] fn double(x: int) -> int { x * 2 }") -> ok);
+        test_parser!(pure_method("#[pure]\nfn area(self) -> int { self.w }") -> ok);
+
+        let (_, func) = pure_func("#[pure] fn one() -> int { 1 }").unwrap();
+        assert!(func.is_pure);
+        assert!(!func.is_test);
+        let (_, func) = function(FuncType::Function)("fn one() -> int { 1 }").unwrap();
+        assert!(!func.is_pure);
+
+        test_parser!(impl_block("impl Rect { #[pure] fn area(self) -> int { self.w } }") -> ok);
+        test_parser!(module_decl("mod math { #[pure] fn one() -> int { 1 } }") -> ok);
+
+        test_parser!(pure_func("#[purely] fn one() -> int { 1 }") -> err);
+        test_parser!(pure_func("#[pure] struct Foo {}") -> err);
+    }
+
+    #[test]
+    fn c_function_parser() {
+        test_parser!(c_function(r#"extern "C" fn double(x: int) -> int = "return x;";"#) -> ok);
+        test_parser!(c_function(r#"extern "C" fn nothing() = "";"#) -> ok);
+        test_parser!(c_function("extern \"C\"\nfn f() -> int =\n\"return 0;\" ;") -> ok);
+
+        let (_, func) = c_function(r#"extern "C" fn greet() = "puts(\"hi\\n\");";"#).unwrap();
+        assert_eq!(func.c_body, Some(String::from("puts(\"hi\\n\");")));
+        assert_eq!(func.sig.return_type, Ty::Unit);
+        assert!(!func.is_extern);
+
+        // The body is required and must be a string
+        test_parser!(c_function(r#"extern "C" fn f() -> int;"#) -> err);
+        test_parser!(c_function(r#"extern "C" fn f() -> int { 1 }"#) -> err);
+        test_parser!(c_function(r#"extern "C" fn f() -> int = b"return 1;";"#) -> err);
+        // Only the C ABI is supported
+        test_parser!(c_function(r#"extern "Rust" fn f() = "";"#) -> err);
+        test_parser!(c_function(r#"extern "C" fn f() = """#) -> err);
+    }
+
+    #[test]
+    fn static_decl_parser() {
+        test_parser!(static_decl("static mut COUNT: int = 0;") -> ok);
+        test_parser!(static_decl("static mut NAME:bstr=b\"dino\" ;") -> ok);
+        test_parser!(static_decl("static\nmut ENABLED: bool =\ntrue;") -> ok);
+
+        let (_, decl) = static_decl("static mut COUNT: int = 3;").unwrap();
+        assert_eq!(decl.name, "COUNT");
+        assert_eq!(decl.ty, Ty::Named("int"));
+
+        // Only mutable statics are supported
+        test_parser!(static_decl("static COUNT: int = 0;") -> err);
+        // The type and the initializer are both required
+        test_parser!(static_decl("static mut COUNT = 0;") -> err);
+        test_parser!(static_decl("static mut COUNT: int;") -> err);
+        test_parser!(static_decl("static mut COUNT: int = 0") -> err);
+    }
+
+    #[test]
+    fn import_decl_parser() {
+        test_parser!(import_decl("use package::geometry::*;") -> ok);
+        test_parser!(import_decl("use package :: shapes :: circle :: * ;") -> ok);
+        test_parser!(import_decl("use package::geometry::area;") -> ok);
+        test_parser!(import_decl("use package::geometry::{area, Point as Coord,};") -> ok);
+        test_parser!(import_decl("pub use package::geometry::*;") -> ok);
+
+        let (_, import) = import_decl("use package::shapes::circle::*;").unwrap();
+        assert_eq!(import.path, vec!["shapes", "circle"]);
+        assert_eq!(import.selection, ImportSelection::All);
+        assert!(!import.is_pub);
+
+        // The last name is the imported item, not part of the path
+        let (_, import) = import_decl("use package::shapes::circle::area;").unwrap();
+        assert_eq!(import.path, vec!["shapes", "circle"]);
+        assert_eq!(import.selection, ImportSelection::Names(vec![
+            ImportName {name: "area", alias: None},
+        ]));
+
+        let (_, import) = import_decl("pub use package::shapes::{circle as round, square};").unwrap();
+        assert_eq!(import.path, vec!["shapes"]);
+        assert_eq!(import.selection, ImportSelection::Names(vec![
+            ImportName {name: "circle", alias: Some("round")},
+            ImportName {name: "square", alias: None},
+        ]));
+        assert!(import.is_pub);
+
+        // Only absolute paths are supported
+        test_parser!(import_decl("use geometry::*;") -> err);
+        // A module must be named
+        test_parser!(import_decl("use package::geometry;") -> err);
+        test_parser!(import_decl("use package::*;") -> err);
+        test_parser!(import_decl("use package::{area};") -> err);
+        test_parser!(import_decl("use package::geometry::*") -> err);
+        // An alias must be a name
+        test_parser!(import_decl("use package::geometry::area as;") -> err);
+        test_parser!(import_decl("use package::geometry::* as all;") -> err);
+    }
+
+    #[test]
+    fn module_decl_parser() {
+        test_parser!(module_decl("mod math {}") -> ok);
+        test_parser!(module_decl("mod math { fn add2(x: int) -> int { x + 2 } }") -> ok);
+        test_parser!(module_decl("mod math {\n    fn one() -> int { 1 }\n\n    fn two() -> int { 2 }\n}") -> ok);
+
+        let (_, module) = module_decl("mod math { fn one() -> int { 1 } fn two() {} }").unwrap();
+        assert_eq!(module.name, "math");
+        assert_eq!(module.functions.len(), 2);
+        assert_eq!(module.functions[1].name, "two");
+
+        // Only functions may be declared in a module
+        test_parser!(module_decl("mod math { struct Point {x: int} }") -> err);
+        test_parser!(module_decl("mod math { mod inner {} }") -> err);
+        // A module must have a name
+        test_parser!(module_decl("mod { fn one() -> int { 1 } }") -> err);
+    }
+
+    #[test]
+    fn real_literal_parser() {
+        test_parser!(real_or_complex_literal("1.5") -> ok);
+        test_parser!(real_or_complex_literal("2.5e3") -> ok);
+        test_parser!(real_or_complex_literal("3.0j") -> ok);
+
+        // `inf`, `nan`, and identifiers that start with them are not real number literals
+        test_parser!(real_or_complex_literal("inf") -> err);
+        test_parser!(real_or_complex_literal("nan") -> err);
+        test_parser!(real_or_complex_literal("inferred_real") -> err);
+        test_parser!(real_or_complex_literal("nan_count") -> err);
+        test_parser!(real_or_complex_literal("infinity2") -> err);
+        test_parser!(real_or_complex_literal("1.5foo") -> err);
+
+        let (_, var) = expr("inferred_real").unwrap();
+        assert!(matches!(var, Expr::Var("inferred_real")), "{:?}", var);
+        let (_, var) = expr("nan").unwrap();
+        assert!(matches!(var, Expr::Var("nan")), "{:?}", var);
+    }
+
+    #[test]
+    fn struct_invariant() {
+        test_parser!(struct_decl("struct Percent { value: int }") -> ok);
+        test_parser!(struct_decl("struct Percent { value: int } requires bool_and(value.gte(0), value.lte(100))") -> ok);
+        test_parser!(struct_decl("struct Percent {value: int} requires\nvalue.gte(0)") -> ok);
+
+        // An invariant must have an expression. The struct on its own still parses, so the dangling
+        // `requires` is only an error once the rest of the module fails to parse.
+        assert!(parse_module("struct Percent { value: int } requires").is_err());
+        assert!(parse_module("struct Percent { value: int } requires fn main() {}").is_err());
+
+        let (_, percent) = struct_decl("struct Percent { value: int } requires value.gte(0)").unwrap();
+        let invariant = percent.invariant.expect("invariant should have been parsed");
+        assert_eq!(invariant.name, INVARIANT_METHOD_NAME);
+        assert_eq!(invariant.sig.return_type, Ty::Named("bool"));
+        assert_eq!(invariant.sig.params, vec![FuncParam {name: "value", ty: Ty::Named("int")}]);
+    }
+
+    #[test]
+    fn struct_field_defaults() {
+        test_parser!(struct_decl("struct Config { retries: int = 3, verbose: bool = false }") -> ok);
+        test_parser!(struct_decl("struct Config { retries: int, verbose: bool=false, }") -> ok);
+
+        // A default must have an expression
+        test_parser!(struct_decl("struct Config { retries: int = }") -> err);
+
+        let (_, config) = struct_decl("struct Config { retries: int = 3, verbose: bool }").unwrap();
+        assert_eq!(config.fields[0].default, Some(Expr::IntegerLiteral(IntegerLiteral {value: 3, type_hint: None})));
+        assert_eq!(config.fields[1].default, None);
+    }
+
+    #[test]
+    fn never_return_type() {
+        let func = function(FuncType::Function);
+        test_parser!(func("fn fail() -> ! {}") -> ok);
+        test_parser!(func("fn fail(message: bstr) -> ! { panic(message); }") -> ok);
+
+        // The never type is only allowed as a return type
+        test_parser!(func("fn fail(x: !) {}") -> err);
+        test_parser!(func("fn fail() -> !! {}") -> err);
+    }
+
+    #[test]
+    fn break_and_continue() {
+        test_parser!(block("{ while true { break; } }") -> ok);
+        test_parser!(block("{ while true { continue; } }") -> ok);
+        test_parser!(stmt("break ;") -> ok);
+        // The semi-colon is optional at the end of a block
+        test_parser!(block("{ while true { if done { break } } }") -> ok);
+        test_parser!(block("{ while true { if done { continue } else { break } } }") -> ok);
+
+        let (_, body) = block("{ if done { break } }").unwrap();
+        match body.ret {
+            Some(Expr::Cond(cond)) => {
+                let (_, if_body) = &cond.conds[0];
+                assert_eq!(if_body.stmts, vec![Stmt::Break]);
+                assert_eq!(if_body.ret, None);
+            },
+            value => panic!("expected a conditional, got: {:?}", value),
+        }
+
+        // Identifiers that only start with a keyword are not `break` or `continue`
+        test_parser!(block("{ breakfast }") -> ok);
+        test_parser!(stmt("break x;") -> err);
+        test_parser!(stmt("continue 1") -> err);
+    }
+
+    #[test]
+    fn nested_blocks() {
+        test_parser!(stmt("{ let x = 2; print_int(x); }") -> ok);
+        test_parser!(stmt("{}") -> ok);
+        test_parser!(block("{ let x = 1; { let x = 2; } { { x } } }") -> ok);
+
+        // A nested block at the end of a block is never used as its return expression
+        let (_, body) = block("{ let x = 1; { x } }").unwrap();
+        assert_eq!(body.ret, None);
+        assert!(matches!(body.stmts.last(), Some(Stmt::Block(_))));
+    }
+
+    #[test]
+    fn statement_lines() {
+        let input = "fn main() {\n    let x = 1;\n\n    print_int(x); print_int(x);\n    if x > 0 {\n        print_int(x);\n    }\n}\n";
+        let module = parse_module(input).unwrap();
+        let body = match &module.decls[..] {
+            [Decl::Function(func)] => &func.body,
+            decls => panic!("expected a single function, got: {:?}", decls),
+        };
+        // The conditional at the end of the block becomes its return expression, but keeps its line
+        assert!(body.ret.is_some());
+        assert_eq!(body.stmt_lines, vec![2, 4, 4, 5]);
+        assert_eq!(body.stmts.len() + 1, body.stmt_lines.len());
+    }
+
+    #[test]
+    fn complex_number_literals() {
+        assert_eq!(expr("3i").unwrap().1, Expr::ComplexLiteral(3.0));
+        assert_eq!(expr("2.5j").unwrap().1, Expr::ComplexLiteral(2.5));
+        assert_eq!(expr("1e3i").unwrap().1, Expr::ComplexLiteral(1e3));
+        assert_eq!(expr("-2i").unwrap().1, Expr::ComplexLiteral(-2.0));
+
+        // A real part and an imaginary part are combined into a single complex number
+        assert_eq!(expr("4.5 - 5i").unwrap().1, Expr::Call(CallExpr {
+            func_name: IdentPath::from("sub_real_complex"),
+            args: vec![Expr::RealLiteral(4.5), Expr::ComplexLiteral(5.0)],
+            named_args: Vec::new(),
+        }));
+        assert_eq!(expr("-1 + 2i").unwrap().1, Expr::Call(CallExpr {
+            func_name: IdentPath::from("add_real_complex"),
+            args: vec![
+                Expr::IntegerLiteral(IntegerLiteral {value: -1, type_hint: None}),
+                Expr::ComplexLiteral(2.0),
+            ],
+            named_args: Vec::new(),
+        }));
+
+        // Explicitly typed integers are not real numbers
+        match expr("1int + 2i").unwrap().1 {
+            Expr::MethodCall(call) => assert_eq!(call.method_name, "add"),
+            value => panic!("expected a method call, got: {:?}", value),
+        }
+    }
+
+    #[test]
+    fn compound_assign_parser() {
+        test_parser!(expr("x += 1") -> ok);
+        test_parser!(expr("self.count-=a.b") -> ok);
+        test_parser!(expr("p.pos.x *= 2") -> ok);
+
+        // `x op= value` becomes `x = x.method(value)`
+        for &(op, method) in &[("+=", "add"), ("-=", "sub"), ("*=", "mul"), ("/=", "div"), ("%=", "rem")] {
+            let source = format!("x {} 2", op);
+            assert_eq!(expr(&source).unwrap().1, Expr::VarAssign(Box::new(VarAssign {
+                lhs: LValueExpr::Var("x"),
+                expr: Expr::MethodCall(Box::new(MethodCall {
+                    lhs: Expr::Var("x"),
+                    method_name: method,
+                    args: vec![Expr::IntegerLiteral(IntegerLiteral {value: 2, type_hint: None})],
+                })),
+            })));
+        }
+
+        // The left-hand side is evaluated twice, so it must not have any side effects unless the
+        // assignment is a statement
+        test_parser!(compound_assign("next_point().x += 1") -> err);
+        test_parser!(compound_assign("points.next().x += 1") -> err);
+
+        // In statement position, the value whose field is assigned to is only evaluated once
+        let base_decl = Stmt::VarDecl(VarDecl {
+            ident: COMPOUND_BASE_VAR,
+            ty: None,
+            expr: Expr::Call(CallExpr {
+                func_name: IdentPath::from("next_point"),
+                args: Vec::new(),
+                named_args: Vec::new(),
+            }),
+        });
+        let access = FieldAccess {lhs: Expr::Var(COMPOUND_BASE_VAR), field: "x"};
+        let assign = Stmt::Expr(Expr::VarAssign(Box::new(VarAssign {
+            lhs: LValueExpr::FieldAccess(access.clone()),
+            expr: Expr::MethodCall(Box::new(MethodCall {
+                lhs: Expr::FieldAccess(Box::new(access)),
+                method_name: "add",
+                args: vec![Expr::IntegerLiteral(IntegerLiteral {value: 1, type_hint: None})],
+            })),
+        })));
+        match stmt("next_point().x += 1;").unwrap().1 {
+            Stmt::Block(block) => assert_eq!(block.stmts, vec![base_decl, assign]),
+            stmt => panic!("expected a block, got: {:?}", stmt),
+        }
+        test_parser!(stmt("points.next().x += 1;") -> ok);
+    }
+
+    #[test]
+    fn offset_of_parser() {
+        test_parser!(offset_of("offset_of(Point, x)") -> ok);
+        test_parser!(offset_of("offset_of( Self , y , )") -> ok);
+
+        // Only a type and a field name are allowed
+        test_parser!(offset_of("offset_of(Point)") -> err);
+        test_parser!(offset_of("offset_of(Point, x, y)") -> err);
+        test_parser!(offset_of("offset_of(Point, p.x)") -> err);
+        // Function calls that only start with `offset_of` are not the builtin
+        test_parser!(offset_of("offset_of_x(Point, x)") -> err);
+    }
+
+    #[test]
+    fn assoc_const_parser() {
+        test_parser!(assoc_const("const MAX: int = 9223372036854775807;") -> ok);
+        test_parser!(assoc_const("const\nORIGIN :Self= 0 ;") -> ok);
+        // The type and the value are both required
+        test_parser!(assoc_const("const MAX = 1;") -> err);
+        test_parser!(assoc_const("const MAX: int;") -> err);
+
+        let (_, block) = impl_block("impl int { const ZERO: int = 0; fn one(self) -> int { 1 } }").unwrap();
+        assert_eq!(block.consts.len(), 1);
+        assert_eq!(block.consts[0].name, "ZERO");
+        assert_eq!(block.methods.len(), 1);
+
+        assert_eq!(expr("int::MAX").unwrap().1, Expr::AssocConst(AssocConstPath {
+            self_ty: Ty::Named("int"),
+            name: "MAX",
+        }));
+        assert_eq!(expr("Self :: MAX").unwrap().1, Expr::AssocConst(AssocConstPath {
+            self_ty: Ty::SelfType,
+            name: "MAX",
+        }));
+        // Calls to associated functions are still parsed as calls
+        assert!(matches!(expr("Point::new()").unwrap().1, Expr::Call(_)));
+    }
+
+    #[test]
+    fn bstr_literals() {
+        assert_eq!(bstr_literal(r#"b"""#).unwrap().1, b"");
+        assert_eq!(bstr_literal(r#"b"line1\nline2\t\"quoted\"""#).unwrap().1,
+            b"line1\nline2\t\"quoted\"");
+        assert_eq!(bstr_literal(r#"b"\r\\\0\'""#).unwrap().1, b"\r\\\0'");
+        // Byte escapes can produce bytes that are not valid UTF-8
+        assert_eq!(bstr_literal(r#"b"\x00\x7f\xFF""#).unwrap().1, b"\x00\x7f\xff");
+
+        test_parser!(bstr_literal(r#"b"abc"#) -> err);
+        test_parser!(bstr_literal(r#"b"\q""#) -> err);
+        test_parser!(bstr_literal(r#"b"\x4""#) -> err);
+        test_parser!(bstr_literal(r#"b"\xG0""#) -> err);
+    }
+
+    #[test]
+    fn module_error_recovery() {
+        let input = "fn main() {
+    let x = ;
+    print_int(x);
+    let y = 2 3;
+}
+
+fn broken(x int) {
+}
+
+fn ok() {}
+";
+        let errors = parse_module(input).unwrap_err();
+        let lines: Vec<_> = errors.iter().map(|err| match err {
+            &Error::InvalidSyntax {span, line, ..} => {
+                assert!(span.end <= input.len());
+                line
+            },
+        }).collect();
+        assert_eq!(lines, vec![2, 4, 7]);
+
+        // The line with the error is shown with the offending input underlined
+        let message = errors[2].to_string();
+        assert_eq!(message.lines().skip(1).collect::<Vec<_>>(),
+            vec!["fn broken(x int) {", "          ^"]);
+
+        // Recovered errors from a previous parse are not reported again
+        assert!(parse_module("fn main() {}").is_ok());
+    }
 }
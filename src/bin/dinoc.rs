@@ -1,25 +1,18 @@
 use std::env;
-use std::io::Write;
-use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::process::{self, Command};
+use std::process;
 use std::str::FromStr;
 
-use tempfile::TempDir;
 use structopt::StructOpt;
 use termcolor::ColorChoice;
 use parking_lot::RwLock;
 
 use dino::{
-    cprintln,
-    cwriteln,
     source_files::SourceFiles,
     diagnostics::Diagnostics,
-    parser,
-    desugar::Desugar,
-    cgenir::{self, GenerateC},
-    cir::CSymbols,
+    trans::CodegenOptions,
+    cc::{self, AsmSyntax},
 };
 
 /// A command line argument that configures the coloring of the output
@@ -66,6 +59,25 @@ struct CompilerOptions {
     /// Write output to <file>
     #[structopt(short = "o", name = "file")]
     output_path: Option<PathBuf>,
+    /// Write a make-compatible list of the source files that the output depends on to <depfile>
+    #[structopt(long = "dep-file", name = "depfile", parse(from_os_str))]
+    dep_file_path: Option<PathBuf>,
+    /// Write the C symbol generated for each item in the program to <symbol-map>
+    #[structopt(long = "symbol-map", name = "symbol-map", parse(from_os_str))]
+    symbol_map_path: Option<PathBuf>,
+    /// Build a test harness that runs every function marked with `#[test]` instead of `main`
+    #[structopt(long = "test")]
+    test: bool,
+    /// Write the generated assembly instead of an executable
+    #[structopt(short = "S", conflicts_with = "emit-obj")]
+    emit_asm: bool,
+    /// Write an object file that has not been linked instead of an executable
+    #[structopt(short = "c")]
+    emit_obj: bool,
+    /// The syntax of the assembly written with -S
+    #[structopt(long = "asm-syntax", parse(try_from_str), default_value = "att",
+        possible_values = AsmSyntax::VARIANTS, case_insensitive = true)]
+    asm_syntax: AsmSyntax,
     /// Configure coloring of output
     #[structopt(long = "color", parse(try_from_str), default_value = "auto",
         possible_values = ColorArg::VARIANTS, case_insensitive = true)]
@@ -81,19 +93,18 @@ macro_rules! quit {
     };
 }
 
-macro_rules! check_errors {
-    ($diag:expr) => {
-        let diag = $diag;
-        match diag.emitted_errors() {
-            0 => {},
-            1 => quit!(diag, "aborting due to 1 previous error"),
-            errors => quit!(diag, "aborting due to {} previous errors", errors),
-        }
-    };
-}
-
 fn main() {
-    let CompilerOptions {program_path, output_path, color} = CompilerOptions::from_args();
+    let CompilerOptions {
+        program_path,
+        output_path,
+        dep_file_path,
+        symbol_map_path,
+        test,
+        emit_asm,
+        emit_obj,
+        asm_syntax,
+        color,
+    } = CompilerOptions::from_args();
 
     let source_files = Arc::new(RwLock::new(SourceFiles::default()));
     let diag = Diagnostics::new(source_files.clone(), color.into());
@@ -104,9 +115,17 @@ fn main() {
         _ => quit!(&diag, "Invalid input path. Must use extension `dino`"),
     };
 
-    // Default output path is the input path without its stem
+    // Default output path is the input path without its stem (or with the extension `s` for
+    // assembly and `o` for an object file)
+    let default_output_path = if emit_asm {
+        Path::new(program_stem).with_extension("s")
+    } else if emit_obj {
+        Path::new(program_stem).with_extension("o")
+    } else {
+        PathBuf::from(program_stem)
+    };
     let output_path = output_path.as_ref().map(|p| p.as_path())
-        .unwrap_or_else(|| Path::new(program_stem));
+        .unwrap_or_else(|| default_output_path.as_path());
     // Append the current directory to the output path if necessary
     let output_path = if output_path.is_absolute() {
         output_path.to_path_buf()
@@ -116,90 +135,43 @@ fn main() {
         current_dir.join(output_path)
     };
 
-    let root_file = source_files.write().add_file(&program_path)
-        .unwrap_or_else(|err| quit!(&diag, "Could not read source file `{}`: {}", program_path.display(), err));
-    let program = {
-        // New scope because we want to drop this lock guard as soon as possible
-        let files = source_files.read();
-        parser::parse_module(files.source(root_file), &diag)
+    let codegen_options = CodegenOptions::default();
+    let compiled = if test {
+        dino::compile_tests2(&program_path, &codegen_options)
+    } else {
+        dino::compile_executable2(&program_path, &codegen_options)
     };
-    check_errors!(&diag);
-    let desugared_program = program.desugar(&diag);
-    check_errors!(&diag);
-
-    let csyms = CSymbols::default();
-
-    //TODO: Replace this with actual code generation once the compiler stages are implemented
-    let cgen_program = cgenir::Program {
-        structs: Vec::new(),
-        functions: Vec::new(),
-        entry_point: Some(Vec::new()),
+    let program = match compiled {
+        Ok(program) => program,
+        Err(err) => {
+            // Compile errors are printed as-is so the output can be checked by the compile-fail tests
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        },
     };
-    let cprogram = cgen_program.to_c();
-
-    //TODO: Add proper logging
-    //TODO: Add support for outputing the generated C code (CLI flag)
-    cprintln!(&csyms, "{}", cprogram);
-
-    // Write the generated code to a temporary file so we can run it through a C compiler
-    let tmp_dir = TempDir::new()
-        .unwrap_or_else(|err| quit!(&diag, "Unable to create temporary directory: {}", err));
-
-    // Write out the runtime and std libraries and associated header files
-    dino::gc_lib::write_gc_lib_files(tmp_dir.path())
-        .unwrap_or_else(|err| quit!(&diag, "Unable to write GC runtime: {}", err));
-    dino::runtime::write_runtime_files(tmp_dir.path())
-        .unwrap_or_else(|err| quit!(&diag, "Unable to write dino runtime: {}", err));
-    dino::dino_std::write_std_files(tmp_dir.path())
-        .unwrap_or_else(|err| quit!(&diag, "Unable to write std library: {}", err));
-
-    let code_file_path = tmp_dir.path().join("main.c");
-    // Drop the file as soon as possible so it finishes being written to
-    // and so it is closed when we close the temporary directory
-    {
-        let mut code_file = File::create(&code_file_path)
-            .unwrap_or_else(|err| quit!(&diag, "Unable to create file `{}` for generated code: {}", code_file_path.display(), err));
-        cwriteln!(code_file, &csyms, "{}", cprogram)
-            .unwrap_or_else(|err| quit!(&diag, "Unable to write generated code: {}", err));
+    for warning in &program.warnings {
+        eprintln!("Warning: {}", warning);
     }
 
-    // Enabling all the warnings and making them an error because this compiler should never get to
-    // this point if it can't produce completely valid C.
-    // See: https://gcc.gnu.org/onlinedocs/gcc/Warning-Options.html
-    //  Or: https://clang.llvm.org/docs/DiagnosticsReference.html
-    let warning_flags = &["-Werror", "-Wall", "-Wextra", "-Wformat=2", "-Wshadow",
-        "-Wpointer-arith", "-Wcast-qual", "-Wno-unused-variable", "-Wno-unused-parameter",
-        "-Wno-unused-value"];
-    // Run the C compiler and copy the result back
-    let status = Command::new("clang")
-        .current_dir(tmp_dir.path())
-        .arg("-std=c99")
-        // Maximum optimization level
-        .arg("-O3")
-        .args(warning_flags)
-        .arg("-g")
-        .arg(code_file_path)
-        // Must link AFTER source code or else the linker will discard all the symbols
-        // These must be linked in *reverse* dependency order
-        .arg(format!("-l{}", dino::dino_std::DINO_STD_LIB_NAME))
-        .arg(format!("-l{}", dino::runtime::RUNTIME_LIB_NAME))
-        .arg(format!("-l{}", dino::gc_lib::GC_LIB_LIB_NAME))
-        .arg("-lpthread")
-        // Search for libraries in the current directory (the temp dir)
-        .arg("-L.")
-        .arg("-o")
-        .arg(output_path)
-        .status()
-        .unwrap_or_else(|err| quit!(&diag, "Failed to run clang: {}", err));
-
-    if !status.success() {
-        panic!("bug: code generation failed");
+    let code = &program.code;
+    let written = if emit_asm {
+        cc::compile_asm(code, &output_path, asm_syntax)
+    } else if emit_obj {
+        cc::compile_object(code, &output_path)
+    } else {
+        cc::compile_executable(code, &output_path)
+    };
+    written.unwrap_or_else(|err| quit!(&diag, "{}", err));
+
+    // Only write out the dependencies once the output has been successfully produced so that
+    // build tools never see a dependency list for an output that does not exist
+    if let Some(dep_file_path) = dep_file_path {
+        dino::write_dep_file(&dep_file_path, &output_path, &program.source_paths)
+            .unwrap_or_else(|err| quit!(&diag, "Unable to write dependency file `{}`: {}", dep_file_path.display(), err));
     }
 
-    // By closing the `TempDir` explicitly we can check that it has been deleted successfully. If
-    // we don't close it explicitly, the directory will still be deleted when `tmp_dir` goes out of
-    // scope, but we won't know whether deleting the directory succeeded.
-    // IMPORTANT: All handles to files in this directory must be closed at this point.
-    tmp_dir.close()
-        .unwrap_or_else(|err| quit!(&diag, "Failed remove temporary directory: {}", err));
+    if let Some(symbol_map_path) = symbol_map_path {
+        dino::write_symbol_map(&symbol_map_path, &program.code)
+            .unwrap_or_else(|err| quit!(&diag, "Unable to write symbol map `{}`: {}", symbol_map_path.display(), err));
+    }
 }
@@ -0,0 +1,174 @@
+//! Runs the C compiler on generated code to produce an executable, an object file, or assembly
+
+use std::io;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::str::FromStr;
+
+use snafu::{Snafu, ResultExt};
+use tempfile::TempDir;
+
+use crate::codegen::CExecutableProgram;
+
+/// The C compiler used to compile the generated code
+pub const C_COMPILER: &str = "clang";
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Unable to create temporary directory: {}", source))]
+    CreateTempDir {
+        source: io::Error,
+    },
+    #[snafu(display("Unable to write '{}': {}", path.display(), source))]
+    WriteFile {
+        path: PathBuf,
+        source: io::Error,
+    },
+    #[snafu(display("Failed to run {}: {}", C_COMPILER, source))]
+    RunCompiler {
+        source: io::Error,
+    },
+    #[snafu(display("bug: the C compiler failed to compile the generated code ({})", status))]
+    CompilerFailed {
+        status: ExitStatus,
+    },
+    #[snafu(display("Failed to remove temporary directory: {}", source))]
+    RemoveTempDir {
+        source: io::Error,
+    },
+}
+
+/// The syntax of the generated assembly
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AsmSyntax {
+    Att,
+    Intel,
+}
+
+impl AsmSyntax {
+    /// Allowed values when parsing this from a string
+    pub const VARIANTS: &'static [&'static str] = &["att", "intel"];
+
+    /// The value of the C compiler's `-masm` flag that selects this syntax
+    pub fn masm_flag(self) -> &'static str {
+        match self {
+            AsmSyntax::Att => "-masm=att",
+            AsmSyntax::Intel => "-masm=intel",
+        }
+    }
+}
+
+impl FromStr for AsmSyntax {
+    type Err = &'static str;
+
+    fn from_str(src: &str) -> Result<AsmSyntax, &'static str> {
+        match src {
+            _ if src.eq_ignore_ascii_case("att") => Ok(AsmSyntax::Att),
+            _ if src.eq_ignore_ascii_case("intel") => Ok(AsmSyntax::Intel),
+            _ => Err("valid values: att, intel"),
+        }
+    }
+}
+
+/// Compiles and links the given code into an executable at `output_path`
+///
+/// The runtime, std library, and garbage collector are linked into the executable.
+pub fn compile_executable(code: &CExecutableProgram, output_path: &Path) -> Result<(), Error> {
+    run_c_compiler(code, output_path, |cc| {
+        // Must link AFTER source code or else the linker will discard all the symbols
+        // These must be linked in *reverse* dependency order
+        cc.arg(format!("-l{}", crate::dino_std::DINO_STD_LIB_NAME))
+            .arg(format!("-l{}", crate::runtime::RUNTIME_LIB_NAME))
+            .arg(format!("-l{}", crate::gc_lib::GC_LIB_LIB_NAME))
+            .arg("-lpthread")
+            // The std library uses the C math library for functions like `real_sqrt`
+            .arg("-lm")
+            // Search for libraries in the current directory (the temp dir)
+            .arg("-L.");
+
+        // Only keep the parts of the runtime and std library that are reachable from the entry
+        // point. The Rust libraries are already compiled with each function in its own section,
+        // so the linker can drop every function that the program never calls. The generated code
+        // is compiled the same way so unused generated functions are dropped too.
+        cc.arg("-ffunction-sections").arg("-fdata-sections");
+        if cfg!(target_os = "macos") {
+            cc.arg("-Wl,-dead_strip");
+        } else {
+            cc.arg("-Wl,--gc-sections");
+        }
+    })
+}
+
+/// Compiles the given code into an object file at `output_path` without linking it
+///
+/// The object file must be linked with the runtime, std library, and garbage collector before it
+/// can be run.
+pub fn compile_object(code: &CExecutableProgram, output_path: &Path) -> Result<(), Error> {
+    run_c_compiler(code, output_path, |cc| {
+        cc.arg("-c");
+    })
+}
+
+/// Compiles the given code into assembly with the given syntax and writes it to `output_path`
+///
+/// Only the generated code is compiled, so the assembly does not include the runtime, std
+/// library, or garbage collector.
+pub fn compile_asm(code: &CExecutableProgram, output_path: &Path, syntax: AsmSyntax) -> Result<(), Error> {
+    run_c_compiler(code, output_path, |cc| {
+        cc.arg("-S").arg(syntax.masm_flag());
+    })
+}
+
+/// Writes the given code to a temporary directory and runs the C compiler on it
+///
+/// The `configure` function adds the arguments that select what the C compiler produces.
+/// `output_path` should be absolute since the C compiler runs in the temporary directory.
+fn run_c_compiler(
+    code: &CExecutableProgram,
+    output_path: &Path,
+    configure: impl FnOnce(&mut Command),
+) -> Result<(), Error> {
+    let tmp_dir = TempDir::new().context(CreateTempDir)?;
+    let tmp_path = tmp_dir.path();
+
+    // Write out the runtime and std libraries and associated header files
+    crate::gc_lib::write_gc_lib_files(tmp_path).context(WriteFile {path: tmp_path})?;
+    crate::runtime::write_runtime_files(tmp_path).context(WriteFile {path: tmp_path})?;
+    crate::dino_std::write_std_files(tmp_path).context(WriteFile {path: tmp_path})?;
+
+    let code_file_path = tmp_path.join("main.c");
+    fs::write(&code_file_path, code.to_string()).context(WriteFile {path: &code_file_path})?;
+
+    // Enabling all the warnings and making them an error because this compiler should never get to
+    // this point if it can't produce completely valid C.
+    // See: https://gcc.gnu.org/onlinedocs/gcc/Warning-Options.html
+    //  Or: https://clang.llvm.org/docs/DiagnosticsReference.html
+    let warning_flags = &["-Werror", "-Wall", "-Wextra", "-Wformat=2", "-Wshadow",
+        "-Wpointer-arith", "-Wcast-qual", "-Wno-unused-variable", "-Wno-unused-parameter",
+        "-Wno-unused-value"];
+
+    let mut cc = Command::new(C_COMPILER);
+    cc.current_dir(tmp_path)
+        .arg("-std=c99")
+        // Maximum optimization level
+        .arg("-O3")
+        .args(warning_flags)
+        .arg("-g")
+        .arg(&code_file_path);
+    configure(&mut cc);
+    let status = cc
+        .arg("-o")
+        .arg(output_path)
+        .status()
+        .context(RunCompiler)?;
+
+    if !status.success() {
+        return Err(Error::CompilerFailed {status});
+    }
+
+    // By closing the `TempDir` explicitly we can check that it has been deleted successfully. If
+    // we don't close it explicitly, the directory will still be deleted when `tmp_dir` goes out of
+    // scope, but we won't know whether deleting the directory succeeded.
+    tmp_dir.close().context(RemoveTempDir)
+}
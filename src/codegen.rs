@@ -27,13 +27,15 @@ impl fmt::Display for CStruct {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let Self {mangled_name, fields} = self;
 
-        writeln!(f, "typedef struct {{")?;
+        // The typedef for the struct is forward declared separately so that structs can refer to
+        // each other (or themselves) regardless of the order they are written out in
+        writeln!(f, "struct {} {{", mangled_name)?;
 
         for field in fields {
-            writeln!(f, "{},", field)?;
+            writeln!(f, "{};", field)?;
         }
 
-        write!(f, "}} {}", mangled_name)
+        write!(f, "}};")
     }
 }
 
@@ -51,7 +53,41 @@ pub struct CStructField {
 impl fmt::Display for CStructField {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let Self {mangled_name, ty} = self;
-        write!(f, "{}: {}", mangled_name, ty)
+        write!(f, "{} {}", ty, mangled_name)
+    }
+}
+
+/// A static null-terminated byte string constant
+#[derive(Debug)]
+pub struct CStrConstant {
+    /// The mangled name of the constant
+    pub mangled_name: String,
+    /// The bytes of the string. The data is allowed to contain null characters.
+    pub data: Vec<u8>,
+}
+
+impl fmt::Display for CStrConstant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {mangled_name, data} = self;
+        write!(f, "static const unsigned char {}[] = ", mangled_name)?;
+        write_nt_str_data(f, data)?;
+        write!(f, ";")
+    }
+}
+
+/// A global variable, initialized to NULL until the entry point assigns its initial value
+#[derive(Debug)]
+pub struct CGlobalVar {
+    /// The mangled name of the global variable
+    pub mangled_name: String,
+    /// The type of the global variable
+    pub ty: CTy,
+}
+
+impl fmt::Display for CGlobalVar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {mangled_name, ty} = self;
+        write!(f, "static {} {};", ty, mangled_name)
     }
 }
 
@@ -59,12 +95,25 @@ impl fmt::Display for CStructField {
 pub struct CExecutableProgram {
     /// The structs generated for the program
     pub structs: Vec<CStruct>,
+    /// The byte string constants used throughout the program
+    ///
+    /// Each of these MUST have a unique name
+    pub str_constants: Vec<CStrConstant>,
+    /// The global variables of the program
+    ///
+    /// Each of these MUST have a unique name
+    pub globals: Vec<CGlobalVar>,
     /// The list of functions, not including the entry point
     ///
     /// Each of these MUST have a unique name
     pub functions: Vec<CFunction>,
     /// The entry point ("main") function of the program
     pub entry_point: CEntryPoint,
+    /// The C symbol generated for each item in the program
+    ///
+    /// This is not part of the generated code. It can be written to a separate file to find the
+    /// generated code for a given item when debugging or calling into the program from C.
+    pub symbols: CSymbolMap,
 }
 
 impl fmt::Display for CExecutableProgram {
@@ -73,12 +122,23 @@ impl fmt::Display for CExecutableProgram {
         writeln!(f, "#include \"{}\"", RUNTIME_HEADER_FILENAME)?;
         writeln!(f, "#include \"{}\"\n", DINO_STD_HEADER_FILENAME)?;
 
-        let Self {structs, functions, entry_point} = self;
+        let Self {structs, str_constants, globals, functions, entry_point, symbols: _} = self;
 
+        for struct_decl in structs {
+            writeln!(f, "typedef struct {0} {0};", struct_decl.mangled_name)?;
+        }
         for struct_decl in structs {
             writeln!(f, "{}", struct_decl)?;
         }
 
+        for constant in str_constants {
+            writeln!(f, "{}", constant)?;
+        }
+
+        for global in globals {
+            writeln!(f, "{}", global)?;
+        }
+
         // Output forward declarations so we don't have to worry about outputting the functions in
         // a specific order
         for func in functions {
@@ -97,6 +157,67 @@ impl fmt::Display for CExecutableProgram {
     }
 }
 
+/// The kind of item that a symbol was generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CSymbolKind {
+    Struct,
+    Function,
+    Method,
+    Static,
+}
+
+impl fmt::Display for CSymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use CSymbolKind::*;
+        match self {
+            Struct => write!(f, "struct"),
+            Function => write!(f, "fn"),
+            Method => write!(f, "method"),
+            Static => write!(f, "static"),
+        }
+    }
+}
+
+/// An item in a program and the name of the C symbol generated for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CSymbol {
+    pub kind: CSymbolKind,
+    /// The path to the item as it is written in the program (e.g. `Point::new` or `math::sqrt`)
+    pub name: String,
+    pub mangled_name: String,
+}
+
+/// Maps the items of a program to the C symbols generated for them
+///
+/// Written as one line per item with the kind, name, and mangled name separated by tabs.
+#[derive(Debug, Default)]
+pub struct CSymbolMap {
+    pub symbols: Vec<CSymbol>,
+}
+
+impl CSymbolMap {
+    pub fn insert(&mut self, kind: CSymbolKind, name: String, mangled_name: String) {
+        self.symbols.push(CSymbol {kind, name, mangled_name});
+    }
+
+    /// Returns the mangled name of the item with the given name, if any
+    pub fn mangled_name(&self, name: &str) -> Option<&str> {
+        self.symbols.iter()
+            .find(|sym| sym.name == name)
+            .map(|sym| sym.mangled_name.as_str())
+    }
+}
+
+impl fmt::Display for CSymbolMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for CSymbol {kind, name, mangled_name} in &self.symbols {
+            writeln!(f, "{}\t{}\t{}", kind, name, mangled_name)?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Special wrapper for the entry point ("main") function. Deals with properly returning an integer
 #[derive(Debug)]
 pub struct CEntryPoint {
@@ -223,8 +344,12 @@ pub enum CStmt {
     Cond(CCond),
     /// An infinite loop
     Loop(CInfiniteLoop),
-    /// The only way to stop a loop
+    /// Exits a loop
     BreakLoop,
+    /// Skips to the next iteration of a loop
+    ContinueLoop,
+    /// A nested block of statements
+    Block(CStmts),
     /// A variable assignment of the form `var-name = value-expr;`
     VarAssign(CVarAssign),
     /// A variable declaration of the form `type-name var-name = value-expr;`
@@ -236,6 +361,15 @@ pub enum CStmt {
     /// A `return expr;` statement. The expression is not optional because every function at least
     /// returns unit.
     Return(CExpr),
+    /// Raw C code provided by the user that is emitted verbatim. This code is never checked.
+    Raw(String),
+    /// A `#line` directive that makes the C compiler (and any debugger) report the code that
+    /// follows as coming from the given line of the given file
+    Line {
+        /// The line number (starting at 1)
+        line: usize,
+        path: String,
+    },
 }
 
 impl fmt::Display for CStmt {
@@ -245,11 +379,32 @@ impl fmt::Display for CStmt {
             Cond(cond) => write!(f, "{}", cond),
             Loop(iloop) => write!(f, "{}", iloop),
             BreakLoop => write!(f, "break;"),
+            ContinueLoop => write!(f, "continue;"),
+            Block(stmts) => {
+                writeln!(f, "{{")?;
+                writeln!(f, "{}", stmts)?;
+                write!(f, "}}")
+            },
             VarAssign(var_assign) => write!(f, "{};", var_assign),
             VarDecl(var_decl) => write!(f, "{}", var_decl),
             TempVarDecl(temp_var_decl) => write!(f, "{}", temp_var_decl),
             Expr(expr) => write!(f, "{};", expr),
             Return(expr) => write!(f, "return {};", expr),
+            Raw(code) => {
+                writeln!(f, "/* UNCHECKED: raw C code from an `extern \"C\"` function */")?;
+                write!(f, "{}", code)
+            },
+            Line {line, path} => {
+                // Only backslashes and double quotes need to be escaped in the file name
+                write!(f, "#line {} \"", line)?;
+                for ch in path.chars() {
+                    if ch == '\\' || ch == '"' {
+                        write!(f, "\\")?;
+                    }
+                    write!(f, "{}", ch)?;
+                }
+                write!(f, "\"")
+            },
         }
     }
 }
@@ -408,6 +563,8 @@ pub enum CExpr {
     DoubleLiteral(f64),
     BoolLiteral(bool),
     Var(String),
+    /// The size of the given type in bytes
+    Sizeof(CTy),
 }
 
 impl fmt::Display for CExpr {
@@ -417,18 +574,8 @@ impl fmt::Display for CExpr {
             Call(call) => write!(f, "{}", call),
             FieldAccess(access) => write!(f, "{}", access),
             NTStrLiteral(data) => {
-                write!(f, "(const unsigned char *)\"")?;
-                for &ch in data {
-                    match ch {
-                        b'\\' => write!(f, "\\\\")?,
-                        b'"' => write!(f, "\\\"")?,
-                        b'\n' => write!(f, "\\n")?,
-                        b'\r' => write!(f, "\\r")?,
-                        b'\t' => write!(f, "\\t")?,
-                        _ => write!(f, "{}", ch as char)?,
-                    }
-                }
-                write!(f, "\"")
+                write!(f, "(const unsigned char *)")?;
+                write_nt_str_data(f, data)
             },
             // Since DInt is 64-bits, we need the LL suffix or the literal is not 64-bits wide.
             // https://en.cppreference.com/w/c/language/integer_constant
@@ -436,8 +583,29 @@ impl fmt::Display for CExpr {
             DoubleLiteral(value) => write!(f, "{}", value),
             BoolLiteral(value) => write!(f, "{}", value),
             Var(name) => write!(f, "{}", name),
+            Sizeof(ty) => write!(f, "sizeof({})", ty),
+        }
+    }
+}
+
+/// Writes the given data as a C string literal, escaping characters as necessary
+fn write_nt_str_data(f: &mut fmt::Formatter<'_>, data: &[u8]) -> fmt::Result {
+    write!(f, "\"")?;
+    for &ch in data {
+        match ch {
+            b'\\' => write!(f, "\\\\")?,
+            b'"' => write!(f, "\\\"")?,
+            b'\n' => write!(f, "\\n")?,
+            b'\r' => write!(f, "\\r")?,
+            b'\t' => write!(f, "\\t")?,
+            b' ' | b'!' ..= b'~' => write!(f, "{}", ch as char)?,
+            // Any other byte (e.g. null or a byte that is not ASCII) is written as an octal escape.
+            // Unlike hex escapes, octal escapes are at most three digits long, so they will never
+            // include any digits that come after them.
+            _ => write!(f, "\\{:03o}", ch)?,
         }
     }
+    write!(f, "\"")
 }
 
 /// A single if-else clause. Note that we are not using `else if` because it makes code generation
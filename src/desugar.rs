@@ -66,12 +66,13 @@ impl Desugar for ast::ImportPath {
     type Output = hir::ImportPath;
 
     fn desugar(&self, diag: &Diagnostics) -> Self::Output {
-        let Self {prefix, path, selection} = self;
+        let &Self {ref prefix, ref path, ref selection, is_pub} = self;
 
         hir::ImportPath {
             prefix: prefix.desugar(diag),
             path: path.desugar(diag),
             selection: selection.desugar(diag),
+            is_pub,
         }
     }
 }
@@ -204,10 +205,32 @@ impl Desugar for ast::Block {
     fn desugar(&self, diag: &Diagnostics) -> Self::Output {
         let &Self {ref decls, ref stmts, ref ret, span} = self;
 
+        // A for loop desugars into multiple statements, so it has to be handled here. The same
+        // goes for a compound assignment that needs to declare a hidden variable.
+        let mut stmts: Vec<_> = stmts.iter().flat_map(|stmt| match stmt {
+            ast::Stmt::ForLoop(for_loop) => for_loop.desugar(diag),
+            ast::Stmt::Expr(ast::Expr::CompoundAssign(assign)) => {
+                let (base_decl, assign) = assign.desugar(diag);
+                base_decl.map(hir::Stmt::VarDecl).into_iter()
+                    .chain(Some(hir::Stmt::Expr(hir::Expr::Assign(Box::new(assign)))))
+                    .collect()
+            },
+            stmt => vec![stmt.desugar(diag)],
+        }).collect();
+
+        let ret = match ret {
+            Some(ast::Expr::CompoundAssign(assign)) => {
+                let (base_decl, assign) = assign.desugar(diag);
+                stmts.extend(base_decl.map(hir::Stmt::VarDecl));
+                Some(hir::Expr::Assign(Box::new(assign)))
+            },
+            ret => ret.desugar(diag),
+        };
+
         hir::Block {
             decls: decls.desugar(diag),
-            stmts: stmts.desugar(diag),
-            ret: ret.desugar(diag),
+            stmts,
+            ret,
             span,
         }
     }
@@ -220,6 +243,7 @@ impl Desugar for ast::Stmt {
         match self {
             ast::Stmt::Cond(cond) => hir::Stmt::Cond(cond.desugar(diag)),
             ast::Stmt::WhileLoop(wloop) => hir::Stmt::WhileLoop(wloop.desugar(diag)),
+            ast::Stmt::ForLoop(_) => unreachable!("bug: for loops should be desugared by the block containing them"),
             ast::Stmt::VarDecl(var_decl) => hir::Stmt::VarDecl(var_decl.desugar(diag)),
             ast::Stmt::Expr(expr) => hir::Stmt::Expr(expr.desugar(diag)),
         }
@@ -239,6 +263,84 @@ impl Desugar for ast::WhileLoop {
     }
 }
 
+/// Desugars `for i in start..end { body }` into:
+///
+/// ```text
+/// let for$next@N = start;
+/// let for$end@N = end;
+/// while for$next@N < for$end@N {
+///     let i = for$next@N;
+///     for$next@N = for$next@N + 1;
+///     body
+/// }
+/// ```
+///
+/// Both `start` and `end` are evaluated exactly once, before the first iteration. The hidden
+/// variables are named after the position of the `for` keyword so that nested loops don't conflict
+/// and so that they can't be referred to by the user. Declaring the loop variable inside the body
+/// keeps it in scope only within the loop. The counter is incremented before the body runs so that
+/// `continue` can't skip the increment.
+impl Desugar for ast::ForLoop {
+    type Output = Vec<hir::Stmt>;
+
+    fn desugar(&self, diag: &Diagnostics) -> Self::Output {
+        let &Self {for_span, ref var, ref start, ref end, ref body} = self;
+
+        let hidden_var = |name: &str| hir::Ident {
+            value: format!("for${}@{}", name, for_span.start).into(),
+            span: for_span,
+        };
+        let hidden_path = |name: &str| hir::Expr::Path(hidden_var(name).into());
+        let method_call = |lhs: hir::Expr, method_name: &str, arg: hir::Expr| {
+            hir::Expr::MethodCall(Box::new(hir::MethodCall {
+                lhs,
+                method_name: hir::Ident {
+                    value: method_name.into(),
+                    span: for_span,
+                },
+                args: vec![arg],
+                span: for_span,
+            }))
+        };
+        let one = hir::Expr::IntegerLiteral(hir::IntegerLiteral {
+            value: 1,
+            suffix: None,
+            span: for_span,
+        });
+
+        let hir::Block {decls, stmts: body_stmts, ret, span} = body.desugar(diag);
+        let mut stmts = vec![
+            hir::Stmt::VarDecl(hir::VarDecl {
+                name: var.desugar(diag),
+                ty: None,
+                expr: hidden_path("next"),
+            }),
+            hir::Stmt::Expr(hir::Expr::Assign(Box::new(hir::Assign {
+                lvalue: hir::LValue::Path(hidden_var("next").into()),
+                expr: method_call(hidden_path("next"), "add", one),
+            }))),
+        ];
+        stmts.extend(body_stmts);
+
+        vec![
+            hir::Stmt::VarDecl(hir::VarDecl {
+                name: hidden_var("next"),
+                ty: None,
+                expr: start.desugar(diag),
+            }),
+            hir::Stmt::VarDecl(hir::VarDecl {
+                name: hidden_var("end"),
+                ty: None,
+                expr: end.desugar(diag),
+            }),
+            hir::Stmt::WhileLoop(hir::WhileLoop {
+                cond: method_call(hidden_path("next"), "lt", hidden_path("end")),
+                body: hir::Block {decls, stmts, ret, span},
+            }),
+        ]
+    }
+}
+
 impl Desugar for ast::VarDecl {
     type Output = hir::VarDecl;
 
@@ -259,6 +361,16 @@ impl Desugar for ast::Expr {
     fn desugar(&self, diag: &Diagnostics) -> Self::Output {
         match self {
             ast::Expr::Assign(assign) => hir::Expr::Assign(Box::new(assign.desugar(diag))),
+            ast::Expr::CompoundAssign(compound) => {
+                // Compound assignments that are statements or the final expression of a block are
+                // handled by the block, so the hidden variable can only be declared there
+                let (base_decl, assign) = compound.desugar(diag);
+                if base_decl.is_some() {
+                    diag.span_error(compound.span(), "compound assignment to a field of a temporary value must be a statement")
+                        .emit();
+                }
+                hir::Expr::Assign(Box::new(assign))
+            },
             ast::Expr::Range(range) => range.desugar(diag),
             ast::Expr::BoolOp(bin) => bin.desugar(diag),
             ast::Expr::CompareOp(bin) => bin.desugar(diag),
@@ -476,6 +588,67 @@ impl Desugar for ast::Assign {
     }
 }
 
+/// Desugars `lhs op= rhs` into `lhs = lhs op rhs` (e.g. `x += 1` becomes `x = x.add(1)`)
+///
+/// The left-hand side is evaluated twice: once to get its current value and once to assign to it.
+/// That is only correct if evaluating it has no side effects, which is the case for a variable,
+/// `self`, or a field access on those. For any other field access, the expression the field is
+/// accessed on is evaluated exactly once into a hidden variable, so `make_point().x += 1` becomes:
+///
+/// ```text
+/// let compound$base@N = make_point();
+/// compound$base@N.x = compound$base@N.x.add(1);
+/// ```
+///
+/// The hidden variable is returned separately since it must be declared in the enclosing block
+/// before the assignment.
+impl Desugar for ast::CompoundAssign {
+    type Output = (Option<hir::VarDecl>, hir::Assign);
+
+    fn desugar(&self, diag: &Diagnostics) -> Self::Output {
+        let &Self {ref lhs, op, ref rhs} = self;
+
+        let (base_decl, lhs) = match lhs {
+            ast::Expr::FieldAccess(access) if !is_place_expr(&access.lhs) => {
+                let base_var = hir::Ident {
+                    value: format!("compound$base@{}", op.span().start).into(),
+                    span: access.lhs.span(),
+                };
+                let base_decl = hir::VarDecl {
+                    name: base_var.clone(),
+                    ty: None,
+                    expr: access.lhs.desugar(diag),
+                };
+
+                let lhs = ast::Expr::FieldAccess(Box::new(ast::FieldAccess {
+                    lhs: ast::Expr::Path(base_var.into()),
+                    field: access.field.clone(),
+                }));
+                (Some(base_decl), lhs)
+            },
+            lhs => (None, lhs.clone()),
+        };
+
+        let assign = ast::Assign {
+            rhs: ast::Expr::NumericOp(Box::new(ast::Binary {lhs: lhs.clone(), op, rhs: rhs.clone()})),
+            lhs,
+        };
+        (base_decl, assign.desugar(diag))
+    }
+}
+
+/// Returns true if the expression is a variable, `self`, or a field access on those
+///
+/// Evaluating these expressions has no side effects, so they can be evaluated more than once.
+fn is_place_expr(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Path(_) |
+        ast::Expr::SelfValue(_) => true,
+        ast::Expr::FieldAccess(access) => is_place_expr(&access.lhs),
+        _ => false,
+    }
+}
+
 impl Desugar for ast::MethodCall {
     type Output = hir::MethodCall;
 
@@ -645,3 +818,261 @@ impl Desugar for ast::Ident {
         self.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use parking_lot::RwLock;
+
+    use crate::parser;
+    use crate::source_files::SourceFiles;
+
+    #[test]
+    fn for_loop() {
+        let source_files = Arc::new(RwLock::new(SourceFiles::default()));
+        let root_file = source_files.write().add_source("test.dino", b"
+            fn main() {
+                for i in 0..3 {
+                    print_int(i);
+                }
+            }
+        ");
+        let diag = Diagnostics::new(source_files.clone(), termcolor::ColorChoice::Auto);
+        let module = parser::parse_module(source_files.read().source(root_file), &diag);
+        let module = module.desugar(&diag);
+        assert_eq!(diag.emitted_errors(), 0);
+
+        let main = match &module.decls[..] {
+            [hir::Decl::Function(func)] => func,
+            decls => panic!("expected a single function, found: {:?}", decls),
+        };
+        let (next, end, wloop) = match &main.body.stmts[..] {
+            [hir::Stmt::VarDecl(next), hir::Stmt::VarDecl(end), hir::Stmt::WhileLoop(wloop)] => (next, end, wloop),
+            stmts => panic!("unexpected statements: {:?}", stmts),
+        };
+
+        // The counter starts at 0 and the loop continues while it is less than 3
+        match (&next.expr, &end.expr) {
+            (hir::Expr::IntegerLiteral(start), hir::Expr::IntegerLiteral(end)) => {
+                assert_eq!((start.value, end.value), (0, 3));
+            },
+            exprs => panic!("unexpected range: {:?}", exprs),
+        }
+        match &wloop.cond {
+            hir::Expr::MethodCall(call) => {
+                assert_eq!(&*call.method_name.value, "lt");
+                assert_eq!(call.lhs, hir::Expr::Path(next.name.clone().into()));
+                assert_eq!(call.args, vec![hir::Expr::Path(end.name.clone().into())]);
+            },
+            cond => panic!("unexpected loop condition: {:?}", cond),
+        }
+
+        // The loop variable takes the value of the counter, which is then incremented by 1 before
+        // the original body runs
+        match &wloop.body.stmts[..] {
+            [hir::Stmt::VarDecl(var), hir::Stmt::Expr(hir::Expr::Assign(incr)), hir::Stmt::Expr(hir::Expr::Call(_))] => {
+                assert_eq!(&*var.name.value, "i");
+                assert_eq!(var.expr, hir::Expr::Path(next.name.clone().into()));
+
+                assert_eq!(incr.lvalue, hir::LValue::Path(next.name.clone().into()));
+                match &incr.expr {
+                    hir::Expr::MethodCall(call) => {
+                        assert_eq!(&*call.method_name.value, "add");
+                        match &call.args[..] {
+                            [hir::Expr::IntegerLiteral(one)] => assert_eq!(one.value, 1),
+                            args => panic!("unexpected increment: {:?}", args),
+                        }
+                    },
+                    expr => panic!("unexpected increment: {:?}", expr),
+                }
+            },
+            stmts => panic!("unexpected loop body: {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn for_loop_continue() {
+        // Same as tests/run-pass/todo/for-continue.dino, which cannot run until the new parser
+        // supports `for` loops
+        let (body, errors) = desugar_main(b"
+            fn main() {
+                for i in 0..10 {
+                    if i % 2 == 0 {
+                        continue;
+                    }
+                    print_int(i);
+                }
+
+                let count = 0;
+                for i in 0..10 {
+                    count = count + 1;
+                    continue;
+                }
+                print_int(count);
+            }
+        ");
+        assert_eq!(errors, 0);
+
+        let loops: Vec<_> = body.stmts.iter().filter_map(|stmt| match stmt {
+            hir::Stmt::WhileLoop(wloop) => Some(wloop),
+            _ => None,
+        }).collect();
+        assert_eq!(loops.len(), 2);
+
+        // A `continue` skips the rest of the loop body, so the counter must be incremented before
+        // any of the original body runs. Otherwise, the loop would never end.
+        for wloop in loops {
+            match &wloop.body.stmts[..] {
+                [hir::Stmt::VarDecl(var), hir::Stmt::Expr(hir::Expr::Assign(_)), rest @ ..] => {
+                    assert_eq!(&*var.name.value, "i");
+                    let continues = rest.iter().any(|stmt| match stmt {
+                        hir::Stmt::Expr(hir::Expr::Continue(_)) => true,
+                        hir::Stmt::Cond(cond) => cond.conds.iter().any(|(_, body)| {
+                            matches!(&body.stmts[..], [hir::Stmt::Expr(hir::Expr::Continue(_))])
+                        }),
+                        _ => false,
+                    });
+                    assert!(continues, "expected a `continue` after the increment: {:?}", rest);
+                },
+                stmts => panic!("unexpected loop body: {:?}", stmts),
+            }
+        }
+    }
+
+    /// Parses and desugars the given source, returning the body of `main` and the number of
+    /// errors emitted
+    fn desugar_main(source: &[u8]) -> (hir::Block, usize) {
+        let source_files = Arc::new(RwLock::new(SourceFiles::default()));
+        let root_file = source_files.write().add_source("test.dino", source);
+        let diag = Diagnostics::new(source_files.clone(), termcolor::ColorChoice::Auto);
+        let module = parser::parse_module(source_files.read().source(root_file), &diag);
+        let mut module = module.desugar(&diag);
+
+        match module.decls.pop() {
+            Some(hir::Decl::Function(func)) => (func.body, diag.emitted_errors()),
+            decl => panic!("expected a function, found: {:?}", decl),
+        }
+    }
+
+    /// Asserts that `expr` is `lhs.method_name(rhs)` and returns `lhs` and `rhs`
+    fn method_call<'a>(expr: &'a hir::Expr, method_name: &str) -> (&'a hir::Expr, &'a hir::Expr) {
+        match expr {
+            hir::Expr::MethodCall(call) => {
+                assert_eq!(&*call.method_name.value, method_name);
+                match &call.args[..] {
+                    [rhs] => (&call.lhs, rhs),
+                    args => panic!("expected a single argument, found: {:?}", args),
+                }
+            },
+            expr => panic!("expected a method call, found: {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn compound_assign() {
+        let (body, errors) = desugar_main(b"
+            fn main() {
+                let x = 3;
+                x += 2;
+                x -= 1;
+                x *= 4;
+                x /= 2;
+                x %= 3;
+            }
+        ");
+        assert_eq!(errors, 0);
+
+        // Each use of `x` has its own span, so only the names are compared
+        let is_x = |path: &hir::Path| matches!(&path.components[..], [name] if &*name.value == "x");
+        let methods = ["add", "sub", "mul", "div", "rem"];
+        assert_eq!(body.stmts.len(), methods.len() + 1);
+        for (stmt, &method_name) in body.stmts[1..].iter().zip(&methods) {
+            // `x op= value` becomes `x = x.method(value)`
+            match stmt {
+                hir::Stmt::Expr(hir::Expr::Assign(assign)) => {
+                    assert!(matches!(&assign.lvalue, hir::LValue::Path(path) if is_x(path)));
+                    let (lhs, _) = method_call(&assign.expr, method_name);
+                    assert!(matches!(lhs, hir::Expr::Path(path) if is_x(path)));
+                },
+                stmt => panic!("unexpected statement: {:?}", stmt),
+            }
+        }
+    }
+
+    #[test]
+    fn compound_assign_field() {
+        // Accessing a field of a variable has no side effects, so it is safe to evaluate twice
+        let (body, errors) = desugar_main(b"
+            fn main() {
+                p.pos.x += 2;
+            }
+        ");
+        assert_eq!(errors, 0);
+
+        match &body.stmts[..] {
+            [hir::Stmt::Expr(hir::Expr::Assign(assign))] => {
+                let access = match &assign.lvalue {
+                    hir::LValue::FieldAccess(access) => access,
+                    lvalue => panic!("unexpected lvalue: {:?}", lvalue),
+                };
+                assert_eq!(&*access.field.value, "x");
+
+                let (lhs, _) = method_call(&assign.expr, "add");
+                match lhs {
+                    hir::Expr::FieldAccess(value) => assert_eq!(**value, *access),
+                    lhs => panic!("unexpected left-hand side: {:?}", lhs),
+                }
+            },
+            stmts => panic!("unexpected statements: {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn compound_assign_field_side_effects() {
+        // The call may have side effects, so it must only be evaluated once, even when the
+        // compound assignment is the final expression of the block
+        let sources: &[&[u8]] = &[
+            b"fn main() { points.next_point().x -= 1; }",
+            b"fn main() { points.next_point().x -= 1 }",
+        ];
+        for source in sources {
+            let (body, errors) = desugar_main(source);
+            assert_eq!(errors, 0);
+
+            let assign = match (&body.stmts[..], &body.ret) {
+                ([_], Some(hir::Expr::Assign(assign))) => assign,
+                ([_, hir::Stmt::Expr(hir::Expr::Assign(assign))], None) => assign,
+                block => panic!("unexpected block: {:?}", block),
+            };
+            let base = match &body.stmts[0] {
+                hir::Stmt::VarDecl(base) => base,
+                stmt => panic!("unexpected statement: {:?}", stmt),
+            };
+            match &base.expr {
+                hir::Expr::MethodCall(call) => assert_eq!(&*call.method_name.value, "next_point"),
+                expr => panic!("unexpected base expression: {:?}", expr),
+            }
+
+            // Both the assignment and the current value refer to the hidden variable
+            let base_var = hir::Expr::Path(base.name.clone().into());
+            let access = match &assign.lvalue {
+                hir::LValue::FieldAccess(access) => access,
+                lvalue => panic!("unexpected lvalue: {:?}", lvalue),
+            };
+            assert_eq!(access.lhs, base_var);
+            let (lhs, _) = method_call(&assign.expr, "sub");
+            match lhs {
+                hir::Expr::FieldAccess(value) => assert_eq!(value.lhs, base_var),
+                lhs => panic!("unexpected left-hand side: {:?}", lhs),
+            }
+        }
+
+        // There is nowhere to declare the hidden variable if the assignment is nested in another
+        // expression
+        let (_, errors) = desugar_main(b"fn main() { print_unit(points.next_point().x += 1); }");
+        assert_eq!(errors, 1);
+    }
+}
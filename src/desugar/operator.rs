@@ -26,7 +26,7 @@ impl Operator for ast::CompareOp {
                 span,
             },
             Le(span) => hir::Ident {
-                value: "le".into(),
+                value: "lte".into(),
                 span,
             },
             Gt(span) => hir::Ident {
@@ -34,7 +34,7 @@ impl Operator for ast::CompareOp {
                 span,
             },
             Ge(span) => hir::Ident {
-                value: "ge".into(),
+                value: "gte".into(),
                 span,
             },
         }
@@ -24,6 +24,8 @@ pub struct Diagnostics {
     out: Mutex<OutputStream>,
     /// The number of errors that have been emitted
     errors: AtomicUsize,
+    /// The number of warnings that have been emitted
+    warnings: AtomicUsize,
 }
 
 impl Diagnostics {
@@ -35,6 +37,7 @@ impl Diagnostics {
             #[cfg(test)]
             out: Mutex::new(writer::NullWriter::new(color_choice)),
             errors: AtomicUsize::default(),
+            warnings: AtomicUsize::default(),
         }
     }
 
@@ -43,6 +46,11 @@ impl Diagnostics {
         self.errors.load(Ordering::SeqCst)
     }
 
+    /// Returns the number of warnings that have been emitted
+    pub fn emitted_warnings(&self) -> usize {
+        self.warnings.load(Ordering::SeqCst)
+    }
+
     pub fn error<'a>(&'a self, message: impl Into<Cow<'a, str>>) -> DiagnosticWriter<'a> {
         self.level(Level::Error, message)
     }
@@ -81,6 +89,7 @@ impl Diagnostics {
             source_files: self.source_files.read(),
             out: self.out.lock(),
             errors: &self.errors,
+            warnings: &self.warnings,
             data,
         }
     }
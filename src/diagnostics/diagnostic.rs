@@ -45,6 +45,7 @@ pub struct DiagnosticWriter<'a> {
     pub(super) source_files: RwLockReadGuard<'a, SourceFiles>,
     pub(super) out: MutexGuard<'a, OutputStream>,
     pub(super) errors: &'a AtomicUsize,
+    pub(super) warnings: &'a AtomicUsize,
     pub(super) data: Diagnostic<'a>,
 }
 
@@ -81,11 +82,13 @@ impl<'a> DiagnosticWriter<'a> {
     }
 
     pub fn emit(self) {
-        let Self {source_files, mut out, errors, data} = self;
+        let Self {source_files, mut out, errors, warnings, data} = self;
         let Diagnostic {title, fragments} = &data;
 
-        if title.level == Level::Error {
-            errors.fetch_add(1, Ordering::SeqCst);
+        match title.level {
+            Level::Error => { errors.fetch_add(1, Ordering::SeqCst); },
+            Level::Warning => { warnings.fetch_add(1, Ordering::SeqCst); },
+            _ => {},
         }
 
         if let Some(frag) = fragments.get(0) {
@@ -27,6 +27,9 @@ pub struct ImportPath {
     pub path: Vec<Ident>,
     /// The items selected from the path
     pub selection: ImportSelection,
+    /// True if the selected items are re-exported (`pub use ...;`), making them visible to every
+    /// module that imports this one
+    pub is_pub: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -6,7 +6,14 @@
 //!     * Every call knows all its types and operators have been desugared
 //! * All declaration names are unique within any given module
 
+pub mod visit;
+
+mod dump;
+
+pub use dump::*;
+
 use std::collections::HashMap;
+use std::path::Path;
 
 pub use crate::ast2::{Ident, IdentPath};
 
@@ -21,6 +28,18 @@ pub struct Program<'a> {
 pub struct Module<'a> {
     pub types: Vec<Struct<'a>>,
     pub functions: Vec<Function<'a>>,
+    /// The functions declared in inline modules, along with the name of their module
+    pub module_functions: Vec<(Ident<'a>, Function<'a>)>,
+    pub statics: Vec<Static<'a>>,
+}
+
+/// A global variable
+#[derive(Debug, Clone)]
+pub struct Static<'a> {
+    pub name: Ident<'a>,
+    pub ty: TyId,
+    /// The initial value of the global variable (always a constant)
+    pub init: Expr<'a>,
 }
 
 #[derive(Debug, Clone)]
@@ -47,14 +66,89 @@ impl<'a> Struct<'a> {
     }
 }
 
-pub type FieldTys<'a> = HashMap<Ident<'a>, TyId>;
+/// The fields of a struct and their types, stored in the order they were declared
+///
+/// Structs are laid out in the generated code exactly as they would be by a C compiler for a C
+/// struct with the same fields in the same order:
+///
+/// * Fields are never reordered. Each field is placed after the field declared before it.
+/// * Every field stores a pointer to its (heap allocated) value, so each field has the size and
+///   alignment of a C pointer (`void*`) on the target platform.
+/// * Since all fields have the same size and alignment, no padding is ever inserted between
+///   fields. The offset of the field at index `i` is therefore `i * sizeof(void*)`.
+/// * The size of the struct is the number of fields multiplied by `sizeof(void*)`. The struct
+///   is not packed, so its alignment is that of a pointer.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FieldTys<'a> {
+    fields: Vec<(Ident<'a>, TyId)>,
+}
+
+impl<'a> FieldTys<'a> {
+    /// Creates an empty set of fields
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a field after all the previously inserted fields
+    ///
+    /// If a field with this name was already present, its type is replaced in place (without
+    /// changing its position) and the previous type is returned.
+    pub fn insert(&mut self, name: Ident<'a>, ty: TyId) -> Option<TyId> {
+        match self.fields.iter_mut().find(|(field_name, _)| *field_name == name) {
+            Some((_, field_ty)) => Some(std::mem::replace(field_ty, ty)),
+            None => {
+                self.fields.push((name, ty));
+                None
+            },
+        }
+    }
+
+    /// Returns the type of the given field, if any
+    pub fn get(&self, name: &Ident<'a>) -> Option<&TyId> {
+        self.fields.iter().find(|(field_name, _)| field_name == name).map(|(_, ty)| ty)
+    }
+
+    /// Returns the offset in bytes of the given field from the start of the struct, if any
+    ///
+    /// See the documentation of this type for the layout rules used to compute the offset. The
+    /// generated code is assumed to run on the same platform as the compiler.
+    pub fn offset_of(&self, name: &Ident<'a>) -> Option<usize> {
+        let index = self.fields.iter().position(|(field_name, _)| field_name == name)?;
+        Some(index * std::mem::size_of::<*const ()>())
+    }
+
+    /// Iterates over the fields in the order they were declared
+    pub fn iter(&self) -> impl Iterator<Item = (&Ident<'a>, &TyId)> {
+        self.fields.iter().map(|(name, ty)| (name, ty))
+    }
+
+    /// Returns the number of fields
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns true if there are no fields
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
 pub type MethodDecls<'a> = HashMap<Ident<'a>, Function<'a>>;
 
 #[derive(Debug, Clone)]
 pub struct Function<'a> {
     pub name: Ident<'a>,
     pub sig: FuncSig<'a>,
+    /// The body of the function. Empty if `c_body` is set.
     pub body: Block<'a>,
+    /// Raw C code that is emitted verbatim as the body of the function (unchecked)
+    pub c_body: Option<&'a str>,
+    /// True if this function should be run by the test harness
+    pub is_test: bool,
+    /// True if this function is marked as having no side effects
+    pub is_pure: bool,
+    /// The path of the file that the function was declared in (if known)
+    pub source_path: Option<&'a Path>,
 }
 
 #[derive(Debug, Clone)]
@@ -76,6 +170,9 @@ pub struct FuncParam<'a> {
 #[derive(Debug, Clone)]
 pub struct Block<'a> {
     pub stmts: Vec<Stmt<'a>>,
+    /// The line (starting at 1) of the start of each statement in `stmts`, followed by the line
+    /// of `ret` (if any)
+    pub stmt_lines: Vec<usize>,
     /// The final statement of the block, used as the return value of the block
     pub ret: Option<Expr<'a>>,
     /// The return type of the block. Must match TyId in `ret` if `ret` is not None
@@ -84,14 +181,63 @@ pub struct Block<'a> {
     pub ret_ty: TyId,
 }
 
+impl<'a> Block<'a> {
+    /// Returns true if the end of the block can never be reached, so it does not need to produce
+    /// a value
+    ///
+    /// This is the case when the block has no final expression and its last statement always
+    /// returns from the function, is a `break` or a `continue`, or is a call to a function that
+    /// never returns (i.e. a call of type `!`).
+    pub fn end_is_unreachable(&self, never: TyId) -> bool {
+        let Block {stmts, stmt_lines: _, ret, ret_ty: _} = self;
+        ret.is_none() && match stmts.last() {
+            Some(Stmt::Break) | Some(Stmt::Continue) => true,
+            Some(&Stmt::Expr(Expr::Call(_, ty))) if ty == never => true,
+            Some(stmt) => stmt.always_returns(),
+            None => false,
+        }
+    }
+
+    /// Returns true if running the block always results in returning from the function
+    pub fn always_returns(&self) -> bool {
+        let Block {stmts, stmt_lines: _, ret, ret_ty: _} = self;
+        match ret {
+            Some(ret) => ret.always_returns(),
+            None => stmts.last().map(Stmt::always_returns).unwrap_or(false),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Stmt<'a> {
     Cond(Cond<'a>),
     WhileLoop(WhileLoop<'a>),
+    /// Exits the innermost loop
+    Break,
+    /// Skips to the next iteration of the innermost loop
+    Continue,
+    /// A nested block, with its own scope for the variables declared within it
+    Block(Block<'a>),
     VarDecl(VarDecl<'a>),
     Expr(Expr<'a>),
 }
 
+impl<'a> Stmt<'a> {
+    /// Returns true if running the statement always results in returning from the function
+    pub fn always_returns(&self) -> bool {
+        match self {
+            Stmt::Cond(cond) => cond.always_returns(),
+            Stmt::Block(block) => block.always_returns(),
+            Stmt::Expr(expr) => expr.always_returns(),
+            // Loops may run zero times or be exited with `break`, so they are never considered
+            Stmt::WhileLoop(_) |
+            Stmt::Break |
+            Stmt::Continue |
+            Stmt::VarDecl(_) => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct WhileLoop<'a> {
     /// The condition for which the loop is expected to continue
@@ -125,6 +271,8 @@ pub enum Expr<'a> {
     BoolLiteral(bool, TyId),
     UnitLiteral(TyId),
     Var(Ident<'a>, TyId),
+    /// A global variable declared with `static`
+    StaticVar(Ident<'a>, TyId),
 }
 
 impl<'a> Expr<'a> {
@@ -143,7 +291,17 @@ impl<'a> Expr<'a> {
             ComplexLiteral(_, ty_id) |
             BoolLiteral(_, ty_id) |
             UnitLiteral(ty_id) |
-            Var(_, ty_id) => ty_id,
+            Var(_, ty_id) |
+            StaticVar(_, ty_id) => ty_id,
+        }
+    }
+
+    /// Returns true if evaluating the expression always results in returning from the function
+    pub fn always_returns(&self) -> bool {
+        match self {
+            Expr::Return(_, _) => true,
+            Expr::Cond(cond, _) => cond.always_returns(),
+            _ => false,
         }
     }
 }
@@ -153,6 +311,8 @@ impl<'a> Expr<'a> {
 pub enum LValueExpr<'a> {
     FieldAccess(FieldAccess<'a>, TyId),
     Var(Ident<'a>, TyId),
+    /// A global variable declared with `static`
+    StaticVar(Ident<'a>, TyId),
 }
 
 #[derive(Debug, Clone)]
@@ -183,6 +343,21 @@ pub struct Cond<'a> {
     pub else_body: Option<Block<'a>>,
 }
 
+impl<'a> Cond<'a> {
+    /// Returns true if the conditional has an `else` clause and every one of its branches always
+    /// returns from the function
+    pub fn always_returns(&self) -> bool {
+        let Cond {conds, else_body} = self;
+
+        match else_body {
+            Some(else_body) => {
+                conds.iter().all(|(_, body)| body.always_returns()) && else_body.always_returns()
+            },
+            None => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CallExpr<'a> {
     /// The name of the function to call
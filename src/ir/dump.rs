@@ -0,0 +1,388 @@
+//! Renders the IR as indented pseudo-Dino code for debugging.
+//!
+//! The output is not meant to be valid Dino code. Every block is annotated with its return type
+//! and every call shows the function that it was resolved to.
+
+use std::fmt;
+
+use crate::resolve2::{ProgramDecls, TyId};
+
+use super::*;
+
+/// Renders the given program as indented pseudo-Dino code
+pub fn dump_ir(prog: &Program, decls: &ProgramDecls) -> String {
+    IrDisplay {prog, decls}.to_string()
+}
+
+/// Displays a program as indented pseudo-Dino code with all type names resolved
+#[derive(Debug, Clone, Copy)]
+pub struct IrDisplay<'a, 'b> {
+    pub prog: &'b Program<'a>,
+    pub decls: &'b ProgramDecls<'a>,
+}
+
+impl<'a, 'b> fmt::Display for IrDisplay<'a, 'b> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let &Self {prog, decls} = self;
+        IrPrinter {f, decls, indent: 0}.program(prog)
+    }
+}
+
+struct IrPrinter<'f, 'w, 'b, 'a> {
+    f: &'f mut fmt::Formatter<'w>,
+    decls: &'b ProgramDecls<'a>,
+    /// The current level of indentation
+    indent: usize,
+}
+
+impl<'f, 'w, 'b, 'a> IrPrinter<'f, 'w, 'b, 'a> {
+    fn program(&mut self, prog: &Program) -> fmt::Result {
+        let Program {top_level_module} = prog;
+        let Module {types, functions, module_functions, statics} = top_level_module;
+
+        for struct_decl in types {
+            self.struct_decl(struct_decl)?;
+        }
+
+        for Static {name, ty, init} in statics {
+            write!(self.f, "static mut {}: {} = ", name, self.ty(*ty))?;
+            self.expr(init)?;
+            writeln!(self.f, ";")?;
+        }
+        if !statics.is_empty() {
+            writeln!(self.f)?;
+        }
+
+        for (i, func) in functions.iter().enumerate() {
+            if i > 0 {
+                writeln!(self.f)?;
+            }
+            self.function(func)?;
+        }
+
+        // Group the functions by module, keeping the modules in the order they were first seen
+        let mut modules: Vec<(&str, Vec<&Function>)> = Vec::new();
+        for (module_name, func) in module_functions {
+            match modules.iter_mut().find(|(name, _)| name == module_name) {
+                Some((_, funcs)) => funcs.push(func),
+                None => modules.push((module_name, vec![func])),
+            }
+        }
+
+        for (module_name, funcs) in modules {
+            writeln!(self.f)?;
+            writeln!(self.f, "mod {} {{", module_name)?;
+            self.indent += 1;
+            for (i, func) in funcs.into_iter().enumerate() {
+                if i > 0 {
+                    writeln!(self.f)?;
+                }
+                self.write_indent()?;
+                self.function(func)?;
+            }
+            self.indent -= 1;
+            writeln!(self.f, "}}")?;
+        }
+
+        Ok(())
+    }
+
+    fn struct_decl(&mut self, struct_decl: &Struct) -> fmt::Result {
+        let Struct {name, is_extern, fields, methods} = struct_decl;
+
+        if *is_extern {
+            writeln!(self.f, "extern struct {};", name)?;
+        } else {
+            writeln!(self.f, "struct {} {{", name)?;
+            for (field, &ty) in fields.iter() {
+                writeln!(self.f, "    {}: {},", field, self.ty(ty))?;
+            }
+            writeln!(self.f, "}}")?;
+        }
+        writeln!(self.f)?;
+
+        if !methods.is_empty() {
+            writeln!(self.f, "impl {} {{", name)?;
+            self.indent += 1;
+
+            // Sort the methods so the output does not depend on the order of the map
+            let mut methods: Vec<_> = methods.values().collect();
+            methods.sort_by_key(|method| method.name);
+            for (i, method) in methods.into_iter().enumerate() {
+                if i > 0 {
+                    writeln!(self.f)?;
+                }
+                self.write_indent()?;
+                self.function(method)?;
+            }
+
+            self.indent -= 1;
+            writeln!(self.f, "}}")?;
+            writeln!(self.f)?;
+        }
+
+        Ok(())
+    }
+
+    fn function(&mut self, func: &Function) -> fmt::Result {
+        let Function {name, sig, body, c_body, is_test, is_pure, source_path: _} = func;
+        let FuncSig {return_type, params} = sig;
+
+        if *is_test {
+            writeln!(self.f, "#[test]")?;
+            self.write_indent()?;
+        }
+        if *is_pure {
+            writeln!(self.f, "#[pure]")?;
+            self.write_indent()?;
+        }
+
+        if c_body.is_some() {
+            write!(self.f, "extern \"C\" ")?;
+        }
+        write!(self.f, "fn {}(", name)?;
+        for (i, FuncParam {name, ty}) in params.iter().enumerate() {
+            if i > 0 {
+                write!(self.f, ", ")?;
+            }
+            write!(self.f, "{}: {}", name, self.ty(*ty))?;
+        }
+        write!(self.f, ") -> {} ", self.ty(*return_type))?;
+
+        match c_body {
+            // The code is shown as-is since it is never checked
+            Some(code) => writeln!(self.f, "= \"{}\"; // UNCHECKED", code.escape_default()),
+            None => {
+                self.block(body)?;
+                writeln!(self.f)
+            },
+        }
+    }
+
+    /// Writes a block, followed by its return type: `{ ... }: ty`
+    fn block(&mut self, block: &Block) -> fmt::Result {
+        let Block {stmts, stmt_lines: _, ret, ret_ty} = block;
+
+        writeln!(self.f, "{{")?;
+        self.indent += 1;
+
+        for stmt in stmts {
+            self.write_indent()?;
+            self.stmt(stmt)?;
+            writeln!(self.f)?;
+        }
+
+        if let Some(ret) = ret {
+            self.write_indent()?;
+            self.expr(ret)?;
+            writeln!(self.f)?;
+        }
+
+        self.indent -= 1;
+        self.write_indent()?;
+        write!(self.f, "}}: {}", self.ty(*ret_ty))
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> fmt::Result {
+        match stmt {
+            Stmt::Cond(cond) => self.cond(cond),
+            Stmt::WhileLoop(WhileLoop {cond, body}) => {
+                write!(self.f, "while ")?;
+                self.expr(cond)?;
+                write!(self.f, " ")?;
+                self.block(body)
+            },
+            Stmt::Break => write!(self.f, "break;"),
+            Stmt::Continue => write!(self.f, "continue;"),
+            Stmt::Block(block) => self.block(block),
+            Stmt::VarDecl(VarDecl {ident, ty, expr}) => {
+                write!(self.f, "let {}: {} = ", ident, self.ty(*ty))?;
+                self.expr(expr)?;
+                write!(self.f, ";")
+            },
+            Stmt::Expr(expr) => {
+                self.expr(expr)?;
+                write!(self.f, ";")
+            },
+        }
+    }
+
+    fn cond(&mut self, cond: &Cond) -> fmt::Result {
+        let Cond {conds, else_body} = cond;
+
+        for (i, (cond_expr, body)) in conds.iter().enumerate() {
+            if i > 0 {
+                write!(self.f, " else ")?;
+            }
+            write!(self.f, "if ")?;
+            self.expr(cond_expr)?;
+            write!(self.f, " ")?;
+            self.block(body)?;
+        }
+
+        if let Some(else_body) = else_body {
+            write!(self.f, " else ")?;
+            self.block(else_body)?;
+        }
+
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &Expr) -> fmt::Result {
+        match expr {
+            Expr::VarAssign(assign, _) => {
+                let VarAssign {lhs, expr} = &**assign;
+                match lhs {
+                    LValueExpr::FieldAccess(access, _) => self.field_access(access)?,
+                    LValueExpr::Var(name, _) |
+                    LValueExpr::StaticVar(name, _) => write!(self.f, "{}", name)?,
+                }
+                write!(self.f, " = ")?;
+                self.expr(expr)
+            },
+            Expr::FieldAccess(access, _) => self.field_access(access),
+            Expr::Cond(cond, _) => self.cond(cond),
+            Expr::Call(CallExpr {func_name, args}, _) => {
+                // The function name is the resolved target of the call
+                write!(self.f, "{}(", func_name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(self.f, ", ")?;
+                    }
+                    self.expr(arg)?;
+                }
+                write!(self.f, ")")
+            },
+            Expr::Return(ret_expr, _) => {
+                write!(self.f, "return")?;
+                if let Some(ret_expr) = ret_expr {
+                    write!(self.f, " ")?;
+                    self.expr(ret_expr)?;
+                }
+                Ok(())
+            },
+            Expr::StructLiteral(StructLiteral {ty_id, field_values}, _) => {
+                writeln!(self.f, "{} {{", self.ty(*ty_id))?;
+                self.indent += 1;
+
+                // Sort the fields so the output does not depend on the order of the map
+                let mut field_values: Vec<_> = field_values.iter().collect();
+                field_values.sort_by_key(|&(&name, _)| name);
+                for (name, value) in field_values {
+                    self.write_indent()?;
+                    write!(self.f, "{}: ", name)?;
+                    self.expr(value)?;
+                    writeln!(self.f, ",")?;
+                }
+
+                self.indent -= 1;
+                self.write_indent()?;
+                write!(self.f, "}}")
+            },
+            Expr::BStrLiteral(value, _) => {
+                write!(self.f, "b\"")?;
+                for &byte in value.iter() {
+                    write!(self.f, "{}", std::ascii::escape_default(byte))?;
+                }
+                write!(self.f, "\"")
+            },
+            // Numeric literals are annotated with their inferred type
+            &Expr::IntegerLiteral(value, ty) => write!(self.f, "({}: {})", value, self.ty(ty)),
+            &Expr::RealLiteral(value, ty) => write!(self.f, "({:?}: {})", value, self.ty(ty)),
+            &Expr::ComplexLiteral(value, ty) => write!(self.f, "({:?}j: {})", value, self.ty(ty)),
+            Expr::BoolLiteral(value, _) => write!(self.f, "{}", value),
+            Expr::UnitLiteral(_) => write!(self.f, "()"),
+            Expr::Var(name, _) |
+            Expr::StaticVar(name, _) => write!(self.f, "{}", name),
+        }
+    }
+
+    fn field_access(&mut self, access: &FieldAccess) -> fmt::Result {
+        let FieldAccess {lhs, field} = access;
+        self.expr(lhs)?;
+        write!(self.f, ".{}", field)
+    }
+
+    fn write_indent(&mut self) -> fmt::Result {
+        for _ in 0..self.indent {
+            write!(self.f, "    ")?;
+        }
+
+        Ok(())
+    }
+
+    fn ty(&self, ty: TyId) -> &'b str {
+        self.decls.source_type_name(ty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tycheck::check_source;
+
+    #[test]
+    fn program_dump() {
+        let source = r#"
+            struct Point { x: int, y: real }
+            impl Point {
+                fn scaled(self) -> real { add_real(self.y, 0.5) }
+            }
+
+            mod math {
+                fn double(x: int) -> int { x * 2 }
+                fn half(x: int) -> int { x / 2 }
+            }
+
+            static mut COUNT: int = 0;
+
+            fn main() {
+                let p = Point {x: 1, y: 2.5};
+                if p.x > 0 {
+                    COUNT = math::double(p.x);
+                }
+                print_real(p.scaled());
+                print_bstr(b"done\n");
+            }
+        "#;
+        let checked = check_source(source).unwrap();
+
+        let expected = r#"struct Point {
+    x: int,
+    y: real,
+}
+
+impl Point {
+    fn scaled(self: Point) -> real {
+        add_real(self.y, (0.5: real))
+    }: real
+}
+
+static mut COUNT: int = (0: int);
+
+fn main() -> () {
+    let p: Point = Point {
+        x: (1: int),
+        y: (2.5: real),
+    };
+    if int__gt(p.x, (0: int)) {
+        COUNT = math::double(p.x);
+    }: ()
+    print_real(Point::scaled(p));
+    print_bstr(b"done\n");
+}: ()
+
+mod math {
+    fn double(x: int) -> int {
+        int__mul(x, (2: int))
+    }: int
+
+    fn half(x: int) -> int {
+        int__div(x, (2: int))
+    }: int
+}
+"#;
+        assert_eq!(dump_ir(&checked.program, checked.decls), expected);
+    }
+}
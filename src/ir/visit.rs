@@ -0,0 +1,119 @@
+//! A read-only traversal of the IR
+//!
+//! Implement `Visitor` and override the methods for the nodes you are interested in. Each method
+//! walks the children of its node by default, so an overridden method should call the matching
+//! `walk_*` function to keep visiting nested nodes.
+
+use super::*;
+
+pub trait Visitor<'a> {
+    fn visit_block(&mut self, block: &Block<'a>) {
+        walk_block(self, block);
+    }
+
+    /// Visits a statement of a block along with the line that the statement starts on (if known)
+    fn visit_stmt(&mut self, stmt: &Stmt<'a>, _line: Option<usize>) {
+        walk_stmt(self, stmt);
+    }
+
+    /// Visits the final expression of a block along with the line that it starts on (if known)
+    fn visit_ret(&mut self, ret: &Expr<'a>, _line: Option<usize>) {
+        self.visit_expr(ret);
+    }
+
+    fn visit_cond(&mut self, cond: &Cond<'a>) {
+        walk_cond(self, cond);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        walk_expr(self, expr);
+    }
+
+    /// Visits the left-hand side of an assignment
+    ///
+    /// Assigning to a variable does not read it, so the variable is not passed to `visit_expr`.
+    fn visit_lvalue(&mut self, lhs: &LValueExpr<'a>) {
+        walk_lvalue(self, lhs);
+    }
+}
+
+pub fn walk_block<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, block: &Block<'a>) {
+    let Block {stmts, stmt_lines, ret, ret_ty: _} = block;
+
+    for (i, stmt) in stmts.iter().enumerate() {
+        visitor.visit_stmt(stmt, stmt_lines.get(i).copied());
+    }
+
+    if let Some(ret) = ret {
+        visitor.visit_ret(ret, stmt_lines.get(stmts.len()).copied());
+    }
+}
+
+pub fn walk_stmt<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, stmt: &Stmt<'a>) {
+    match stmt {
+        Stmt::Cond(cond) => visitor.visit_cond(cond),
+        Stmt::WhileLoop(WhileLoop {cond, body}) => {
+            visitor.visit_expr(cond);
+            visitor.visit_block(body);
+        },
+        Stmt::Break |
+        Stmt::Continue => {},
+        Stmt::Block(block) => visitor.visit_block(block),
+        Stmt::VarDecl(VarDecl {ident: _, ty: _, expr}) => visitor.visit_expr(expr),
+        Stmt::Expr(expr) => visitor.visit_expr(expr),
+    }
+}
+
+pub fn walk_cond<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, cond: &Cond<'a>) {
+    let Cond {conds, else_body} = cond;
+
+    for (cond_expr, body) in conds {
+        visitor.visit_expr(cond_expr);
+        visitor.visit_block(body);
+    }
+
+    if let Some(else_body) = else_body {
+        visitor.visit_block(else_body);
+    }
+}
+
+pub fn walk_expr<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, expr: &Expr<'a>) {
+    match expr {
+        Expr::VarAssign(assign, _) => {
+            let VarAssign {lhs, expr} = &**assign;
+            visitor.visit_lvalue(lhs);
+            visitor.visit_expr(expr);
+        },
+        Expr::FieldAccess(access, _) => visitor.visit_expr(&access.lhs),
+        Expr::Cond(cond, _) => visitor.visit_cond(cond),
+        Expr::Call(CallExpr {func_name: _, args}, _) => {
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        },
+        Expr::Return(ret_expr, _) => if let Some(ret_expr) = ret_expr {
+            visitor.visit_expr(ret_expr);
+        },
+        Expr::StructLiteral(StructLiteral {ty_id: _, field_values}, _) => {
+            for value in field_values.values() {
+                visitor.visit_expr(value);
+            }
+        },
+        Expr::BStrLiteral(_, _) |
+        Expr::IntegerLiteral(_, _) |
+        Expr::RealLiteral(_, _) |
+        Expr::ComplexLiteral(_, _) |
+        Expr::BoolLiteral(_, _) |
+        Expr::UnitLiteral(_) |
+        Expr::Var(_, _) |
+        Expr::StaticVar(_, _) => {},
+    }
+}
+
+pub fn walk_lvalue<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, lhs: &LValueExpr<'a>) {
+    match lhs {
+        LValueExpr::FieldAccess(access, _) => visitor.visit_expr(&access.lhs),
+        LValueExpr::Var(_, _) |
+        LValueExpr::StaticVar(_, _) => {},
+    }
+}
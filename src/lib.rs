@@ -7,9 +7,11 @@ pub mod ir;
 pub mod resolve2;
 pub mod primitives2;
 pub mod tycheck;
+pub mod analysis;
 pub mod runtime;
 pub mod dino_std;
 pub mod gc_lib;
+pub mod cc;
 
 pub mod source_files;
 pub mod span;
@@ -28,6 +30,8 @@ pub mod cir;
 
 use std::fs;
 use std::io;
+use std::env;
+use std::fmt;
 use std::path::{Path, PathBuf};
 
 use snafu::{Snafu, ResultExt};
@@ -41,16 +45,36 @@ pub enum Error {
         path: PathBuf,
         source: io::Error,
     },
-    #[snafu(display("Parse error while reading '{}': {}", path.display(), source))]
+    // Each error is shown as `path:line:column: message` so that editors can jump to it
+    #[snafu(display("{}", errors.iter()
+        .map(|err| format!("{}:{}", path.display(), err))
+        .collect::<Vec<_>>()
+        .join("\n")))]
     ParseError {
         path: PathBuf,
-        source: ast2::ParseError,
+        /// Every syntax error in the file, in the order they occur
+        errors: Vec<ast2::ParseError>,
+    },
+    #[snafu(display("In '{}': could not read module `{}` from '{}': {}", path.display(), module,
+        module_path.display(), source))]
+    ImportError {
+        /// The file containing the import
+        path: PathBuf,
+        /// The imported module, as it was written in the import
+        module: String,
+        /// The file the module was expected to be in
+        module_path: PathBuf,
+        source: io::Error,
     },
     #[snafu(display("In '{}': {}", path.display(), source))]
     ResolveError {
         path: PathBuf,
         source: resolve2::Error,
     },
+    #[snafu(display("Internal error: the prelude is malformed: {}", source))]
+    PreludeError {
+        source: resolve2::Error,
+    },
     #[snafu(display("In '{}': {}", path.display(), source))]
     TypeError {
         path: PathBuf,
@@ -63,307 +87,1058 @@ pub enum Error {
     },
 }
 
+/// The environment variable that enables tracing
+///
+/// If this variable is set (to any value), a one-line summary of the output of each compiler
+/// phase is logged to stderr. This helps narrow down which phase is responsible when an input
+/// takes too long to compile or produces far more code than expected.
+pub const TRACE_ENV_VAR: &str = "DINO_TRACE";
+
+/// The environment variable that enables dumping the IR
+///
+/// If this variable is set (to any value), the IR of the program is written to stderr after type
+/// checking (see `ir::dump_ir`). This shows the type inferred for every expression and the
+/// function that every call was resolved to.
+pub const DUMP_IR_ENV_VAR: &str = "DINO_DUMP_IR";
+
+/// The result of compiling a program
+#[derive(Debug)]
+pub struct CompiledProgram {
+    /// The generated code
+    pub code: CExecutableProgram,
+    /// Problems found in the program that did not prevent it from compiling
+    pub warnings: Vec<ModuleWarning>,
+    /// The path of every source file that was read, starting with the file that was compiled
+    ///
+    /// This includes every module that was loaded because of an import, directly or indirectly.
+    pub source_paths: Vec<PathBuf>,
+}
+
+/// A warning found in one of the modules of a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleWarning {
+    /// The file of the module that the warning was found in
+    pub path: PathBuf,
+    pub warning: tycheck::Warning,
+}
+
+impl fmt::Display for ModuleWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "In '{}': {}", self.path.display(), self.warning)
+    }
+}
+
 /// Compiles the given file into executable code
-pub fn compile_executable2<P: AsRef<Path>>(path: P) -> Result<CExecutableProgram, Error> {
-    let path = path.as_ref();
-    let input_program = fs::read_to_string(path)
-        .with_context(|| IOError {path: path.to_path_buf()})?;
-    let program = ast2::Program::parse(&input_program)
-        .with_context(|| ParseError {path: path.to_path_buf()})?;
-    let (mut decls, resolved_ast) = resolve2::ProgramDecls::extract(&program)
-        .with_context(|| ResolveError {path: path.to_path_buf()})?;
-    insert_prelude(&mut decls);
-    let program_ir = tycheck::infer_and_check(resolved_ast, &decls)
-        .with_context(|| TypeError {path: path.to_path_buf()})?;
-    let code = trans::executable(&program_ir, &decls)
+///
+/// Any warnings found in the program are returned alongside the code so that the caller can
+/// decide how to report them.
+pub fn compile_executable2<P: AsRef<Path>>(
+    path: P,
+    options: &trans::CodegenOptions,
+) -> Result<CompiledProgram, Error> {
+    compile(path.as_ref(), |prog, decls| trans::executable(prog, decls, options))
+}
+
+/// Compiles the given file into a test harness that runs every function marked with `#[test]`
+///
+/// The generated executable reports whether each test passed or failed. It exits with a non-zero
+/// exit code if any test failed. Any warnings found in the program are returned alongside the code.
+pub fn compile_tests2<P: AsRef<Path>>(
+    path: P,
+    options: &trans::CodegenOptions,
+) -> Result<CompiledProgram, Error> {
+    compile(path.as_ref(), |prog, decls| trans::test_harness(prog, decls, options))
+}
+
+/// Runs every compiler phase on the given file, using `generate` to produce the final code
+fn compile(
+    path: &Path,
+    generate: impl FnOnce(&ir::Program, &resolve2::ProgramDecls) -> Result<CExecutableProgram, trans::Error>,
+) -> Result<CompiledProgram, Error> {
+    let modules = load_modules(path)?;
+    // Every module was already parsed successfully while it was being loaded
+    let programs: Vec<_> = modules.iter().map(|module| {
+        let mut program = ast2::Program::parse(&module.source)
+            .expect("bug: module failed to parse after it was loaded");
+        // Recorded so that the generated code can refer back to the file of each function
+        program.top_level_module.set_source_path(&module.path);
+        program
+    }).collect();
+    trace("parse", || format!("{} modules, {} bytes of source, {} top-level declarations",
+        modules.len(), modules.iter().map(|module| module.source.len()).sum::<usize>(),
+        programs.iter().map(|program| program.top_level_module.decls.len()).sum::<usize>()));
+
+    // Each module has its own namespace, so the items of imported modules are declared with names
+    // that include the path of their module. The root module is never imported, so its items keep
+    // the names they are written with.
+    let qualified_names: Vec<_> = modules.iter().zip(&programs).map(|(module, program)| {
+        if module.module_path.is_empty() {
+            None
+        } else {
+            Some(resolve2::QualifiedNames::new(module.module_path.clone(), &program.top_level_module))
+        }
+    }).collect();
+    let program_modules: Vec<_> = modules.iter().zip(&programs).zip(&qualified_names)
+        .map(|((module, program), qualified_names)| resolve2::ProgramModule {
+            path: &module.path,
+            module: &program.top_level_module,
+            qualified_names: qualified_names.as_ref(),
+        })
+        .collect();
+
+    // The prelude is inserted first so that any declarations that conflict with it are reported
+    // as errors in the program
+    let mut decls = resolve2::ProgramDecls::new();
+    insert_prelude(&mut decls).context(PreludeError)?;
+    let module_decls = decls.extract_modules(&program_modules)
+        .map_err(|resolve2::ModuleError {path, error}| Error::ResolveError {path, source: error})?;
+    trace("resolve", || format!("{} types, {} functions (including primitives and the prelude)",
+        decls.top_level_decls.type_count(), decls.top_level_decls.functions().count()));
+
+    let mut module_irs = Vec::new();
+    let mut warnings = Vec::new();
+    for (module, module_decls) in modules.iter().zip(module_decls) {
+        let (module_ir, module_warnings) = tycheck::infer_and_check(module_decls, &decls)
+            .with_context(|| TypeError {path: module.path.clone()})?;
+        module_irs.push(module_ir);
+        warnings.extend(module_warnings.into_iter()
+            .map(|warning| ModuleWarning {path: module.path.clone(), warning}));
+    }
+    let program_ir = tycheck::merge_modules(module_irs, &decls);
+    trace("tycheck", || {
+        let ir::Module {types, functions, module_functions, statics: _} = &program_ir.top_level_module;
+        let methods: usize = types.iter().map(|ty| ty.methods.len()).sum();
+        format!("{} IR functions, {} IR methods", functions.len() + module_functions.len(), methods)
+    });
+    if env::var_os(DUMP_IR_ENV_VAR).is_some() {
+        eprint!("{}", ir::dump_ir(&program_ir, &decls));
+    }
+    let code = generate(&program_ir, &decls)
         .with_context(|| CodeGenerationError {path: path.to_path_buf()})?;
+    trace("codegen", || format!("{} bytes of generated C", code.to_string().len()));
+
+    let source_paths = modules.into_iter().map(|module| module.path).collect();
+    Ok(CompiledProgram {code, warnings, source_paths})
+}
+
+/// Writes a makefile rule to `dep_file_path` listing every source file that `output_path` was
+/// compiled from (see `CompiledProgram::source_paths`)
+///
+/// Build tools such as make and ninja can use this file to know when the program needs to be
+/// recompiled.
+pub fn write_dep_file(dep_file_path: &Path, output_path: &Path, source_paths: &[PathBuf]) -> io::Result<()> {
+    let mut rule = format!("{}:", escape_dep_path(output_path));
+    for path in source_paths {
+        rule.push(' ');
+        rule.push_str(&escape_dep_path(path));
+    }
+    rule.push('\n');
+
+    fs::write(dep_file_path, rule)
+}
+
+/// Writes the symbol map of the given program to `symbol_map_path`
+///
+/// Each line of the file contains the kind of an item, its name in the program, and the C symbol
+/// generated for it, separated by tabs (see `codegen::CSymbolMap`). This makes it possible to find
+/// the item that a symbol in a debugger or profiler came from.
+pub fn write_symbol_map(symbol_map_path: &Path, code: &CExecutableProgram) -> io::Result<()> {
+    fs::write(symbol_map_path, code.symbols.to_string())
+}
+
+/// Escapes the characters that have a special meaning in a makefile rule
+fn escape_dep_path(path: &Path) -> String {
+    let mut escaped = String::new();
+    for ch in path.to_string_lossy().chars() {
+        match ch {
+            ' ' | '#' => escaped.push('\\'),
+            '$' => escaped.push('$'),
+            _ => {},
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// A source file read while compiling a program
+struct SourceModule {
+    path: PathBuf,
+    /// The path that the module is imported with (e.g. `["shapes", "square"]`), or an empty path
+    /// for the file being compiled
+    module_path: Vec<String>,
+    source: String,
+}
+
+/// Reads the file at `path` and every module that it imports, directly or indirectly
+///
+/// Imported modules are found relative to the directory containing `path`. Each module is only
+/// read once, even if it is imported more than once. The file at `path` is always returned first.
+fn load_modules(path: &Path) -> Result<Vec<SourceModule>, Error> {
+    let source = fs::read_to_string(path)
+        .with_context(|| IOError {path: path.to_path_buf()})?;
+
+    let mut loader = ModuleLoader {
+        package_dir: path.parent().unwrap_or_else(|| Path::new("")),
+        modules: Vec::new(),
+        import_stack: Vec::new(),
+    };
+    loader.load(path.to_path_buf(), Vec::new(), source)?;
+
+    Ok(loader.modules)
+}
+
+struct ModuleLoader<'a> {
+    /// The directory that all import paths are relative to
+    package_dir: &'a Path,
+    /// The modules loaded so far, in the order they were loaded
+    modules: Vec<SourceModule>,
+    /// The modules currently being loaded, each one imported by the one before it
+    import_stack: Vec<PathBuf>,
+}
 
-    Ok(code)
+impl<'a> ModuleLoader<'a> {
+    /// Loads the given module and then every module it imports that has not been loaded yet
+    fn load(&mut self, path: PathBuf, module_path: Vec<String>, source: String) -> Result<(), Error> {
+        // The parsed program borrows from the source, so it can't be kept while the source is
+        // moved into the list of modules. Only the imports are kept for now.
+        let imports: Vec<_> = {
+            let program = ast2::Program::parse(&source)
+                .or_else(|errors| ParseError {path: path.clone(), errors}.fail())?;
+
+            program.top_level_module.decls.iter().filter_map(|decl| match decl {
+                ast2::Decl::Import(import) => Some(self.module_file(import)),
+                _ => None,
+            }).collect()
+        };
+
+        self.import_stack.push(path.clone());
+        self.modules.push(SourceModule {path: path.clone(), module_path, source});
+
+        for (module_path, module_file) in imports {
+            if let Some(start) = self.import_stack.iter().position(|loading| *loading == module_file) {
+                let cycle = self.import_stack[start..].iter().chain(Some(&module_file))
+                    .map(|path| format!("'{}'", path.display()))
+                    .collect();
+                return Err(resolve2::Error::CyclicImport {cycle})
+                    .with_context(|| ResolveError {path: path.clone()});
+            }
+
+            if self.modules.iter().any(|loaded| loaded.path == module_file) {
+                continue;
+            }
+
+            let source = fs::read_to_string(&module_file).with_context(|| ImportError {
+                path: path.clone(),
+                module: format!("package::{}", module_path.join("::")),
+                module_path: module_file.clone(),
+            })?;
+            self.load(module_file, module_path, source)?;
+        }
+
+        self.import_stack.pop();
+
+        Ok(())
+    }
+
+    /// Returns the path of the imported module and the path of the file that contains it
+    fn module_file(&self, import: &ast2::ImportPath) -> (Vec<String>, PathBuf) {
+        let ast2::ImportPath {path, selection: _, is_pub: _} = import;
+
+        let module_path = path.iter().map(|name| name.to_string()).collect();
+        let module_file = path.iter().fold(self.package_dir.to_path_buf(), |dir, name| dir.join(name))
+            .with_extension("dino");
+
+        (module_path, module_file)
+    }
 }
 
-fn insert_prelude(decls: &mut resolve2::ProgramDecls) {
+/// Logs a one-line summary of the output of a compiler phase if tracing is enabled
+///
+/// The summary is only computed when tracing is enabled since it may be expensive to compute.
+fn trace(phase: &str, summary: impl FnOnce() -> String) {
+    if env::var_os(TRACE_ENV_VAR).is_some() {
+        eprintln!("[trace] {}: {}", phase, summary());
+    }
+}
+
+/// Registers the extern functions and methods provided by the runtime
+///
+/// Each printable type has two printing functions: `print_*` never ends the line and `println_*`
+/// always does.
+///
+/// Returns an error if the prelude is malformed (e.g. if the same item is registered twice)
+fn insert_prelude(decls: &mut resolve2::ProgramDecls) -> Result<(), resolve2::Error> {
     //TODO: Figure out how to do this properly without hard coding things
 
-    use crate::ir::{FuncSig, FuncParam};
-    use crate::resolve2::FunctionInfo;
+    use crate::ir::{FuncSig, FuncParam, FieldTys};
+    use crate::resolve2::{FunctionInfo, TypeInfo};
 
     let prims = &decls.prims;
     let decls = &mut decls.top_level_decls;
 
-    decls.insert_func(FunctionInfo::new_extern("unit__eq", FuncSig {
+    decls.insert_func(FunctionInfo::new_pure_extern("unit__eq", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "left", ty: prims.unit()},
             FuncParam {name: "right", ty: prims.unit()},
         ],
-    })).unwrap();
+    }))?;
     decls.insert_func(FunctionInfo::new_extern("print_unit", FuncSig {
         return_type: prims.unit(),
         params: vec![
             FuncParam {name: "value", ty: prims.unit()},
         ],
-    })).unwrap();
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("println_unit", FuncSig {
+        return_type: prims.unit(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.unit()},
+        ],
+    }))?;
 
-    decls.insert_func(FunctionInfo::new_extern("bool__eq", FuncSig {
+    decls.insert_func(FunctionInfo::new_pure_extern("bool__eq", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "left", ty: prims.bool()},
             FuncParam {name: "right", ty: prims.bool()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bool_and", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bool_and", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "left", ty: prims.bool()},
             FuncParam {name: "right", ty: prims.bool()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bool_or", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bool_or", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "left", ty: prims.bool()},
             FuncParam {name: "right", ty: prims.bool()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bool__not", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bool__not", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "value", ty: prims.bool()},
         ],
-    })).unwrap();
+    }))?;
+    decls.insert_method(prims.bool(), "not", FunctionInfo::new_pure_extern("bool__not", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "self", ty: prims.bool()},
+        ],
+    }))?;
     decls.insert_func(FunctionInfo::new_extern("print_bool", FuncSig {
         return_type: prims.unit(),
         params: vec![
             FuncParam {name: "value", ty: prims.bool()},
         ],
-    })).unwrap();
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("println_bool", FuncSig {
+        return_type: prims.unit(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.bool()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bool_to_int", FuncSig {
+        return_type: prims.int(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.bool()},
+        ],
+    }))?;
 
-    decls.insert_method(prims.int(), "eq", FunctionInfo::new_extern("int__eq", FuncSig {
+    decls.insert_method(prims.int(), "eq", FunctionInfo::new_pure_extern("int__eq", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
             FuncParam {name: "right", ty: prims.int()},
         ],
-    })).unwrap();
-    decls.insert_method(prims.int(), "gt", FunctionInfo::new_extern("int__gt", FuncSig {
+    }))?;
+    decls.insert_method(prims.int(), "gt", FunctionInfo::new_pure_extern("int__gt", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
             FuncParam {name: "right", ty: prims.int()},
         ],
-    })).unwrap();
-    decls.insert_method(prims.int(), "gte", FunctionInfo::new_extern("int__gte", FuncSig {
+    }))?;
+    decls.insert_method(prims.int(), "gte", FunctionInfo::new_pure_extern("int__gte", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
             FuncParam {name: "right", ty: prims.int()},
         ],
-    })).unwrap();
-    decls.insert_method(prims.int(), "lt", FunctionInfo::new_extern("int__lt", FuncSig {
+    }))?;
+    decls.insert_method(prims.int(), "lt", FunctionInfo::new_pure_extern("int__lt", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
             FuncParam {name: "right", ty: prims.int()},
         ],
-    })).unwrap();
-    decls.insert_method(prims.int(), "lte", FunctionInfo::new_extern("int__lte", FuncSig {
+    }))?;
+    decls.insert_method(prims.int(), "lte", FunctionInfo::new_pure_extern("int__lte", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
             FuncParam {name: "right", ty: prims.int()},
         ],
-    })).unwrap();
+    }))?;
 
-    decls.insert_method(prims.int(), "add", FunctionInfo::new_extern("int__add", FuncSig {
+    decls.insert_method(prims.int(), "add", FunctionInfo::new_pure_extern("int__add", FuncSig {
         return_type: prims.int(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
             FuncParam {name: "other", ty: prims.int()},
         ],
-    })).unwrap();
-    decls.insert_method(prims.int(), "sub", FunctionInfo::new_extern("int__sub", FuncSig {
+    }))?;
+    decls.insert_method(prims.int(), "sub", FunctionInfo::new_pure_extern("int__sub", FuncSig {
         return_type: prims.int(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
             FuncParam {name: "right", ty: prims.int()},
         ],
-    })).unwrap();
-    decls.insert_method(prims.int(), "mul", FunctionInfo::new_extern("int__mul", FuncSig {
+    }))?;
+    decls.insert_method(prims.int(), "mul", FunctionInfo::new_pure_extern("int__mul", FuncSig {
         return_type: prims.int(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
             FuncParam {name: "right", ty: prims.int()},
         ],
-    })).unwrap();
-    decls.insert_method(prims.int(), "div", FunctionInfo::new_extern("int__div", FuncSig {
+    }))?;
+    decls.insert_method(prims.int(), "div", FunctionInfo::new_pure_extern("int__div", FuncSig {
         return_type: prims.int(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
             FuncParam {name: "right", ty: prims.int()},
         ],
-    })).unwrap();
-    decls.insert_method(prims.int(), "rem", FunctionInfo::new_extern("int__rem", FuncSig {
+    }))?;
+    decls.insert_method(prims.int(), "rem", FunctionInfo::new_pure_extern("int__rem", FuncSig {
         return_type: prims.int(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
             FuncParam {name: "right", ty: prims.int()},
         ],
-    })).unwrap();
-    decls.insert_method(prims.int(), "neg", FunctionInfo::new_extern("int__neg", FuncSig {
+    }))?;
+    decls.insert_method(prims.int(), "neg", FunctionInfo::new_pure_extern("int__neg", FuncSig {
         return_type: prims.int(),
         params: vec![
             FuncParam {name: "self", ty: prims.int()},
         ],
-    })).unwrap();
+    }))?;
+
+    // The result of `int::divmod`. This will be replaced by a tuple once the language supports
+    // tuple types.
+    let mut divmod_fields = FieldTys::new();
+    divmod_fields.insert("quotient", prims.int());
+    divmod_fields.insert("remainder", prims.int());
+    let divmod_int = decls.insert_type("divmod_int", TypeInfo {
+        is_extern: true,
+        ..TypeInfo::new("DDivModInt", divmod_fields)
+    })?;
+    decls.insert_method(prims.int(), "divmod", FunctionInfo::new_pure_extern("int__divmod", FuncSig {
+        return_type: divmod_int,
+        params: vec![
+            FuncParam {name: "self", ty: prims.int()},
+            FuncParam {name: "right", ty: prims.int()},
+        ],
+    }))?;
+
     decls.insert_func(FunctionInfo::new_extern("print_int", FuncSig {
         return_type: prims.unit(),
         params: vec![
             FuncParam {name: "value", ty: prims.int()},
         ],
-    })).unwrap();
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("println_int", FuncSig {
+        return_type: prims.unit(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.int()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("int_abs", FuncSig {
+        return_type: prims.int(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.int()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("int_to_bool", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.int()},
+        ],
+    }))?;
 
-    decls.insert_func(FunctionInfo::new_extern("add_real", FuncSig {
+    decls.insert_func(FunctionInfo::new_pure_extern("add_real", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.real()},
+            FuncParam {name: "right", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("sub_real", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.real()},
+            FuncParam {name: "right", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("mul_real", FuncSig {
         return_type: prims.real(),
         params: vec![
             FuncParam {name: "left", ty: prims.real()},
             FuncParam {name: "right", ty: prims.real()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("sub_real", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("div_real", FuncSig {
         return_type: prims.real(),
         params: vec![
             FuncParam {name: "left", ty: prims.real()},
             FuncParam {name: "right", ty: prims.real()},
         ],
-    })).unwrap();
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_eq", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.real()},
+            FuncParam {name: "right", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_lt", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.real()},
+            FuncParam {name: "right", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_lte", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.real()},
+            FuncParam {name: "right", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_gt", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.real()},
+            FuncParam {name: "right", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_gte", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.real()},
+            FuncParam {name: "right", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_neg", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_method(prims.real(), "neg", FunctionInfo::new_pure_extern("real__neg", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "self", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_abs", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_sign", FuncSig {
+        return_type: prims.int(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_sqrt", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_pow", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "base", ty: prims.real()},
+            FuncParam {name: "exponent", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_floor", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_ceil", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_sin", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_cos", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("real_ln", FuncSig {
+        return_type: prims.real(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.real()},
+        ],
+    }))?;
     decls.insert_func(FunctionInfo::new_extern("print_real", FuncSig {
         return_type: prims.unit(),
         params: vec![
             FuncParam {name: "value", ty: prims.real()},
         ],
-    })).unwrap();
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("println_real", FuncSig {
+        return_type: prims.unit(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.real()},
+        ],
+    }))?;
 
-    decls.insert_func(FunctionInfo::new_extern("add_complex", FuncSig {
+    decls.insert_func(FunctionInfo::new_pure_extern("add_complex", FuncSig {
+        return_type: prims.complex(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.complex()},
+            FuncParam {name: "right", ty: prims.complex()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("add_real_complex", FuncSig {
+        return_type: prims.complex(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.real()},
+            FuncParam {name: "right", ty: prims.complex()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("add_complex_real", FuncSig {
+        return_type: prims.complex(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.complex()},
+            FuncParam {name: "right", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("sub_complex", FuncSig {
+        return_type: prims.complex(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.complex()},
+            FuncParam {name: "right", ty: prims.complex()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("sub_real_complex", FuncSig {
+        return_type: prims.complex(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.real()},
+            FuncParam {name: "right", ty: prims.complex()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("sub_complex_real", FuncSig {
+        return_type: prims.complex(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.complex()},
+            FuncParam {name: "right", ty: prims.real()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("mul_complex", FuncSig {
         return_type: prims.complex(),
         params: vec![
             FuncParam {name: "left", ty: prims.complex()},
             FuncParam {name: "right", ty: prims.complex()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("add_real_complex", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("mul_real_complex", FuncSig {
         return_type: prims.complex(),
         params: vec![
             FuncParam {name: "left", ty: prims.real()},
             FuncParam {name: "right", ty: prims.complex()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("add_complex_real", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("mul_complex_real", FuncSig {
         return_type: prims.complex(),
         params: vec![
             FuncParam {name: "left", ty: prims.complex()},
             FuncParam {name: "right", ty: prims.real()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("sub_complex", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("div_complex", FuncSig {
         return_type: prims.complex(),
         params: vec![
             FuncParam {name: "left", ty: prims.complex()},
             FuncParam {name: "right", ty: prims.complex()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("sub_real_complex", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("div_real_complex", FuncSig {
         return_type: prims.complex(),
         params: vec![
             FuncParam {name: "left", ty: prims.real()},
             FuncParam {name: "right", ty: prims.complex()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("sub_complex_real", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("div_complex_real", FuncSig {
         return_type: prims.complex(),
         params: vec![
             FuncParam {name: "left", ty: prims.complex()},
             FuncParam {name: "right", ty: prims.real()},
         ],
-    })).unwrap();
+    }))?;
     decls.insert_func(FunctionInfo::new_extern("print_complex", FuncSig {
         return_type: prims.unit(),
         params: vec![
             FuncParam {name: "value", ty: prims.complex()},
         ],
-    })).unwrap();
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("println_complex", FuncSig {
+        return_type: prims.unit(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.complex()},
+        ],
+    }))?;
 
-    decls.insert_func(FunctionInfo::new_extern("bstr_len", FuncSig {
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_len", FuncSig {
+        return_type: prims.int(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.bstr()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_hash", FuncSig {
         return_type: prims.int(),
         params: vec![
             FuncParam {name: "value", ty: prims.bstr()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bstr_eq", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_eq", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "left", ty: prims.bstr()},
             FuncParam {name: "right", ty: prims.bstr()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bstr_gt", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_gt", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "left", ty: prims.bstr()},
             FuncParam {name: "right", ty: prims.bstr()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bstr_gte", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_gte", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "left", ty: prims.bstr()},
             FuncParam {name: "right", ty: prims.bstr()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bstr_lt", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_lt", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "left", ty: prims.bstr()},
             FuncParam {name: "right", ty: prims.bstr()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bstr_lte", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_lte", FuncSig {
         return_type: prims.bool(),
         params: vec![
             FuncParam {name: "left", ty: prims.bstr()},
             FuncParam {name: "right", ty: prims.bstr()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bstr_concat", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_concat", FuncSig {
         return_type: prims.bstr(),
         params: vec![
             FuncParam {name: "left", ty: prims.bstr()},
             FuncParam {name: "right", ty: prims.bstr()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bstr_slice", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_join3", FuncSig {
+        return_type: prims.bstr(),
+        params: vec![
+            FuncParam {name: "a", ty: prims.bstr()},
+            FuncParam {name: "b", ty: prims.bstr()},
+            FuncParam {name: "c", ty: prims.bstr()},
+            FuncParam {name: "sep", ty: prims.bstr()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_replace", FuncSig {
+        return_type: prims.bstr(),
+        params: vec![
+            FuncParam {name: "string", ty: prims.bstr()},
+            FuncParam {name: "from", ty: prims.bstr()},
+            FuncParam {name: "to", ty: prims.bstr()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_slice", FuncSig {
         return_type: prims.bstr(),
         params: vec![
             FuncParam {name: "string", ty: prims.bstr()},
             FuncParam {name: "start", ty: prims.int()},
             FuncParam {name: "end", ty: prims.int()},
         ],
-    })).unwrap();
-    decls.insert_func(FunctionInfo::new_extern("bstr_get", FuncSig {
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_get", FuncSig {
         return_type: prims.bstr(),
         params: vec![
             FuncParam {name: "string", ty: prims.bstr()},
             FuncParam {name: "index", ty: prims.int()},
         ],
-    })).unwrap();
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_contains_byte", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "string", ty: prims.bstr()},
+            FuncParam {name: "byte", ty: prims.int()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_find", FuncSig {
+        return_type: prims.int(),
+        params: vec![
+            FuncParam {name: "haystack", ty: prims.bstr()},
+            FuncParam {name: "needle", ty: prims.bstr()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_contains", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "haystack", ty: prims.bstr()},
+            FuncParam {name: "needle", ty: prims.bstr()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_before", FuncSig {
+        return_type: prims.bstr(),
+        params: vec![
+            FuncParam {name: "string", ty: prims.bstr()},
+            FuncParam {name: "index", ty: prims.int()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_after", FuncSig {
+        return_type: prims.bstr(),
+        params: vec![
+            FuncParam {name: "string", ty: prims.bstr()},
+            FuncParam {name: "index", ty: prims.int()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_byte_min", FuncSig {
+        return_type: prims.int(),
+        params: vec![
+            FuncParam {name: "string", ty: prims.bstr()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_byte_max", FuncSig {
+        return_type: prims.int(),
+        params: vec![
+            FuncParam {name: "string", ty: prims.bstr()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_from_int", FuncSig {
+        return_type: prims.bstr(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.int()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_from_int_radix", FuncSig {
+        return_type: prims.bstr(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.int()},
+            FuncParam {name: "radix", ty: prims.int()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("bstr_to_int", FuncSig {
+        return_type: prims.option_int(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.bstr()},
+        ],
+    }))?;
     decls.insert_func(FunctionInfo::new_extern("print_bstr", FuncSig {
         return_type: prims.unit(),
         params: vec![
             FuncParam {name: "value", ty: prims.bstr()},
         ],
-    })).unwrap();
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("println_bstr", FuncSig {
+        return_type: prims.unit(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.bstr()},
+        ],
+    }))?;
+
+    decls.insert_func(FunctionInfo::new_extern("panic", FuncSig {
+        return_type: prims.never(),
+        params: vec![
+            FuncParam {name: "message", ty: prims.bstr()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("assert", FuncSig {
+        return_type: prims.unit(),
+        params: vec![
+            FuncParam {name: "cond", ty: prims.bool()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("assert_eq_bstr", FuncSig {
+        return_type: prims.unit(),
+        params: vec![
+            FuncParam {name: "left", ty: prims.bstr()},
+            FuncParam {name: "right", ty: prims.bstr()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("unreachable", FuncSig {
+        return_type: prims.never(),
+        params: Vec::new(),
+    }))?;
 
     decls.insert_func(FunctionInfo::new_extern("read_line_bstr", FuncSig {
         return_type: prims.bstr(),
         params: Vec::new(),
-    })).unwrap();
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("read_all_bstr", FuncSig {
+        return_type: prims.bstr(),
+        params: Vec::new(),
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("read_file", FuncSig {
+        return_type: prims.bstr(),
+        params: vec![
+            FuncParam {name: "path", ty: prims.bstr()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_extern("write_file", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "path", ty: prims.bstr()},
+            FuncParam {name: "contents", ty: prims.bstr()},
+        ],
+    }))?;
+
+    decls.insert_func(FunctionInfo::new_pure_extern("some_int", FuncSig {
+        return_type: prims.option_int(),
+        params: vec![
+            FuncParam {name: "value", ty: prims.int()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("none_int", FuncSig {
+        return_type: prims.option_int(),
+        params: Vec::new(),
+    }))?;
+    decls.insert_method(prims.option_int(), "is_some", FunctionInfo::new_pure_extern("option_int__is_some", FuncSig {
+        return_type: prims.bool(),
+        params: vec![
+            FuncParam {name: "self", ty: prims.option_int()},
+        ],
+    }))?;
+    decls.insert_method(prims.option_int(), "unwrap_int", FunctionInfo::new_pure_extern("option_int__unwrap_int", FuncSig {
+        return_type: prims.int(),
+        params: vec![
+            FuncParam {name: "self", ty: prims.option_int()},
+        ],
+    }))?;
+
+    decls.insert_func(FunctionInfo::new_pure_extern("int_list_new", FuncSig {
+        return_type: prims.int_list(),
+        params: Vec::new(),
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("int_list_push", FuncSig {
+        return_type: prims.int_list(),
+        params: vec![
+            FuncParam {name: "list", ty: prims.int_list()},
+            FuncParam {name: "value", ty: prims.int()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("int_list_len", FuncSig {
+        return_type: prims.int(),
+        params: vec![
+            FuncParam {name: "list", ty: prims.int_list()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("int_list_get", FuncSig {
+        return_type: prims.int(),
+        params: vec![
+            FuncParam {name: "list", ty: prims.int_list()},
+            FuncParam {name: "index", ty: prims.int()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("int_list_sort", FuncSig {
+        return_type: prims.int_list(),
+        params: vec![
+            FuncParam {name: "list", ty: prims.int_list()},
+        ],
+    }))?;
+    decls.insert_func(FunctionInfo::new_pure_extern("int_list_sort_desc", FuncSig {
+        return_type: prims.int_list(),
+        params: vec![
+            FuncParam {name: "list", ty: prims.int_list()},
+        ],
+    }))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dep_file_lists_imported_modules() {
+        let program = compile_executable2("tests/run-pass/import-modules.dino",
+            &trans::CodegenOptions::default()).unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let dep_file_path = dir.path().join("import-modules.d");
+        write_dep_file(&dep_file_path, Path::new("out dir/import-modules"), &program.source_paths).unwrap();
+
+        // The entry file comes first, followed by each module in the order it was loaded
+        assert_eq!(fs::read_to_string(&dep_file_path).unwrap(), "out\\ dir/import-modules: \
+            tests/run-pass/import-modules.dino \
+            tests/run-pass/modules/geometry.dino \
+            tests/run-pass/modules/shapes/square.dino\n");
+    }
+
+    #[test]
+    fn symbol_map_lists_generated_symbols() {
+        let program = compile_executable2("tests/run-pass/import-modules.dino",
+            &trans::CodegenOptions::default()).unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let symbol_map_path = dir.path().join("import-modules.syms");
+        write_symbol_map(&symbol_map_path, &program.code).unwrap();
+
+        // Items from imported modules are listed by their qualified names along with the items of
+        // the entry file
+        assert_eq!(fs::read_to_string(&symbol_map_path).unwrap(), "\
+            struct\tpackage::modules::geometry::Point\tdino_7package7modules8geometry5Point\n\
+            struct\tpackage::modules::shapes::square::Square\t\
+                dino_7package7modules6shapes6square6Square\n\
+            fn\tpackage::modules::geometry::coord_sum\tdino_7package7modules8geometry9coord_sum\n\
+            fn\tpackage::modules::shapes::square::square_area\t\
+                dino_7package7modules6shapes6square11square_area\n\
+            fn\tpackage::modules::shapes::square::square_bottom_right\t\
+                dino_7package7modules6shapes6square19square_bottom_right\n\
+            fn\tmain\tmain\n");
+    }
+
+    #[test]
+    fn warnings_name_their_module() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let main_path = dir.path().join("main.dino");
+        let helpers_path = dir.path().join("helpers.dino");
+        fs::write(&main_path, "use package::helpers::*;\nfn main() { println_int(scaled(2)); }\n").unwrap();
+        fs::write(&helpers_path, "fn scaled(x: int) -> int { let unused = 1; x * 3 }\n").unwrap();
+
+        let program = compile_executable2(&main_path, &trans::CodegenOptions::default()).unwrap();
+
+        // The warning is reported in the module that contains the function, not the entry file
+        assert_eq!(program.warnings.len(), 1);
+        assert_eq!(program.warnings[0].path, helpers_path);
+        assert!(matches!(&program.warnings[0].warning,
+            tycheck::Warning::UnusedVariable {name, ..} if name == "unused"));
+    }
 }
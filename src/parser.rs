@@ -103,21 +103,42 @@ fn decl(input: Input) -> ParseResult<Decl> {
     ))(input)
 }
 
+/// An import, which also re-exports the imported names if it starts with `pub`
 fn use_decl(input: Input) -> ParseResult<ImportPath> {
-    surrounded(kw(Kw::Use), import_path, tk(Semicolon))(input)
-}
-
-fn import_path(input: Input) -> ParseResult<ImportPath> {
     map(
         tuple((
-            opt(suffixed(path_prefix, tk(DoubleColon))),
-            many0(suffixed(ident, tk(DoubleColon))),
-            import_path_selection,
+            opt(kw(Kw::Pub)),
+            surrounded(kw(Kw::Use), import_path, tk(Semicolon)),
         )),
-        |(prefix, path, selection)| ImportPath {prefix, path, selection},
+        |(pub_token, import)| ImportPath {is_pub: pub_token.is_some(), ..import},
     )(input)
 }
 
+fn import_path(input: Input) -> ParseResult<ImportPath> {
+    let (mut input, prefix) = opt(suffixed(path_prefix, tk(DoubleColon)))(input)?;
+
+    // An identifier is only part of the path if it is followed by `::`. Otherwise, it is the single
+    // name being imported (e.g. `use shapes::square;`). This can't be parsed with `many0` since the
+    // identifier would already be consumed by the time the missing `::` is found.
+    let mut path = Vec::new();
+    while let Ok((next_input, name)) = ident(input) {
+        match tk(DoubleColon)(next_input) {
+            Ok((next_input, _)) => {
+                path.push(name);
+                input = next_input;
+            },
+            Err(_) => {
+                let (next_input, alias) = opt(prefixed(kw(Kw::As), ident))(next_input)?;
+                let selection = ImportSelection::Names(vec![ImportName::Name {name, alias}]);
+                return Ok((next_input, ImportPath {prefix, path, selection, is_pub: false}));
+            },
+        }
+    }
+
+    let (input, selection) = import_path_selection(input)?;
+    Ok((input, ImportPath {prefix, path, selection, is_pub: false}))
+}
+
 fn import_path_selection(input: Input) -> ParseResult<ImportSelection> {
     alt((
         map(tk(Star), |token| ImportSelection::All(token.span)),
@@ -240,6 +261,7 @@ fn stmt(input: Input) -> ParseResult<Stmt> {
     alt((
         map(cond, Stmt::Cond),
         map(while_loop, Stmt::WhileLoop),
+        map(for_loop, Stmt::ForLoop),
         map(var_decl, Stmt::VarDecl),
         map(suffixed(expr, tk(Semicolon)), Stmt::Expr),
     ))(input)
@@ -252,6 +274,20 @@ fn while_loop(input: Input) -> ParseResult<WhileLoop> {
     )(input)
 }
 
+/// Parses a loop over an exclusive range of integers: `for <ident> in <start>..<end> { ... }`
+fn for_loop(input: Input) -> ParseResult<ForLoop> {
+    map(
+        tuple((kw(Kw::For), ident, kw(Kw::In), prec3, tk(DoublePeriod), prec3, block)),
+        |(for_token, var, _, start, _, end, body)| ForLoop {
+            for_span: for_token.span,
+            var,
+            start,
+            end,
+            body,
+        },
+    )(input)
+}
+
 fn var_decl(input: Input) -> ParseResult<VarDecl> {
     map(
         tuple((kw(Kw::Let), ident, opt(prefixed(tk(Colon), ty)), tk(Equals), expr, tk(Semicolon))),
@@ -278,11 +314,23 @@ fn prec0(input: Input) -> ParseResult<Expr> {
 }
 
 fn prec1(input: Input) -> ParseResult<Expr> {
+    let compound_op = alt((
+        map(tk(PlusEquals), |token| NumericOp::Add(token.span)),
+        map(tk(MinusEquals), |token| NumericOp::Sub(token.span)),
+        map(tk(StarEquals), |token| NumericOp::Mul(token.span)),
+        map(tk(SlashEquals), |token| NumericOp::Div(token.span)),
+        map(tk(PercentEquals), |token| NumericOp::Rem(token.span)),
+    ));
+
     alt((
         map(
             tuple((prec2, tk(Equals), expr)),
             |(lhs, _, rhs)| Expr::Assign(Box::new(Assign {lhs, rhs})),
         ),
+        map(
+            tuple((prec2, compound_op, expr)),
+            |(lhs, op, rhs)| Expr::CompoundAssign(Box::new(CompoundAssign {lhs, op, rhs})),
+        ),
         prec2,
     ))(input)
 }
@@ -417,6 +465,10 @@ fn prec13(input: Input) -> ParseResult<Expr> {
     )(input)
 }
 
+/// Parses an optional prefix unary operator (`+`, `-`, or `!`)
+///
+/// Unary operators bind more loosely than method calls, field accesses, function calls, and
+/// indexing, so `-5.add(3)` is parsed as `-(5.add(3))` and `-x.y` is parsed as `-(x.y)`.
 fn prec14(input: Input) -> ParseResult<Expr> {
     let unary_op = alt((
         map(tk(Plus), |token| UnaryOp::Pos(token.span)),
@@ -735,3 +787,242 @@ fn tk(kind: TokenKind) -> impl FnMut(Input) -> ParseResult<&Token> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use parking_lot::RwLock;
+
+    use crate::source_files::SourceFiles;
+
+    /// Parses the given source as a module, returning the module and the number of errors emitted
+    fn parse(source: &[u8]) -> (Module, usize) {
+        let source_files = Arc::new(RwLock::new(SourceFiles::default()));
+        let root_file = source_files.write().add_source("test.dino", source);
+        let diag = Diagnostics::new(source_files.clone(), termcolor::ColorChoice::Auto);
+        let files = source_files.read();
+        let module = parse_module(files.source(root_file), &diag);
+        (module, diag.emitted_errors())
+    }
+
+    #[test]
+    fn small_program() {
+        let (module, errors) = parse(b"
+            fn add(x: int, y: int) -> int {
+                x + y
+            }
+
+            fn main() {
+                let count: int = 0;
+                let limit = 3;
+                while count < limit.min(10) {
+                    if count == 0 {
+                        print_int(add(count, 1));
+                    } else if count == 1 {
+                        count.print();
+                    } else {
+                        print_real(2.5);
+                    }
+                    count = count + 1;
+                }
+            }
+        ");
+        assert_eq!(errors, 0);
+
+        let names: Vec<_> = module.decls.iter().map(|decl| match decl {
+            Decl::Function(func) => &*func.name.value,
+            _ => panic!("expected only functions, found: {:?}", decl),
+        }).collect();
+        assert_eq!(names, &["add", "main"]);
+
+        let main = match &module.decls[1] {
+            Decl::Function(func) => func,
+            _ => unreachable!(),
+        };
+        assert!(main.sig.params.is_empty());
+        assert_eq!(main.sig.return_type, None);
+
+        let Block {decls, stmts, ret, span: _} = &main.body;
+        assert!(decls.is_empty());
+        assert!(ret.is_none());
+        match &stmts[..] {
+            [Stmt::VarDecl(count), Stmt::VarDecl(limit), Stmt::WhileLoop(wloop)] => {
+                assert_eq!(&*count.name.value, "count");
+                assert!(count.ty.is_some());
+                assert_eq!(&*limit.name.value, "limit");
+                assert!(limit.ty.is_none());
+
+                match &wloop.body.stmts[..] {
+                    [Stmt::Cond(cond), Stmt::Expr(Expr::Assign(_))] => {
+                        assert_eq!(cond.conds.len(), 2);
+                        assert!(cond.else_body.is_some());
+                    },
+                    stmts => panic!("unexpected loop body: {:?}", stmts),
+                }
+            },
+            stmts => panic!("unexpected statements: {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn use_decls() {
+        let (module, errors) = parse(b"
+            use shapes::{circle as round, self as geometry};
+            pub use shapes::square;
+        ");
+        assert_eq!(errors, 0);
+
+        let imports: Vec<_> = module.decls.iter().map(|decl| match decl {
+            Decl::Import(import) => import,
+            _ => panic!("expected only imports, found: {:?}", decl),
+        }).collect();
+
+        match &imports[..] {
+            [aliased, reexport] => {
+                assert!(!aliased.is_pub);
+                match &aliased.selection {
+                    ImportSelection::Names(names) => match &names[..] {
+                        [ImportName::Name {name, alias: Some(alias)}, ImportName::SelfValue {alias: Some(self_alias)}] => {
+                            assert_eq!(&*name.value, "circle");
+                            assert_eq!(&*alias.value, "round");
+                            assert_eq!(&*self_alias.value, "geometry");
+                        },
+                        names => panic!("unexpected import names: {:?}", names),
+                    },
+                    selection => panic!("expected a list of names, found: {:?}", selection),
+                }
+
+                assert!(reexport.is_pub);
+                match &reexport.selection {
+                    ImportSelection::Names(names) => match &names[..] {
+                        [ImportName::Name {name, alias: None}] => assert_eq!(&*name.value, "square"),
+                        names => panic!("unexpected import names: {:?}", names),
+                    },
+                    selection => panic!("expected a list of names, found: {:?}", selection),
+                }
+            },
+            imports => panic!("unexpected imports: {:?}", imports),
+        }
+    }
+
+    /// Parses the given source as the body of a function, returning its final expression
+    fn parse_ret_expr(source: &str) -> Expr {
+        let (mut module, errors) = parse(format!("fn main() {{ {} }}", source).as_bytes());
+        assert_eq!(errors, 0);
+        match module.decls.pop() {
+            Some(Decl::Function(func)) => func.body.ret.expect("expected a final expression"),
+            decl => panic!("expected a function, found: {:?}", decl),
+        }
+    }
+
+    #[test]
+    fn unary_minus() {
+        match parse_ret_expr("-5") {
+            Expr::UnaryOp(unary) => match (unary.op, &unary.expr) {
+                (UnaryOp::Neg(_), Expr::IntegerLiteral(lit)) => assert_eq!(lit.value, 5),
+                expr => panic!("unexpected expression: {:?}", expr),
+            },
+            expr => panic!("expected a negation, found: {:?}", expr),
+        }
+
+        match parse_ret_expr("-1.5") {
+            Expr::UnaryOp(unary) => match (unary.op, &unary.expr) {
+                (UnaryOp::Neg(_), Expr::RealLiteral(lit)) => assert_eq!(lit.value, 1.5),
+                expr => panic!("unexpected expression: {:?}", expr),
+            },
+            expr => panic!("expected a negation, found: {:?}", expr),
+        }
+
+        // Method calls bind more tightly than unary minus
+        match parse_ret_expr("-5.add(3)") {
+            Expr::UnaryOp(unary) => match (unary.op, &unary.expr) {
+                (UnaryOp::Neg(_), Expr::MethodCall(call)) => assert_eq!(&*call.method_name.value, "add"),
+                expr => panic!("unexpected expression: {:?}", expr),
+            },
+            expr => panic!("expected a negation, found: {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn binary_operator_precedence() {
+        // Multiplication binds more tightly than addition
+        match parse_ret_expr("1 + 2 * 3") {
+            Expr::NumericOp(add) => match (add.op, &add.lhs, &add.rhs) {
+                (NumericOp::Add(_), Expr::IntegerLiteral(lit), Expr::NumericOp(mul)) => {
+                    assert_eq!(lit.value, 1);
+                    match mul.op {
+                        NumericOp::Mul(_) => {},
+                        op => panic!("expected a multiplication, found: {:?}", op),
+                    }
+                },
+                expr => panic!("unexpected expression: {:?}", expr),
+            },
+            expr => panic!("expected an addition, found: {:?}", expr),
+        }
+
+        // Operators with the same precedence are left associative
+        match parse_ret_expr("1 - 2 - 3") {
+            Expr::NumericOp(outer) => match (outer.op, &outer.lhs, &outer.rhs) {
+                (NumericOp::Sub(_), Expr::NumericOp(inner), Expr::IntegerLiteral(lit)) => {
+                    assert_eq!(lit.value, 3);
+                    match (inner.op, &inner.lhs, &inner.rhs) {
+                        (NumericOp::Sub(_), Expr::IntegerLiteral(lhs), Expr::IntegerLiteral(rhs)) => {
+                            assert_eq!((lhs.value, rhs.value), (1, 2));
+                        },
+                        expr => panic!("unexpected expression: {:?}", expr),
+                    }
+                },
+                expr => panic!("unexpected expression: {:?}", expr),
+            },
+            expr => panic!("expected a subtraction, found: {:?}", expr),
+        }
+
+        // Comparisons bind more loosely than arithmetic
+        match parse_ret_expr("1 + 2 <= 3 * 4") {
+            Expr::CompareOp(cmp) => match (cmp.op, &cmp.lhs, &cmp.rhs) {
+                (CompareOp::Le(_), Expr::NumericOp(_), Expr::NumericOp(_)) => {},
+                expr => panic!("unexpected expression: {:?}", expr),
+            },
+            expr => panic!("expected a comparison, found: {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn for_loop() {
+        let (module, errors) = parse(b"
+            fn main() {
+                for i in 0..3 {
+                    print_int(i);
+                }
+            }
+        ");
+        assert_eq!(errors, 0);
+
+        let main = match &module.decls[..] {
+            [Decl::Function(func)] => func,
+            decls => panic!("expected a single function, found: {:?}", decls),
+        };
+        match &main.body.stmts[..] {
+            [Stmt::ForLoop(for_loop)] => {
+                assert_eq!(&*for_loop.var.value, "i");
+                match (&for_loop.start, &for_loop.end) {
+                    (Expr::IntegerLiteral(start), Expr::IntegerLiteral(end)) => {
+                        assert_eq!((start.value, end.value), (0, 3));
+                    },
+                    range => panic!("unexpected range: {:?}", range),
+                }
+                assert_eq!(for_loop.body.stmts.len(), 1);
+            },
+            stmts => panic!("expected a for loop, found: {:?}", stmts),
+        }
+    }
+
+    #[test]
+    fn syntax_error() {
+        // Errors are reported through the diagnostics rather than causing a panic
+        let (module, errors) = parse(b"fn main() { let = 1; }");
+        assert_eq!(errors, 1);
+        assert!(module.decls.is_empty());
+    }
+}
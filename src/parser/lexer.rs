@@ -79,12 +79,17 @@ impl<'a> Lexer<'a> {
             (b'~', _) => self.byte_token(start, Tilde),
             (b'^', _) => self.byte_token(start, Caret),
 
+            (b'+', Some(b'=')) => self.next_token(start, PlusEquals),
             (b'+', _) => self.byte_token(start, Plus),
+            (b'-', Some(b'=')) => self.next_token(start, MinusEquals),
             (b'-', _) => self.byte_token(start, Minus),
+            (b'*', Some(b'=')) => self.next_token(start, StarEquals),
             (b'*', _) => self.byte_token(start, Star),
             // Note that we don't need to check for `//` or `/*` because comments have already been
             // ignored
+            (b'/', Some(b'=')) => self.next_token(start, SlashEquals),
             (b'/', _) => self.byte_token(start, Slash),
+            (b'%', Some(b'=')) => self.next_token(start, PercentEquals),
             (b'%', _) => self.byte_token(start, Percent),
 
             (b'b', Some(b'"')) => {
@@ -159,18 +164,29 @@ impl<'a> Lexer<'a> {
         // The count of unmatched `/*` seen
         let mut count = 1;
         while count > 0 {
-            match (self.scanner.next(), self.scanner.next()) {
-                (Some(b'/'), Some(b'*')) => count += 1,
-                (Some(b'*'), Some(b'/')) => count -= 1,
+            // Only advance past both bytes when they form a delimiter so that a delimiter starting
+            // at an odd offset (e.g. the `*/` in `/* a */`) is never skipped over
+            match (self.scanner.peek(), self.scanner.peek2()) {
+                (Some(b'/'), Some(b'*')) => {
+                    self.scanner.next2();
+                    count += 1;
+                },
+                (Some(b'*'), Some(b'/')) => {
+                    self.scanner.next2();
+                    count -= 1;
+                },
                 // If we reach EOF, stop iterating
-                (None, _) | (_, None) => break,
+                (None, _) => break,
                 // Ignore all characters between the comment delimiters
-                _ => {},
+                _ => {
+                    self.scanner.next();
+                },
             }
         }
 
         if count > 0 {
-            let span = self.scanner.span(start, self.scanner.current_pos());
+            // Point at the opening `/*` since the end of the file is not a useful location
+            let span = self.scanner.span(start, start + 2);
             self.diag.span_error(span, "unterminated block comment").emit();
         }
     }
@@ -185,57 +201,120 @@ impl<'a> Lexer<'a> {
     }
 
     // Parses the remaining byte string literal after `b"`
+    //
+    //TODO: String interpolation (`{expr}`) is not supported yet. When it is added, `{{` and `}}`
+    // must produce a literal `{` and `}` respectively, and a `{` with no matching `}` must be
+    // reported as an error (e.g. "unmatched `{` in interpolated string").
     fn bstr_lit(&mut self, start: usize) -> Token {
         let mut unescaped_text = Vec::new();
-        while let Some(ch) = self.scanner.next() {
-            if ch == b'"' {
-                break;
+        // Invalid escapes are reported as they are found, but we keep going until the end of the
+        // literal so that the rest of the literal does not produce a bunch of garbage tokens
+        let mut found_invalid_escape = false;
+        loop {
+            let ch_start = self.scanner.current_pos();
+            match self.scanner.next() {
+                Some(b'"') => break,
+
+                Some(b'\\') => match self.bstr_escape(ch_start) {
+                    Some(unescaped_char) => unescaped_text.push(unescaped_char),
+                    None => found_invalid_escape = true,
+                },
 
-            } else if ch == b'\\' {
-                let unescaped_char = match self.scanner.next() {
-                    Some(b'\\') => b'\\',
-                    Some(b'"') => b'\"',
-                    Some(b'\'') => b'\'',
-                    Some(b'n') => b'\n',
-                    Some(b'r') => b'\r',
-                    Some(b't') => b'\t',
-                    Some(b'0') => b'\0',
-                    //TODO: Support more kinds of escapes:
-                    // ASCII/Byte strings: https://doc.rust-lang.org/reference/tokens.html#ascii-escapes
-                    // Unicode strings: https://doc.rust-lang.org/reference/tokens.html#unicode-escapes
-
-                    Some(ch) => {
-                        let token = self.byte_token(start, TokenKind::Error);
-                        self.diag.span_error(token.span, format!("unknown character escape: `\\{}`", ch as char)).emit();
-                        return token;
-                    },
-                    None => {
-                        let token = self.token_to_current(start, TokenKind::Error, None);
-                        self.diag.span_error(token.span, "unterminated double quote byte string").emit();
-                        return token;
-                    },
-                };
-                unescaped_text.push(unescaped_char);
+                Some(ch) => unescaped_text.push(ch),
 
-            } else {
-                unescaped_text.push(ch);
+                None => {
+                    // Point at the opening quote, right after the `b`
+                    let quote_span = self.scanner.byte_span(start + 1);
+                    let token = self.token_to_current(start, TokenKind::Error, None);
+                    self.diag.span_error(quote_span, "unterminated double quote byte string").emit();
+                    return token;
+                },
             }
         }
 
+        if found_invalid_escape {
+            return self.token_to_current(start, TokenKind::Error, None);
+        }
+
         let data = TokenData::BStr {unescaped_text: unescaped_text.into()};
         self.token_to_current(start, TokenKind::Literal(LitKind::BStr), data)
     }
 
+    /// Parses the remainder of an escape sequence in a byte string literal, given the position of
+    /// the `\` that started it
+    ///
+    /// Returns the byte produced by the escape, or None if the escape was invalid. An error is
+    /// reported for every invalid escape except one that is cut off by the end of the file, since
+    /// that will be reported as an unterminated literal.
+    fn bstr_escape(&mut self, backslash: usize) -> Option<u8> {
+        let unescaped_char = match self.scanner.next()? {
+            b'\\' => b'\\',
+            b'"' => b'"',
+            b'\'' => b'\'',
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'0' => b'\0',
+
+            // A byte escape with exactly two hex digits, e.g. `\x7F`
+            b'x' => {
+                let digits_start = self.scanner.current_pos();
+                while self.scanner.current_pos() - digits_start < 2 {
+                    match self.scanner.peek() {
+                        Some(ch) if ch.is_ascii_hexdigit() => {
+                            self.scanner.next();
+                        },
+                        _ => break,
+                    }
+                }
+
+                let digits_end = self.scanner.current_pos();
+                if digits_end - digits_start < 2 {
+                    let span = self.scanner.span(backslash, digits_end);
+                    self.diag.span_error(span, "byte escapes must have exactly two hexadecimal digits (e.g. `\\x7F`)").emit();
+                    return None;
+                }
+
+                u8::from_str_radix(self.scanner.slice(digits_start, digits_end), 16)
+                    .expect("bug: two hex digits should always fit in a byte")
+            },
+
+            //TODO: Support unicode escapes once there is a unicode string type:
+            // https://doc.rust-lang.org/reference/tokens.html#unicode-escapes
+
+            ch => {
+                let span = self.scanner.byte_span(backslash);
+                self.diag.span_error(span, format!("unknown character escape: `\\{}`", ch as char)).emit();
+                return None;
+            },
+        };
+
+        Some(unescaped_char)
+    }
+
     /// Parses a numeric literal, given a starting digit
     ///
     /// If the literal is immediately followed by an ident, we will attempt to parse that as a
     /// literal suffix (e.g. `123int` or `3.4j`).
     fn num_lit(&mut self, start: usize) -> Token {
+        // Integer literals in other bases start with a prefix that specifies the base. The leading
+        // `0` has already been consumed.
+        if self.scanner.slice(start, self.scanner.current_pos()) == "0" {
+            match self.scanner.peek() {
+                Some(b'x') => return self.radix_int_lit(start, 16),
+                Some(b'o') => return self.radix_int_lit(start, 8),
+                Some(b'b') => return self.radix_int_lit(start, 2),
+                _ => {},
+            }
+        }
+
         // true if this is a real number literal
         let mut real = false;
+        // false if any digit separator was misplaced (this has already been reported)
+        let mut valid_separators = true;
         // Try to get as many additional digits as possible. Totally fine if we get zero more
         // digits, because we already have one.
-        self.digits();
+        valid_separators &= self.digits(true, |ch| ch.is_ascii_digit()).is_some();
 
         // Check for floating-point part (optional)
         // Only a floating point part if followed by a digit since otherwise this could be a period
@@ -245,7 +324,7 @@ impl<'a> Lexer<'a> {
                 // Skip the '.'
                 self.scanner.next();
                 // Scan for one or more digits
-                self.digits();
+                valid_separators &= self.digits(false, |ch| ch.is_ascii_digit()).is_some();
                 real = true;
             },
 
@@ -254,6 +333,7 @@ impl<'a> Lexer<'a> {
 
         match self.scanner.peek() {
             Some(b'e') | Some(b'E') => {
+                let exponent_start = self.scanner.current_pos();
                 self.scanner.next();
                 // Check for a sign, `+` or `-`
                 match self.scanner.peek() {
@@ -263,11 +343,15 @@ impl<'a> Lexer<'a> {
                     _ => {},
                 }
                 // Find remaining digits
-                let found_digits = self.digits();
-                if found_digits == 0 {
-                    let token = self.token_to_current(start, TokenKind::Error, None);
-                    self.diag.span_error(token.span, "expected at least one digit in exponent").emit();
-                    return token;
+                match self.digits(false, |ch| ch.is_ascii_digit()) {
+                    Some(0) => {
+                        let token = self.token_to_current(start, TokenKind::Error, None);
+                        let exponent_span = self.scanner.byte_span(exponent_start);
+                        self.diag.span_error(exponent_span, "expected at least one digit in exponent").emit();
+                        return token;
+                    },
+                    Some(_) => {},
+                    None => valid_separators = false,
                 }
 
                 real = true;
@@ -278,6 +362,12 @@ impl<'a> Lexer<'a> {
         // One past the end index of the literal
         let lit_end = self.scanner.current_pos();
 
+        if !valid_separators {
+            return self.invalid_num_lit(start);
+        }
+        // The digit separators are only for readability and are not part of the value
+        let lit_text = self.scanner.slice(start, lit_end).replace('_', "");
+
         // Check for suffix
         let suffix = match self.scanner.peek() {
             Some(b'a' ..= b'z') |
@@ -293,7 +383,7 @@ impl<'a> Lexer<'a> {
                     "int" => Some(Suffix::Int),
                     "real" => Some(Suffix::Real),
                     "j" | "J" | "i" | "I" => {
-                        let value = self.scanner.slice(start, lit_end).parse()
+                        let value = lit_text.parse()
                             .expect("bug: should have been a valid `f64` literal");
                         let data = TokenData::Complex(value);
                         return self.token_to_current(start, TokenKind::Literal(LitKind::Complex), data);
@@ -309,37 +399,159 @@ impl<'a> Lexer<'a> {
         };
 
         if real {
-            if suffix.is_some() {
+            // The `real` suffix is allowed (e.g. `1e3real`), but it doesn't change anything since
+            // the literal is already a real number
+            if suffix == Some(Suffix::Int) {
                 let token = self.token_to_current(start, TokenKind::Error, None);
-                self.diag.span_error(token.span, "real number literals may not have a suffix").emit();
+                self.diag.span_error(token.span, "real number literals may not have an `int` suffix").emit();
                 return token;
             }
 
-            let value = self.scanner.slice(start, lit_end).parse()
+            let value = lit_text.parse()
                 .expect("bug: should have been a valid `f64` literal");
             self.token_to_current(start, TokenKind::Literal(LitKind::Real), TokenData::Real(value))
         } else {
-            let value = self.scanner.slice(start, lit_end).parse()
-                .expect("bug: should have been a valid `i64` literal");
+            let value = match lit_text.parse() {
+                Ok(value) => value,
+                // The literal only contains digits, so the only way parsing can fail is overflow
+                Err(_) => return self.int_lit_too_large(start, lit_end),
+            };
             let data = TokenData::Integer(value, suffix);
             self.token_to_current(start, TokenKind::Literal(LitKind::Integer), data)
         }
     }
 
-    /// Advances the scanner until no more digits are found. Returns the number of digits found.
+    /// Parses the remaining integer literal after the `0` of a base prefix (e.g. `0x`)
     ///
-    /// The final, non-digit character is NOT consumed
-    fn digits(&mut self) -> usize {
+    /// Like decimal integer literals, the literal may be followed by an `int` suffix. A `real`
+    /// suffix is not allowed.
+    fn radix_int_lit(&mut self, start: usize, radix: u32) -> Token {
+        // Skip the character that specifies the base
+        self.scanner.next();
+
+        // Letters are only digits in hexadecimal. In every other base, a letter begins a suffix.
+        // Digits that are invalid for the base are checked for below so they can be reported.
+        let digits_start = self.scanner.current_pos();
+        let digit_count = self.digits(false, |ch| ch.is_ascii_digit() || (radix == 16 && ch.is_ascii_hexdigit()));
+        let lit_end = self.scanner.current_pos();
+
+        let digit_count = match digit_count {
+            Some(digit_count) => digit_count,
+            None => return self.invalid_num_lit(start),
+        };
+
+        let base_name = match radix {
+            2 => "binary",
+            8 => "octal",
+            16 => "hexadecimal",
+            _ => unreachable!("bug: unsupported integer literal base"),
+        };
+
+        // Check for suffix
+        let suffix = match self.scanner.peek() {
+            Some(b'a' ..= b'z') |
+            Some(b'A' ..= b'Z') |
+            Some(b'_') => {
+                let suffix_start = self.scanner.current_pos();
+                self.scanner.next();
+                // Find an ident, but ignore the token
+                self.ident(suffix_start);
+
+                match self.scanner.slice(suffix_start, self.scanner.current_pos()) {
+                    "int" => Some(Suffix::Int),
+                    suffix => {
+                        let token = self.token_to_current(suffix_start, TokenKind::Error, None);
+                        self.diag.span_error(token.span, format!("invalid suffix `{}` for {} literal", suffix, base_name)).emit();
+                        return token;
+                    },
+                }
+            },
+            _ => None,
+        };
+
+        if digit_count == 0 {
+            let token = self.token_to_current(start, TokenKind::Error, None);
+            self.diag.span_error(token.span, format!("expected at least one digit in {} literal", base_name)).emit();
+            return token;
+        }
+
+        let digits = self.scanner.slice(digits_start, lit_end);
+        if let Some(index) = digits.bytes().position(|ch| ch != b'_' && !(ch as char).is_digit(radix)) {
+            let digit_span = self.scanner.byte_span(digits_start + index);
+            let token = self.token_to_current(start, TokenKind::Error, None);
+            let digit = digits.as_bytes()[index] as char;
+            self.diag.span_error(digit_span, format!("invalid digit `{}` in {} literal", digit, base_name)).emit();
+            return token;
+        }
+
+        let value = match i64::from_str_radix(&digits.replace('_', ""), radix) {
+            Ok(value) => value,
+            // All of the digits are valid, so the only way parsing can fail is overflow
+            Err(_) => return self.int_lit_too_large(start, lit_end),
+        };
+        let data = TokenData::Integer(value, suffix);
+        self.token_to_current(start, TokenKind::Literal(LitKind::Integer), data)
+    }
+
+    /// Reports that the integer literal from `start` to `lit_end` does not fit in an `i64`
+    fn int_lit_too_large(&self, start: usize, lit_end: usize) -> Token {
+        let lit_span = self.scanner.span(start, lit_end);
+        let token = self.token_to_current(start, TokenKind::Error, None);
+        self.diag.span_error(lit_span, format!("integer literal is too large (the maximum is `{}`)", i64::MAX)).emit();
+        token
+    }
+
+    /// Produces an error token for a numeric literal with a problem that has already been reported
+    ///
+    /// Any suffix after the literal is skipped without being checked.
+    fn invalid_num_lit(&mut self, start: usize) -> Token {
+        while let Some(ch) = self.scanner.peek() {
+            if ch.is_ascii_alphanumeric() || ch == b'_' {
+                self.scanner.next();
+            } else {
+                break;
+            }
+        }
+
+        self.token_to_current(start, TokenKind::Error, None)
+    }
+
+    /// Advances the scanner until no more digits or digit separators (`_`) are found. Returns the
+    /// number of digits found, or None if a separator was not placed between two digits.
+    ///
+    /// `after_digit` should be true if the character right before the current position is a digit
+    /// of the same number. Only the first misplaced separator is reported. The final, non-digit
+    /// character is NOT consumed.
+    fn digits(&mut self, after_digit: bool, is_digit: impl Fn(u8) -> bool) -> Option<usize> {
         let mut digits = 0;
+        let mut prev_is_digit = after_digit;
+        let mut valid_separators = true;
         while let Some(ch) = self.scanner.peek() {
-            if ch.is_ascii_digit() {
+            if is_digit(ch) {
                 digits += 1;
-                self.scanner.next();
+                prev_is_digit = true;
+
+            } else if ch == b'_' {
+                let next_is_digit = self.scanner.peek2().map(&is_digit).unwrap_or(false);
+                if valid_separators && !(prev_is_digit && next_is_digit) {
+                    let span = self.scanner.byte_span(self.scanner.current_pos());
+                    self.diag.span_error(span, "digit separators (`_`) are only allowed between two digits").emit();
+                    valid_separators = false;
+                }
+                prev_is_digit = false;
+
             } else {
                 break;
             }
+
+            self.scanner.next();
+        }
+
+        if valid_separators {
+            Some(digits)
+        } else {
+            None
         }
-        digits
     }
 
     /// Parses an identifier, assuming that the first character has already been parsed
@@ -514,6 +726,75 @@ mod tests {
         expect_error!(b"0foo");
     }
 
+    #[test]
+    fn integer_literals_too_large() {
+        expect_token!(b"9223372036854775807", int!(i64::MAX));
+        expect_error!(b"9223372036854775808");
+        expect_error!(b"99999999999999999999int");
+    }
+
+    #[test]
+    fn radix_integer_literals() {
+        expect_token!(b"0xFF", int!(0xFF));
+        expect_token!(b"0xff", int!(0xFF));
+        expect_token!(b"0xdeadBEEF", int!(0xdeadbeef));
+        expect_token!(b"0o755", int!(0o755));
+        expect_token!(b"0b1010", int!(0b1010));
+        expect_token!(b"0b0", int!(0));
+        expect_token!(b"0x7fffffffffffffff", int!(i64::MAX));
+
+        expect_token!(b"0xFFint", int!(0xFF, Suffix::Int));
+        expect_token!(b"0o17int", int!(0o17, Suffix::Int));
+        expect_token!(b"0b11int", int!(0b11, Suffix::Int));
+
+        expect_tokens!(b"0xF.foo", &[int!(0xF), t!(Period), ident!("foo")]);
+    }
+
+    #[test]
+    fn digit_separators() {
+        expect_token!(b"1_000_000", int!(1_000_000));
+        expect_token!(b"1_2_3", int!(123));
+        expect_token!(b"1_000int", int!(1000, Suffix::Int));
+        expect_token!(b"0_1", int!(1));
+        expect_token!(b"1_000.000_1", real!(1_000.000_1));
+        expect_token!(b"1e1_0", real!(1e10));
+        expect_token!(b"1_0j", complex!(10.0));
+        expect_token!(b"0xDE_AD_BE_EF", int!(0xDE_AD_BE_EF));
+        expect_token!(b"0b1010_1010", int!(0b1010_1010));
+        expect_token!(b"0o7_5_5int", int!(0o755, Suffix::Int));
+    }
+
+    #[test]
+    fn digit_separators_invalid() {
+        // A leading underscore makes this an identifier, not a number
+        expect_token!(b"_1", ident!("_1"));
+        expect_error!(b"1_");
+        expect_error!(b"1__2");
+        expect_error!(b"1_int");
+        expect_error!(b"1_.5");
+        // A period followed by an underscore is a field access
+        expect_tokens!(b"1._5", &[int!(1), t!(Period), ident!("_5")]);
+        expect_error!(b"1.5_");
+        expect_error!(b"1e_5");
+        expect_error!(b"0x_FF");
+        expect_error!(b"0xFF_");
+        expect_error!(b"0b1__0");
+    }
+
+    #[test]
+    fn radix_integer_literals_invalid() {
+        expect_error!(b"0x");
+        expect_error!(b"0bint");
+        expect_error!(b"0b123");
+        expect_error!(b"0o8");
+        // A real suffix makes no sense on these literals
+        expect_error!(b"0xFFreal");
+        expect_error!(b"0b1real");
+        expect_error!(b"0o7j");
+        expect_error!(b"0x8000000000000000");
+        expect_error!(b"0b11111111111111111111111111111111111111111111111111111111111111111");
+    }
+
     #[test]
     fn real_literals() {
         expect_token!(b"0.0", real!(0.0));
@@ -526,6 +807,13 @@ mod tests {
         expect_token!(b"99.9e+9", real!(99.9e+9));
         expect_token!(b"99.9E-10", real!(99.9e-10));
         expect_token!(b"99.9E+9", real!(99.9e+9));
+        expect_token!(b"6.022e23", real!(6.022e23));
+        expect_token!(b"1.6e-19", real!(1.6e-19));
+        expect_token!(b"1e3", real!(1000.0));
+
+        // The real suffix is allowed, but redundant
+        expect_token!(b"1e3real", real!(1000.0));
+        expect_token!(b"0.0real", real!(0.0));
     }
 
     #[test]
@@ -536,9 +824,37 @@ mod tests {
         expect_error!(b"0.0e+");
         expect_error!(b"0.0e-");
         expect_tokens!(b".0foo", &[t!(Period), t!(Error)]);
-        // Real number literals may not have a suffix
+        // Real number literals may not have an int suffix
         expect_error!(b"0.0int");
-        expect_error!(b"0.0real");
+        expect_error!(b"1e3int");
+        expect_error!(b"1.5e");
+        expect_error!(b"1.5e+");
+        expect_error!(b"1.5E-");
+    }
+
+    #[test]
+    fn real_literals_exponent() {
+        expect_tokens!(b"1e3 + 2.5E-1 * 4e+0real", &[
+            real!(1000.0),
+            t!(Plus),
+            real!(0.25),
+            t!(Star),
+            real!(4.0),
+        ]);
+
+        // A missing exponent produces a single error token for the whole literal
+        let source_files = Arc::new(RwLock::new(SourceFiles::default()));
+        let root_file = source_files.write().add_source("test.rs", b"1.5e+ 2");
+        let diag = Diagnostics::new(source_files.clone(), termcolor::ColorChoice::Auto);
+        let files = source_files.read();
+        let scanner = Scanner::new(files.source(root_file));
+        let mut lexer = Lexer::new(scanner, &diag);
+        let token = lexer.next();
+        assert_eq!(token.kind, Error);
+        assert_eq!(token.span, Span {start: 0, end: 5});
+        assert_eq!(diag.emitted_errors(), 1);
+        let token = lexer.next();
+        assert_eq!(token.data, int!(2).data);
     }
 
     #[test]
@@ -569,6 +885,37 @@ mod tests {
         expect_tokens!(b".0jfoo", &[t!(Period), t!(Error)]);
     }
 
+    macro_rules! bstr {
+        ($value:expr) => (
+            t!(Literal(LitKind::BStr), TokenData::BStr {unescaped_text: (&$value[..]).into()})
+        );
+    }
+
+    #[test]
+    fn bstr_literals() {
+        expect_token!(b"b\"\"", bstr!(b""));
+        expect_token!(b"b\"abc\"", bstr!(b"abc"));
+        expect_token!(b"b\"line1\\nline2\\t\\\"quoted\\\"\"", bstr!(b"line1\nline2\t\"quoted\""));
+        expect_token!(b"b\"\\n\\n\"", bstr!(b"\n\n"));
+        expect_token!(b"b\"\\r\\\\\\0\\'\"", bstr!(b"\r\\\0'"));
+        expect_token!(b"b\"\\x00\\x7f\\xFF\\xaB\"", bstr!(b"\x00\x7f\xff\xab"));
+        // Comment delimiters inside a literal are part of the literal
+        expect_token!(b"b\"// /* */\"", bstr!(b"// /* */"));
+    }
+
+    #[test]
+    fn bstr_literals_invalid() {
+        expect_error!(b"b\"abc");
+        expect_error!(b"b\"abc\\");
+        expect_error!(b"b\"abc\\\"");
+        expect_error!(b"b\"\\q\"");
+        expect_error!(b"b\"\\x\"");
+        expect_error!(b"b\"\\x4\"");
+        expect_error!(b"b\"\\xG0\"");
+        // The rest of the literal is consumed after an invalid escape
+        expect_tokens!(b"b\"\\q abc\" 1", &[t!(Error), int!(1)]);
+    }
+
     #[test]
     fn integer_field_access() {
         expect_tokens!(b"123.foo", &[int!(123), t!(Period), ident!("foo")]);
@@ -579,6 +926,23 @@ mod tests {
         expect_tokens!(b"123\0456", &[int!(123), t!(Error), int!(456)]);
     }
 
+    #[test]
+    fn compound_assignment_operators() {
+        expect_tokens!(b"x += 1", &[ident!("x"), t!(PlusEquals), int!(1)]);
+        expect_tokens!(b"-= *= /= %=", &[
+            t!(MinusEquals),
+            t!(StarEquals),
+            t!(SlashEquals),
+            t!(PercentEquals),
+        ]);
+        // The operator is only recognized if there is no space before the `=`
+        expect_tokens!(b"+ =", &[t!(Plus), t!(Equals)]);
+        expect_tokens!(b"x-=-1", &[ident!("x"), t!(MinusEquals), t!(Minus), int!(1)]);
+        expect_tokens!(b"+==", &[t!(PlusEquals), t!(Equals)]);
+        // Comments still take priority over `/=`
+        expect_tokens!(b"x //= 2", &[ident!("x")]);
+    }
+
     #[test]
     fn nested_block_comment() {
         expect_tokens!(b"/**/", &[]);
@@ -589,5 +953,63 @@ mod tests {
                 */ cool
     *//* okokok */
         ", &[]);
+        expect_tokens!(b"/* a */", &[]);
+        expect_tokens!(b"/* a /* b */ c */", &[]);
+        expect_tokens!(b"1 /* a /* b */ c */ 2", &[int!(1), int!(2)]);
+    }
+
+    #[test]
+    fn line_comment() {
+        expect_tokens!(b"// foo", &[]);
+        expect_tokens!(b"1 // foo /* bar\n2", &[int!(1), int!(2)]);
+        expect_tokens!(b"/// doc comments are ignored too\nfoo", &[ident!("foo")]);
+    }
+
+    #[test]
+    fn unterminated_block_comment() {
+        let source_files = Arc::new(RwLock::new(SourceFiles::default()));
+        let root_file = source_files.write().add_source("test.rs", b"foo /* a /* b */");
+        let diag = Diagnostics::new(source_files.clone(), termcolor::ColorChoice::Auto);
+        let files = source_files.read();
+        let scanner = Scanner::new(files.source(root_file));
+        let mut lexer = Lexer::new(scanner, &diag);
+
+        assert_eq!(lexer.next().kind, Ident);
+        assert_eq!(lexer.next().kind, Eof);
+        assert_eq!(diag.emitted_errors(), 1);
+    }
+
+    #[test]
+    fn comments_preserve_spans() {
+        let source: &[u8] = b"fn main() {\n    let x = 1; // one\n    /* two /* nested */ */ print_int(x);\n}\n";
+        let without_comments: &[u8] = b"fn main() {\n    let x = 1;\n    print_int(x);\n}\n";
+
+        let source_files = Arc::new(RwLock::new(SourceFiles::default()));
+        let file = source_files.write().add_source("test.rs", source);
+        let plain_file = source_files.write().add_source("plain.rs", without_comments);
+        let diag = Diagnostics::new(source_files.clone(), termcolor::ColorChoice::Auto);
+        let files = source_files.read();
+
+        let file_source = files.source(file);
+        let mut lexer = Lexer::new(Scanner::new(file_source), &diag);
+        let plain_source = files.source(plain_file);
+        let mut plain_lexer = Lexer::new(Scanner::new(plain_source), &diag);
+
+        loop {
+            let token = lexer.next();
+            let plain_token = plain_lexer.next();
+            assert_eq!(token.kind, plain_token.kind);
+            assert_eq!(token.data, plain_token.data);
+            // Each span must still point at the text of its token
+            assert_eq!(
+                file_source.slice(token.span.start..token.span.end),
+                plain_source.slice(plain_token.span.start..plain_token.span.end),
+            );
+
+            if token.kind == Eof {
+                break;
+            }
+        }
+        assert_eq!(diag.emitted_errors(), 0);
     }
 }
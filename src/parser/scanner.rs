@@ -13,7 +13,8 @@ impl<'a> Scanner<'a> {
     pub fn new(source: FileSource<'a>) -> Self {
         Self {
             source,
-            current: 0,
+            // Positions are global across all source files, so a file may not start at zero
+            current: source.start_index(),
         }
     }
 
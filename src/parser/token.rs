@@ -116,6 +116,17 @@ pub enum TokenKind {
     /// The `%` symbol
     Percent,
 
+    /// The `+=` symbol
+    PlusEquals,
+    /// The `-=` symbol
+    MinusEquals,
+    /// The `*=` symbol
+    StarEquals,
+    /// The `/=` symbol
+    SlashEquals,
+    /// The `%=` symbol
+    PercentEquals,
+
     /// A placeholder for a def which could not be computed; this is
     /// propagated to avoid useless error messages.
     Error,
@@ -176,6 +187,12 @@ impl fmt::Display for TokenKind {
             Slash => write!(f, "`/`"),
             Percent => write!(f, "`%`"),
 
+            PlusEquals => write!(f, "`+=`"),
+            MinusEquals => write!(f, "`-=`"),
+            StarEquals => write!(f, "`*=`"),
+            SlashEquals => write!(f, "`/=`"),
+            PercentEquals => write!(f, "`%=`"),
+
             Error => panic!("The Error token kind should not be formatted"),
 
             Eof => write!(f, "end of file"),
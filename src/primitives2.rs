@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use crate::ir::FieldTys;
 use crate::resolve2::{TyId, DeclMap, TypeInfo, LiteralConstructors};
 
 macro_rules! primitives {
@@ -32,6 +33,17 @@ macro_rules! primitives {
                     self.$prim
                 }
             )*
+
+            /// Returns the name of the given type if it is one of the primitives
+            pub fn name_of(&self, ty: TyId) -> Option<&'static str> {
+                $(
+                    if ty == self.$prim {
+                        return Some($name);
+                    }
+                )*
+
+                None
+            }
         }
     };
 }
@@ -49,8 +61,23 @@ primitives! {
                 unit_literal_constructor: Some("__dino__DUnit_from_unit_literal"),
                 ..LiteralConstructors::default()
             },
-            fields: HashMap::default(),
+            fields: FieldTys::default(),
+            methods: HashMap::default(),
+            field_defaults: HashMap::default(),
+            consts: HashMap::default(),
+        },
+
+        // The never type is the return type of functions that never return. No value of this type
+        // can ever be created, so it has no literal constructors. It is represented the same way
+        // as unit in the generated code.
+        never => "!" => TypeInfo {
+            name: "DUnit",
+            is_extern: true,
+            constructors: LiteralConstructors::default(),
+            fields: FieldTys::default(),
             methods: HashMap::default(),
+            field_defaults: HashMap::default(),
+            consts: HashMap::default(),
         },
 
         bool => "bool" => TypeInfo {
@@ -61,8 +88,10 @@ primitives! {
                 coerce_bool: Some("__dino__DBool_coerce_bool"),
                 ..LiteralConstructors::default()
             },
-            fields: HashMap::default(),
+            fields: FieldTys::default(),
             methods: HashMap::default(),
+            field_defaults: HashMap::default(),
+            consts: HashMap::default(),
         },
 
         int => "int" => TypeInfo {
@@ -72,8 +101,10 @@ primitives! {
                 int_literal_constructor: Some("__dino__DInt_from_int_literal"),
                 ..LiteralConstructors::default()
             },
-            fields: HashMap::default(),
+            fields: FieldTys::default(),
             methods: HashMap::default(),
+            field_defaults: HashMap::default(),
+            consts: HashMap::default(),
         },
 
         real => "real" => TypeInfo {
@@ -84,8 +115,10 @@ primitives! {
                 real_literal_constructor: Some("__dino__DReal_from_real_literal"),
                 ..LiteralConstructors::default()
             },
-            fields: HashMap::default(),
+            fields: FieldTys::default(),
             methods: HashMap::default(),
+            field_defaults: HashMap::default(),
+            consts: HashMap::default(),
         },
 
         complex => "complex" => TypeInfo {
@@ -97,8 +130,10 @@ primitives! {
                 complex_literal_constructor: Some("__dino__DComplex_from_complex_literal"),
                 ..LiteralConstructors::default()
             },
-            fields: HashMap::default(),
+            fields: FieldTys::default(),
             methods: HashMap::default(),
+            field_defaults: HashMap::default(),
+            consts: HashMap::default(),
         },
 
         bstr => "bstr" => TypeInfo {
@@ -108,8 +143,34 @@ primitives! {
                 bstr_literal_constructor: Some("__dino__DBStr_from_bstr_literal"),
                 ..LiteralConstructors::default()
             },
-            fields: HashMap::default(),
+            fields: FieldTys::default(),
+            methods: HashMap::default(),
+            field_defaults: HashMap::default(),
+            consts: HashMap::default(),
+        },
+
+        // An optional integer. This will be replaced by a generic option type once the language
+        // supports generics.
+        option_int => "option_int" => TypeInfo {
+            name: "DOptionInt",
+            is_extern: true,
+            constructors: LiteralConstructors::default(),
+            fields: FieldTys::default(),
+            methods: HashMap::default(),
+            field_defaults: HashMap::default(),
+            consts: HashMap::default(),
+        },
+
+        // A list of integers. This will be replaced by a generic list type once the language
+        // supports generics.
+        int_list => "int_list" => TypeInfo {
+            name: "DIntList",
+            is_extern: true,
+            constructors: LiteralConstructors::default(),
+            fields: FieldTys::default(),
             methods: HashMap::default(),
+            field_defaults: HashMap::default(),
+            consts: HashMap::default(),
         },
     }
 }
@@ -136,13 +136,16 @@ impl<'a> ModuleWalker<'a> {
         for decl in decls {
             match decl {
                 hir::Decl::Import(import) => {
-                    let hir::ImportPath {prefix, path, selection} = import;
+                    let hir::ImportPath {prefix, path, selection, is_pub: _} = import;
 
                     //TODO: Lookup the module at `path`
                     //TODO: Allow imports from `Self`
+                    //TODO: If `is_pub`, also add the imported items to the exports of this module
+                    //  so that they are visible to any module importing this one
                     use hir::ImportSelection::*;
                     match selection {
-                        //TODO: Lookup each name in the `path` module
+                        //TODO: Lookup each name in the `path` module and bind the item to its
+                        //  alias (if any) instead of its name in this scope
                         Names(names) => todo!(),
                         //TODO: Add `path` module DefId to `wildcard_imports`
                         All(span) => todo!(),
@@ -222,7 +225,12 @@ impl<'a> ModuleWalker<'a> {
             match struct_fields.insert(name_ident.value.clone(), nir::def_data::DefData::Field {ty}) {
                 Ok(_) => {},
                 Err(_) => {
-                    self.diag.span_error(name_ident.span, format!("field `{}` is already declared", name_ident)).emit();
+                    // The first field with this name is the one that was kept
+                    let first = fields.iter().find(|field| field.name.value == name_ident.value)
+                        .expect("bug: a duplicate field must have been declared before");
+                    self.diag.span_error(name_ident.span, format!("field `{}` is already declared", name_ident))
+                        .span_note(first.name.span, format!("`{}` first declared here", name_ident))
+                        .emit();
                 },
             };
         }
@@ -287,7 +295,14 @@ impl<'a> ModuleWalker<'a> {
                         let mut store = self.def_store.lock();
                         let ty_info = store.data_mut(self_ty_id).unwrap_type_mut();
                         if ty_info.methods.contains_key(&method.name.value) {
-                            self.diag.span_error(method.name.span, format!("duplicate definitions with name `{}`", method.name)).emit();
+                            let mut diag = self.diag.span_error(method.name.span, format!("duplicate definitions with name `{}`", method.name));
+                            // The previous definition may be in a different impl block, in which
+                            // case its span is not available here
+                            let first = methods.iter().find(|other| other.name.value == method.name.value);
+                            if let Some(first) = first.filter(|first| first.name.span != method.name.span) {
+                                diag = diag.span_note(first.name.span, format!("previous definition of `{}` here", method.name));
+                            }
+                            diag.emit();
                             continue;
 
                         } else {
@@ -363,7 +378,11 @@ impl<'a> ModuleWalker<'a> {
             let name_id = match self.top_scope().variables.insert(name.value.clone(), nir::def_data::DefData::Variable) {
                 Ok(id) => id,
                 Err(_) => {
-                    self.diag.span_error(name.span, format!("identifier `{}` is bound more than once in this parameter list", name)).emit();
+                    let first = params.iter().find(|param| param.name.value == name.value)
+                        .expect("bug: a duplicate parameter must have been declared before");
+                    self.diag.span_error(name.span, format!("identifier `{}` is bound more than once in this parameter list", name))
+                        .span_note(first.name.span, format!("`{}` first bound here", name))
+                        .emit();
 
                     // Generate a fresh name so there is at least the right number of params
                     duplicate_count += 1;
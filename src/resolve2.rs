@@ -3,11 +3,14 @@
 mod decl_map;
 mod func_info;
 mod type_info;
+mod module_scope;
 
 pub use decl_map::*;
 pub use func_info::*;
 pub use type_info::*;
+pub use module_scope::*;
 
+use std::path::{Path, PathBuf};
 use std::collections::{HashSet, HashMap};
 
 use snafu::{Snafu, OptionExt};
@@ -24,6 +27,36 @@ pub enum Error {
         /// The name that was declared multiple times
         duplicate: String,
     },
+    #[snafu(display("the function '{}' is defined multiple times: `{}` and `{}` (functions cannot be overloaded)", name, first_sig, second_sig))]
+    DuplicateFunc {
+        /// The name of the function that was defined multiple times
+        name: String,
+        /// The signature of the first definition, as it would be written in source code
+        first_sig: String,
+        /// The signature of the repeated definition, as it would be written in source code
+        second_sig: String,
+    },
+    #[snafu(display("method `{}` shadows the built-in method of the same name on primitive type `{}`", method_name, type_name))]
+    ShadowedPrimitiveMethod {
+        /// The name of the method that was already provided by the prelude
+        method_name: String,
+        /// The name of the primitive type, as it would be written in source code
+        type_name: String,
+    },
+    #[snafu(display("duplicate definitions with name `{}` for type `{}`", method_name, type_name))]
+    DuplicateMethod {
+        /// The name of the method that was defined more than once
+        method_name: String,
+        /// The name of the type, as it would be written in source code
+        type_name: String,
+    },
+    #[snafu(display("associated constant `{}` is defined multiple times for type `{}`", const_name, type_name))]
+    DuplicateAssocConst {
+        /// The name of the constant that was defined more than once
+        const_name: String,
+        /// The name of the type, as it would be written in source code
+        type_name: String,
+    },
     #[snafu(display("field `{}` is already declared for type `{}`", duplicate, type_name))]
     DuplicateField {
         /// The type that had a duplicate field
@@ -31,6 +64,25 @@ pub enum Error {
         /// The name of the repeated field
         duplicate: String,
     },
+    #[snafu(display("default value of field `{}` for type `{}` must be a constant", field, type_name))]
+    NonConstantFieldDefault {
+        /// The type with the field whose default is not a constant
+        type_name: String,
+        /// The name of the field
+        field: String,
+    },
+    #[snafu(display("value of associated constant `{}` for type `{}` must be a constant", const_name, type_name))]
+    NonConstantAssocConst {
+        /// The name of the associated constant whose value is not a constant
+        const_name: String,
+        /// The name of the type, as it would be written in source code
+        type_name: String,
+    },
+    #[snafu(display("initial value of static `{}` must be a constant", name))]
+    NonConstantStatic {
+        /// The name of the static whose initializer is not a constant
+        name: String,
+    },
     #[snafu(display("identifier `{}` is bound more than once in this parameter list", duplicate))]
     DuplicateFuncParam {
         /// The name of the repeated parameter name
@@ -40,17 +92,72 @@ pub enum Error {
     UnresolvedType {
         name: String,
     },
+    #[snafu(display("the name '{}' is defined in both '{}' and '{}'", name, first_module.display(),
+        second_module.display()))]
+    DuplicateImportedDecl {
+        /// The name declared in more than one module
+        name: String,
+        /// The file of the module that declared the name first
+        first_module: PathBuf,
+        /// The file of the module that declared the name again
+        second_module: PathBuf,
+    },
+    #[snafu(display("cannot find '{}' in module `{}`", name, module))]
+    UnresolvedImport {
+        /// The name of the item that was imported
+        name: String,
+        /// The imported module, as it was written in the import
+        module: String,
+    },
+    #[snafu(display("cyclic import: {}", cycle.join(" imports ")))]
+    CyclicImport {
+        /// The modules in the cycle, each imported by the one before it. The first module is
+        /// repeated at the end.
+        cycle: Vec<String>,
+    },
+}
+
+/// An error found in one of the modules of a program
+#[derive(Debug)]
+pub struct ModuleError {
+    /// The file of the module that the error was found in
+    pub path: PathBuf,
+    pub error: Error,
+}
+
+/// A module of a program, along with the file it was read from
+#[derive(Debug, Clone, Copy)]
+pub struct ProgramModule<'a> {
+    pub path: &'a Path,
+    pub module: &'a ast2::Module<'a>,
+    /// The names that the items of the module are declared with, or None for the root module
+    /// (whose items keep the names they are written with)
+    pub qualified_names: Option<&'a QualifiedNames>,
 }
 
 /// The declarations of a module with function signatures and type fields resolved
+///
+/// Top-level items are listed with the name they are declared with in the program (see
+/// `QualifiedNames`), which may be different from the name they are written with in the module.
 #[derive(Debug, Default)]
 pub struct ModuleDecls<'a> {
+    /// The items that can be referred to by name in the module
+    pub scope: ModuleScope<'a>,
     /// A mapping of type ID to its struct
     pub types: HashMap<TyId, ir::Struct<'a>>,
     /// A mapping of the Self type to its resolved methods
     pub methods: HashMap<TyId, Vec<(ir::FuncSig<'a>, &'a ast2::Function<'a>)>>,
-    /// A list of functions and their resolved signatures
-    pub functions: Vec<(ir::FuncSig<'a>, &'a ast2::Function<'a>)>,
+    /// A list of functions along with their names and resolved signatures
+    pub functions: Vec<(ir::Ident<'a>, ir::FuncSig<'a>, &'a ast2::Function<'a>)>,
+    /// A list of the functions declared in inline modules, along with the name of their module
+    /// and their resolved signatures
+    pub module_functions: Vec<(ir::Ident<'a>, ir::FuncSig<'a>, &'a ast2::Function<'a>)>,
+    /// A list of global variables along with their names and resolved types
+    pub statics: Vec<(ir::Ident<'a>, TyId, &'a ast2::Static<'a>)>,
+    /// A list of associated constants and their resolved types
+    pub assoc_consts: Vec<(TyId, &'a ast2::AssocConst<'a>)>,
+    /// A list of the default values of struct fields and the resolved types of those fields
+    pub field_defaults: Vec<(TyId, &'a ast2::Expr<'a>)>,
 }
 
 #[derive(Debug)]
@@ -60,38 +167,147 @@ pub struct ProgramDecls<'a> {
     pub prims: Primitives,
 }
 
+impl<'a> Default for ProgramDecls<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<'a> ProgramDecls<'a> {
-    /// Extracts the declarations from the given program
-    pub fn extract(prog: &'a ast2::Program<'a>) -> Result<(Self, ModuleDecls<'a>), Error> {
+    /// Creates the declarations for a program containing only the primitive types
+    ///
+    /// The prelude should be inserted before the declarations of the program are extracted so
+    /// that any conflicts with the prelude are reported as errors in the program.
+    pub fn new() -> Self {
         let mut top_level_decls = DeclMap::default();
         let prims = Primitives::new(&mut top_level_decls);
-        let mut program_decls = Self {top_level_decls, prims};
+        Self {top_level_decls, prims}
+    }
 
+    /// Extracts the declarations from the given program, which must not import any modules
+    pub fn extract(&mut self, prog: &'a ast2::Program<'a>) -> Result<ModuleDecls<'a>, Error> {
         let ast2::Program {top_level_module} = prog;
-        let ast2::Module {decls} = top_level_module;
 
-        let mut module_decls = ModuleDecls::default();
-        program_decls.reserve_types(&decls)?;
-        program_decls.resolve_fields(&decls, &mut module_decls)?;
-        program_decls.resolve_funcs_methods(&decls, &mut module_decls)?;
+        let module = ProgramModule {
+            path: Path::new(""),
+            module: top_level_module,
+            qualified_names: None,
+        };
+        let mut module_decls = self.extract_modules(&[module]).map_err(|err| err.error)?;
 
-        Ok((program_decls, module_decls))
+        Ok(module_decls.remove(0))
+    }
+
+    /// Extracts the declarations from each of the given modules, starting with the root module
+    ///
+    /// Every module imported by one of the modules must also be given. Each module can only refer
+    /// to the prelude, its own items, and the items it imports from other modules.
+    pub fn extract_modules(
+        &mut self,
+        modules: &[ProgramModule<'a>],
+    ) -> Result<Vec<ModuleDecls<'a>>, ModuleError> {
+        let in_module = |module: &ProgramModule| {
+            let path = module.path.to_path_buf();
+            move |error| ModuleError {path, error}
+        };
+
+        // Every module must declare its own items before any imports are added so that a
+        // conflict within an imported module is reported in that module
+        let prelude = ModuleScope::prelude(&self.top_level_decls);
+        let mut all_decls = modules.iter().map(|module| {
+            let mut scope = prelude.clone();
+            scope.insert_declared(module.path, module.module, module.qualified_names)
+                .map_err(in_module(module))?;
+            Ok(ModuleDecls {scope, ..ModuleDecls::default()})
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        // The index of the module imported by each import of each module
+        let imported_modules: Vec<Vec<_>> = modules.iter().map(|module| {
+            imports(module.module).map(|import| {
+                let ast2::ImportPath {path, selection: _, is_pub: _} = import;
+                let index = modules.iter().position(|module| module.qualified_names
+                    .map(|names| names.is_imported_with(path))
+                    .unwrap_or(false));
+                let index = index
+                    .expect("bug: every imported module should be loaded before it is resolved");
+                (index, import)
+            }).collect()
+        }).collect();
+
+        // A module may import items that another module re-exports, so the imports of each module
+        // are only added once the modules it imports have all of their imports
+        for index in import_order(&imported_modules) {
+            for &(imported_index, import) in &imported_modules[index] {
+                let ast2::ImportPath {path, selection, is_pub} = import;
+                let module_name = format!("package::{}", path.join("::"));
+                // Cyclic imports are reported while loading modules, so a module never imports
+                // itself
+                let imported_scope = all_decls[imported_index].scope.clone();
+
+                all_decls[index].scope.insert_imported(&imported_scope, selection, *is_pub, &module_name)
+                    .map_err(in_module(&modules[index]))?;
+            }
+        }
+
+        // Each pass runs on every module before the next pass so that the items of any module are
+        // available to the modules that import it
+        for (module, module_decls) in modules.iter().zip(&all_decls) {
+            let ast2::Module {decls} = module.module;
+            self.reserve_types(decls, &module_decls.scope).map_err(in_module(module))?;
+        }
+        for (module, module_decls) in modules.iter().zip(&mut all_decls) {
+            let ast2::Module {decls} = module.module;
+            self.resolve_fields(decls, module_decls).map_err(in_module(module))?;
+        }
+        for (module, module_decls) in modules.iter().zip(&mut all_decls) {
+            let ast2::Module {decls} = module.module;
+            self.resolve_funcs_methods(decls, module_decls).map_err(in_module(module))?;
+        }
+
+        Ok(all_decls)
+    }
+
+    /// Returns the name of the given type as it would be written in source code (see
+    /// `DeclMap::source_type_name`)
+    pub fn source_type_name(&self, ty: TyId) -> &str {
+        self.top_level_decls.source_type_name(ty, &self.prims)
+    }
+
+    /// Returns the names and signatures of all methods registered on the given type
+    ///
+    /// The methods are returned in no particular order.
+    pub fn methods_of(&self, ty: TyId) -> impl Iterator<Item = (&str, &ir::FuncSig<'a>)> + '_ {
+        self.top_level_decls.methods(ty)
+    }
+
+    /// Returns the names and signatures of all top-level functions
+    ///
+    /// The functions are returned in no particular order.
+    pub fn functions(&self) -> impl Iterator<Item = (&str, &ir::FuncSig<'a>)> + '_ {
+        self.top_level_decls.functions()
     }
 
     /// Reserves type IDs for the declared types
-    fn reserve_types(&mut self, decls: &[ast2::Decl<'a>]) -> Result<(), Error> {
+    fn reserve_types(&mut self, decls: &[ast2::Decl<'a>], scope: &ModuleScope<'a>) -> Result<(), Error> {
         // Inserts all the types so they are available for everything resolved after
         for decl in decls {
             match decl {
                 ast2::Decl::Struct(struct_decl) => {
-                    let ast2::Struct {name, fields: _} = struct_decl;
+                    let ast2::Struct {name, fields: _, invariant: _} = struct_decl;
+
+                    self.top_level_decls.reserve_type(declared_name(scope, name))?;
+                },
 
-                    self.top_level_decls.reserve_type(name)?;
+                // Modules are inserted along with the types so that their names can't conflict
+                ast2::Decl::Module(ast2::ModuleDecl {name, functions: _}) => {
+                    self.top_level_decls.insert_module(declared_name(scope, name))?;
                 },
 
                 // Ignore in this pass
+                ast2::Decl::Import(_) |
                 ast2::Decl::Impl(_) |
-                ast2::Decl::Function(_) => {},
+                ast2::Decl::Function(_) |
+                ast2::Decl::Static(_) => {},
             }
         }
 
@@ -103,22 +319,24 @@ impl<'a> ProgramDecls<'a> {
     /// Assumes that all types (user-defined or otherwise) have been given a type ID at this point.
     fn resolve_fields(
         &mut self,
-        decls: &[ast2::Decl<'a>],
+        decls: &'a [ast2::Decl<'a>],
         module_decls: &mut ModuleDecls<'a>,
     ) -> Result<(), Error> {
         for decl in decls {
             match decl {
                 ast2::Decl::Struct(struct_decl) => {
-                    let ast2::Struct {name, fields: parsed_fields} = struct_decl;
+                    let ast2::Struct {name, fields: parsed_fields, invariant: _} = struct_decl;
 
-                    let self_ty = self.top_level_decls.type_id(name)
+                    let ty_name = declared_name(&module_decls.scope, name);
+                    let self_ty = self.top_level_decls.type_id(&ty_name)
                         .expect("bug: all types should have been inserted by now");
 
                     // Use a loop to explicitly check for duplicate fields
                     let mut fields = ir::FieldTys::new();
+                    let mut field_defaults = HashMap::new();
                     for field in parsed_fields {
-                        let ast2::StructField {name: field_name, ty} = field;
-                        let field_ty = self.resolve_ty(ty, Some(self_ty))?;
+                        let ast2::StructField {name: field_name, ty, default} = field;
+                        let field_ty = self.resolve_ty(ty, Some(self_ty), &module_decls.scope)?;
 
                         if fields.insert(field_name, field_ty).is_some() {
                             return Err(Error::DuplicateField {
@@ -126,17 +344,37 @@ impl<'a> ProgramDecls<'a> {
                                 duplicate: field_name.to_string(),
                             });
                         }
+
+                        if let Some(default) = default {
+                            // Only constants are supported for now so that evaluating a default
+                            // never has any side effects
+                            if !is_constant(default) {
+                                return Err(Error::NonConstantFieldDefault {
+                                    type_name: name.to_string(),
+                                    field: field_name.to_string(),
+                                });
+                            }
+
+                            field_defaults.insert(*field_name, default);
+                            module_decls.field_defaults.push((field_ty, default));
+                        }
                     }
 
-                    module_decls.types.insert(self_ty, ir::Struct::new(name, fields.clone()));
+                    module_decls.types.insert(self_ty, ir::Struct::new(ty_name, fields.clone()));
 
-                    let type_info = TypeInfo::new(name, fields);
-                    self.top_level_decls.insert_type(name, type_info)?;
+                    let type_info = TypeInfo {
+                        field_defaults,
+                        ..TypeInfo::new(ty_name, fields)
+                    };
+                    self.top_level_decls.insert_type(ty_name, type_info)?;
                 },
 
                 // Ignore in this pass
+                ast2::Decl::Import(_) |
+                ast2::Decl::Module(_) |
                 ast2::Decl::Impl(_) |
-                ast2::Decl::Function(_) => {},
+                ast2::Decl::Function(_) |
+                ast2::Decl::Static(_) => {},
             }
         }
 
@@ -154,35 +392,161 @@ impl<'a> ProgramDecls<'a> {
         // Insert everything else, now that the types are there
         for decl in decls {
             match decl {
-                // Already handled in another pass
-                ast2::Decl::Struct(_) => {},
+                // Imported items were added to the scope of the module before any pass ran
+                ast2::Decl::Import(_) => {},
+
+                ast2::Decl::Module(ast2::ModuleDecl {name, functions}) => {
+                    let name = declared_name(&module_decls.scope, name);
+                    for func in functions {
+                        let func_info = self.resolve_function(func, None, &module_decls.scope)?;
+                        module_decls.module_functions.push((name, func_info.sig.clone(), func));
+                        self.top_level_decls.insert_module_func(name, func_info)?;
+                    }
+                },
+
+                ast2::Decl::Struct(struct_decl) => self.resolve_invariant(struct_decl, module_decls)?,
 
                 ast2::Decl::Impl(impl_block) => self.resolve_impl_block(impl_block, module_decls)?,
 
                 ast2::Decl::Function(func) => {
-                    let func_info = self.resolve_function(func, None)?;
-                    module_decls.functions.push((func_info.sig.clone(), func));
-                    self.top_level_decls.insert_func(func_info)?;
+                    let func_info = FunctionInfo {
+                        name: declared_name(&module_decls.scope, func.name),
+                        ..self.resolve_function(func, None, &module_decls.scope)?
+                    };
+                    module_decls.functions.push((func_info.name, func_info.sig.clone(), func));
+                    self.insert_func(func.name, func_info)?;
                 },
+
+                ast2::Decl::Static(static_decl) => self.resolve_static(static_decl, module_decls)?,
             }
         }
 
         Ok(())
     }
 
+    /// Inserts a new top-level function, reporting both signatures if the name is already taken
+    ///
+    /// There is no overloading, so the signatures are only shown to make it clear that two
+    /// functions with the same name conflict even if their signatures are different. The
+    /// signatures are shown with `name`, the name that the function is written with.
+    fn insert_func(&mut self, name: ir::Ident<'a>, func_info: FunctionInfo<'a>) -> Result<(), Error> {
+        if let Some(first_sig) = self.top_level_decls.func_sig(&func_info.name) {
+            return Err(Error::DuplicateFunc {
+                name: name.to_string(),
+                first_sig: self.sig_to_string(name, first_sig),
+                second_sig: self.sig_to_string(name, &func_info.sig),
+            });
+        }
+
+        self.top_level_decls.insert_func(func_info)
+    }
+
+    /// Renders the given function signature as it would be written in source code
+    fn sig_to_string(&self, name: &str, sig: &ir::FuncSig) -> String {
+        let ir::FuncSig {return_type, params} = sig;
+
+        let params: Vec<_> = params.iter()
+            .map(|ir::FuncParam {name, ty}| format!("{}: {}", name, self.source_type_name(*ty)))
+            .collect();
+        let mut sig_str = format!("fn {}({})", name, params.join(", "));
+
+        // The unit return type is usually left out in source code
+        if *return_type != self.prims.unit() {
+            sig_str.push_str(" -> ");
+            sig_str.push_str(self.source_type_name(*return_type));
+        }
+
+        sig_str
+    }
+
+    /// Adds the invariant of the given struct (if any) as an associated function of that struct
+    fn resolve_invariant(
+        &mut self,
+        struct_decl: &'a ast2::Struct<'a>,
+        module_decls: &mut ModuleDecls<'a>,
+    ) -> Result<(), Error> {
+        let ast2::Struct {name, fields: _, invariant} = struct_decl;
+        let invariant = match invariant {
+            Some(invariant) => invariant,
+            None => return Ok(()),
+        };
+
+        let self_ty = self.top_level_decls.type_id(&declared_name(&module_decls.scope, name))
+            .expect("bug: all types should have been inserted by now");
+
+        let func_info = self.resolve_function(invariant, Some(self_ty), &module_decls.scope)?;
+        module_decls.methods.entry(self_ty).or_default().push((func_info.sig.clone(), invariant));
+        self.top_level_decls.insert_method(self_ty, invariant.name, func_info)?;
+
+        Ok(())
+    }
+
+    /// Resolves the type of the given global variable
+    fn resolve_static(
+        &mut self,
+        static_decl: &'a ast2::Static<'a>,
+        module_decls: &mut ModuleDecls<'a>,
+    ) -> Result<(), Error> {
+        let &ast2::Static {name, ref ty, ref init} = static_decl;
+
+        // The initializer is evaluated before any code runs, so only constants are supported
+        if !is_constant(init) {
+            return Err(Error::NonConstantStatic {name: name.to_string()});
+        }
+
+        let name = declared_name(&module_decls.scope, name);
+        let ty = self.resolve_ty(ty, None, &module_decls.scope)?;
+        module_decls.statics.push((name, ty, static_decl));
+        self.top_level_decls.insert_static(name, ty)
+    }
+
     fn resolve_impl_block(
         &mut self,
         impl_block: &'a ast2::Impl<'a>,
         module_decls: &mut ModuleDecls<'a>,
     ) -> Result<(), Error> {
-        let ast2::Impl {self_ty, methods} = impl_block;
-        let self_ty = self.resolve_ty(self_ty, None)?;
+        let ast2::Impl {self_ty, methods, consts} = impl_block;
+        let self_ty = self.resolve_ty(self_ty, None, &module_decls.scope)?;
+
+        for assoc_const in consts {
+            let &ast2::AssocConst {name, ref ty, ref value} = assoc_const;
+
+            // Constants are inlined wherever they are used, so their values must not have any
+            // side effects
+            if !is_constant(value) {
+                return Err(Error::NonConstantAssocConst {
+                    const_name: name.to_string(),
+                    type_name: self.source_type_name(self_ty).to_string(),
+                });
+            }
+            // Constants may also be spread across several impl blocks
+            if self.top_level_decls.assoc_const(self_ty, &name).is_some() {
+                return Err(Error::DuplicateAssocConst {
+                    const_name: name.to_string(),
+                    type_name: self.source_type_name(self_ty).to_string(),
+                });
+            }
+
+            let ty = self.resolve_ty(ty, Some(self_ty), &module_decls.scope)?;
+            module_decls.assoc_consts.push((ty, assoc_const));
+            self.top_level_decls.insert_assoc_const(self_ty, name, AssocConstInfo {ty, value})?;
+        }
 
         // Multiple impl blocks for the same type are allowed, so we have to be careful here
         // to not overwrite a previous impl block
         let method_decls = module_decls.methods.entry(self_ty).or_default();
         for func in methods {
-            let func_info = self.resolve_function(func, Some(self_ty))?;
+            self.check_shadowed_primitive_method(self_ty, func.name)?;
+            // Methods may be spread across several impl blocks, so this also catches a method
+            // that was defined in an earlier impl block
+            if self.top_level_decls.method(self_ty, &func.name).is_some() {
+                return Err(Error::DuplicateMethod {
+                    method_name: func.name.to_string(),
+                    type_name: self.source_type_name(self_ty).to_string(),
+                });
+            }
+
+            let func_info = self.resolve_function(func, Some(self_ty), &module_decls.scope)?;
             method_decls.push((func_info.sig.clone(), func));
             self.top_level_decls.insert_method(self_ty, func.name, func_info)?;
         }
@@ -190,20 +554,56 @@ impl<'a> ProgramDecls<'a> {
         Ok(())
     }
 
-    fn resolve_function(&self, func: &ast2::Function<'a>, self_ty: Option<TyId>) -> Result<FunctionInfo<'a>, Error> {
-        let &ast2::Function {name, ref sig, body: _, is_extern} = func;
+    /// Returns an error if the given method would shadow a method of a primitive type that is
+    /// provided by the prelude
+    ///
+    /// Methods on primitive types are resolved by name, so a user-defined method with the same
+    /// name as a built-in method would make it unclear which of the two gets called.
+    fn check_shadowed_primitive_method(
+        &self,
+        self_ty: TyId,
+        method_name: ir::Ident<'a>,
+    ) -> Result<(), Error> {
+        let type_name = match self.prims.name_of(self_ty) {
+            Some(_) => self.source_type_name(self_ty),
+            None => return Ok(()),
+        };
+
+        match self.top_level_decls.method(self_ty, &method_name) {
+            Some(method) if method.is_extern => Err(Error::ShadowedPrimitiveMethod {
+                method_name: method_name.to_string(),
+                type_name: type_name.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn resolve_function(
+        &self,
+        func: &ast2::Function<'a>,
+        self_ty: Option<TyId>,
+        scope: &ModuleScope<'a>,
+    ) -> Result<FunctionInfo<'a>, Error> {
+        let &ast2::Function {name, ref sig, body: _, is_extern, c_body: _, is_test: _, is_pure,
+            source_path: _} = func;
 
         Ok(FunctionInfo {
             name,
-            sig: self.resolve_sig(sig, self_ty)?,
+            sig: self.resolve_sig(sig, self_ty, scope)?,
             is_extern,
+            is_pure,
         })
     }
 
-    fn resolve_sig(&self, sig: &ast2::FuncSig<'a>, self_ty: Option<TyId>) -> Result<ir::FuncSig<'a>, Error> {
+    fn resolve_sig(
+        &self,
+        sig: &ast2::FuncSig<'a>,
+        self_ty: Option<TyId>,
+        scope: &ModuleScope<'a>,
+    ) -> Result<ir::FuncSig<'a>, Error> {
         let ast2::FuncSig {return_type, params} = sig;
 
-        let return_type = self.resolve_ty(return_type, self_ty)?;
+        let return_type = self.resolve_ty(return_type, self_ty, scope)?;
 
         // Ensure that parameter names are unique
         let mut param_names = HashSet::new();
@@ -214,14 +614,19 @@ impl<'a> ProgramDecls<'a> {
                 return Err(Error::DuplicateFuncParam {duplicate: name.to_string()});
             }
 
-            let ty = self.resolve_ty(ty, self_ty)?;
+            let ty = self.resolve_ty(ty, self_ty, scope)?;
             Ok(ir::FuncParam {name, ty})
         }).collect::<Result<Vec<_>, _>>()?;
 
         Ok(ir::FuncSig {return_type, params})
     }
 
-    fn resolve_ty(&self, ty: &ast2::Ty<'a>, self_ty: Option<TyId>) -> Result<TyId, Error> {
+    fn resolve_ty(
+        &self,
+        ty: &ast2::Ty<'a>,
+        self_ty: Option<TyId>,
+        scope: &ModuleScope<'a>,
+    ) -> Result<TyId, Error> {
         match ty {
             ast2::Ty::Unit => Ok(self.prims.unit()),
 
@@ -232,8 +637,79 @@ impl<'a> ProgramDecls<'a> {
                 }),
             },
 
-            &ast2::Ty::Named(ty_name) => self.top_level_decls.type_id(&ty_name)
+            &ast2::Ty::Named(ty_name) => scope.get(ty_name)
+                .and_then(|name| self.top_level_decls.type_id(&name))
                 .with_context(|| UnresolvedType {name: ty_name}),
+
+            ast2::Ty::Never => Ok(self.prims.never()),
         }
     }
 }
+
+/// Returns the imports of the given module
+fn imports<'a>(module: &'a ast2::Module<'a>) -> impl Iterator<Item = &'a ast2::ImportPath<'a>> {
+    let ast2::Module {decls} = module;
+
+    decls.iter().filter_map(|decl| match decl {
+        ast2::Decl::Import(import) => Some(import),
+        _ => None,
+    })
+}
+
+/// Returns the indexes of the modules ordered so that every module comes after the modules that it
+/// imports
+///
+/// Each module is given as the indexes of the modules that it imports (along with each import).
+/// Cyclic imports are reported while loading modules, so there are never any cycles.
+fn import_order<T>(imported_modules: &[Vec<(usize, T)>]) -> Vec<usize> {
+    fn visit<T>(index: usize, imported_modules: &[Vec<(usize, T)>], visited: &mut [bool], order: &mut Vec<usize>) {
+        if visited[index] {
+            return;
+        }
+        visited[index] = true;
+
+        for &(imported_index, _) in &imported_modules[index] {
+            visit(imported_index, imported_modules, visited, order);
+        }
+        order.push(index);
+    }
+
+    let mut visited = vec![false; imported_modules.len()];
+    let mut order = Vec::new();
+    for index in 0..imported_modules.len() {
+        visit(index, imported_modules, &mut visited, &mut order);
+    }
+
+    order
+}
+
+/// Returns the name that the given item, declared in the module with the given scope, is declared
+/// with in the program
+fn declared_name<'a>(scope: &ModuleScope<'a>, name: &str) -> ir::Ident<'a> {
+    scope.get(name).expect("bug: every item should be in the scope of the module declaring it")
+}
+
+/// Returns true if the given expression is a constant (i.e. a literal)
+fn is_constant(expr: &ast2::Expr) -> bool {
+    use ast2::Expr::*;
+    match expr {
+        BStrLiteral(_) |
+        IntegerLiteral(_) |
+        RealLiteral(_) |
+        ComplexLiteral(_) |
+        BoolLiteral(_) |
+        UnitLiteral => true,
+
+        VarAssign(_) |
+        MethodCall(_) |
+        FieldAccess(_) |
+        Cond(_) |
+        Call(_) |
+        Return(_) |
+        StructLiteral(_) |
+        OffsetOf(_) |
+        AssocConst(_) |
+        SelfLiteral |
+        Var(_) => false,
+    }
+}
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 
 use crate::ir;
+use crate::ast2;
+use crate::primitives2::Primitives;
 
-use super::{TypeInfo, FunctionInfo, LiteralConstructors, Error};
+use super::{TypeInfo, AssocConstInfo, FunctionInfo, LiteralConstructors, Error};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TyId(usize);
 
 /// The declarations in a module, indexed by name
@@ -16,6 +18,10 @@ pub struct DeclMap<'a> {
     /// Lookups may rely on this being Some(TypeInfo).
     types: Vec<Option<TypeInfo<'a>>>,
     type_ids: HashMap<ir::Ident<'a>, TyId>,
+    /// The type of each global variable
+    statics: HashMap<ir::Ident<'a>, TyId>,
+    /// The functions of each inline module, indexed by module name
+    modules: HashMap<ir::Ident<'a>, HashMap<ir::Ident<'a>, FunctionInfo<'a>>>,
 }
 
 impl<'a> DeclMap<'a> {
@@ -23,6 +29,13 @@ impl<'a> DeclMap<'a> {
     pub fn reserve_type(&mut self, ty_name: ir::Ident<'a>) -> Result<TyId, Error> {
         let id = TyId(self.types.len());
 
+        // Types and modules share the same namespace since both can be the first part of a path
+        if self.modules.contains_key(ty_name) {
+            return Err(Error::DuplicateDecl {
+                duplicate: ty_name.to_string(),
+            });
+        }
+
         // It's an error to overwrite type info that was already previously present
         // However, if the type was reserved (i.e. self.types[id.0] == None), this is fine.
         if self.type_ids.insert(ty_name, id).is_some() && self.types[id.0].is_some() {
@@ -81,6 +94,49 @@ impl<'a> DeclMap<'a> {
         Ok(())
     }
 
+    /// Inserts a new global variable with the given type
+    pub fn insert_static(&mut self, name: ir::Ident<'a>, ty: TyId) -> Result<(), Error> {
+        if self.statics.insert(name, ty).is_some() {
+            return Err(Error::DuplicateDecl {
+                duplicate: name.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Inserts a new inline module with no functions
+    pub fn insert_module(&mut self, name: ir::Ident<'a>) -> Result<(), Error> {
+        if self.type_ids.contains_key(name) || self.modules.insert(name, HashMap::new()).is_some() {
+            return Err(Error::DuplicateDecl {
+                duplicate: name.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Inserts a new function into the given inline module
+    ///
+    /// The module must have already been inserted.
+    pub fn insert_module_func(
+        &mut self,
+        module_name: ir::Ident<'a>,
+        func_info: FunctionInfo<'a>,
+    ) -> Result<(), Error> {
+        let functions = self.modules.get_mut(module_name)
+            .expect("bug: module should have been inserted before its functions");
+
+        let func_name = func_info.name;
+        if functions.insert(func_name, func_info).is_some() {
+            return Err(Error::DuplicateDecl {
+                duplicate: format!("{}::{}", module_name, func_name),
+            });
+        }
+
+        Ok(())
+    }
+
     /// Inserts a new method for the given type
     pub fn insert_method(
         &mut self,
@@ -99,6 +155,24 @@ impl<'a> DeclMap<'a> {
         Ok(())
     }
 
+    /// Inserts a new associated constant for the given type
+    pub fn insert_assoc_const(
+        &mut self,
+        id: TyId,
+        const_name: ir::Ident<'a>,
+        const_info: AssocConstInfo<'a>,
+    ) -> Result<(), Error> {
+        let consts = &mut self.type_info_mut(id).consts;
+
+        if consts.insert(const_name, const_info).is_some() {
+            return Err(Error::DuplicateDecl {
+                duplicate: const_name.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
     /// Returns the ID of the given type name
     pub fn type_id(&self, ty: &ir::Ident<'a>) -> Option<TyId> {
         self.type_ids.get(ty).copied()
@@ -109,6 +183,19 @@ impl<'a> DeclMap<'a> {
         &self.type_info(id).name
     }
 
+    /// Returns the name of the given type as it would be written in source code
+    ///
+    /// Unlike `type_name`, this returns the source name of primitive types (e.g. `int`) rather
+    /// than the name of the type used during code generation.
+    pub fn source_type_name(&self, id: TyId, prims: &Primitives) -> &str {
+        match prims.name_of(id) {
+            // The unit type has no name, so use its syntax instead
+            Some(_) if id == prims.unit() => "()",
+            Some(name) => name,
+            None => self.type_name(id),
+        }
+    }
+
     /// Returns true if this type is extern
     pub fn type_is_extern(&self, id: TyId) -> bool {
         self.type_info(id).is_extern
@@ -124,6 +211,27 @@ impl<'a> DeclMap<'a> {
         self.type_info(ty_id).fields.get(field_name).copied()
     }
 
+    /// Returns an iterator over the names and types of the fields of the given type, in the order
+    /// they were declared
+    pub fn fields(&self, id: TyId) -> impl Iterator<Item = (&ir::Ident<'a>, &TyId)> + '_ {
+        self.type_info(id).fields.iter()
+    }
+
+    /// Returns the default value of the given field, if any
+    pub fn field_default(&self, ty_id: TyId, field_name: &ir::Ident<'a>) -> Option<&'a ast2::Expr<'a>> {
+        self.type_info(ty_id).field_defaults.get(field_name).copied()
+    }
+
+    /// Returns the associated constant corresponding to the given type and name, if any
+    pub fn assoc_const(&self, id: TyId, const_name: &ir::Ident<'a>) -> Option<&AssocConstInfo<'a>> {
+        self.type_info(id).consts.get(const_name)
+    }
+
+    /// Returns the offset in bytes of the given field from the start of the type, if any
+    pub fn field_offset(&self, ty_id: TyId, field_name: &ir::Ident<'a>) -> Option<usize> {
+        self.type_info(ty_id).fields.offset_of(field_name)
+    }
+
     /// Returns the method function decl corresponding to the given name, if any
     pub fn method(&self, id: TyId, method_name: &ir::Ident<'a>) -> Option<&FunctionInfo<'a>> {
         self.type_info(id).methods.get(method_name)
@@ -134,9 +242,86 @@ impl<'a> DeclMap<'a> {
         self.method(id, method_name).map(|func| &func.sig)
     }
 
+    /// Returns an iterator over the names and signatures of all methods of the given type
+    pub fn methods(&self, id: TyId) -> impl Iterator<Item = (&str, &ir::FuncSig<'a>)> + '_ {
+        self.type_info(id).methods.iter().map(|(&name, func)| (name, &func.sig))
+    }
+
+    /// Returns the number of types declared, including any reserved types
+    pub fn type_count(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Returns an iterator over the names and signatures of all functions
+    pub fn functions(&self) -> impl Iterator<Item = (&str, &ir::FuncSig<'a>)> + '_ {
+        self.functions.iter().map(|(&name, func)| (name, &func.sig))
+    }
+
+    /// Returns the function decl corresponding to the given name, if any
+    pub fn func(&self, func_name: &ir::Ident<'a>) -> Option<&FunctionInfo<'a>> {
+        self.functions.get(func_name)
+    }
+
     /// Returns the function signature corresponding to the given name, if any
     pub fn func_sig(&self, func_name: &ir::Ident<'a>) -> Option<&ir::FuncSig<'a>> {
-        self.functions.get(func_name).map(|entry| &entry.sig)
+        self.func(func_name).map(|entry| &entry.sig)
+    }
+
+    /// Returns the extern method that is linked in with the given name (e.g. `int__add`), if any
+    pub fn extern_method(&self, extern_name: &str) -> Option<&FunctionInfo<'a>> {
+        self.types.iter().flatten()
+            .flat_map(|ty_info| ty_info.methods.values())
+            .find(|func| func.is_extern && func.name == extern_name)
+    }
+
+    /// Returns true if at least one type has a method with the given name and every method with
+    /// that name is marked as pure
+    ///
+    /// Useful when the type that a method is called on is not known yet.
+    pub fn method_is_always_pure(&self, method_name: &ir::Ident<'a>) -> bool {
+        let mut methods = self.types.iter().flatten()
+            .filter_map(|ty_info| ty_info.methods.get(method_name))
+            .peekable();
+        methods.peek().is_some() && methods.all(|func| func.is_pure)
+    }
+
+    /// Returns true if an inline module with the given name was declared
+    pub fn is_module(&self, name: &ir::Ident<'a>) -> bool {
+        self.modules.contains_key(name)
+    }
+
+    /// Returns an iterator over the names and signatures of all functions in the given module
+    ///
+    /// The iterator is empty if there is no module with the given name.
+    pub fn module_functions(&self, module_name: &ir::Ident<'a>) -> impl Iterator<Item = (&str, &ir::FuncSig<'a>)> + '_ {
+        self.modules.get(module_name).into_iter().flatten().map(|(&name, func)| (name, &func.sig))
+    }
+
+    /// Returns the function with the given name in the given module, if any
+    pub fn module_func(&self, module_name: &ir::Ident<'a>, func_name: &ir::Ident<'a>) -> Option<&FunctionInfo<'a>> {
+        self.modules.get(module_name)?.get(func_name)
+    }
+
+    /// Returns the signature of the function with the given name in the given module, if any
+    pub fn module_func_sig(&self, module_name: &ir::Ident<'a>, func_name: &ir::Ident<'a>) -> Option<&ir::FuncSig<'a>> {
+        self.module_func(module_name, func_name).map(|entry| &entry.sig)
+    }
+
+    /// Returns the type of the global variable with the given name, if any
+    pub fn static_ty(&self, name: &ir::Ident<'a>) -> Option<TyId> {
+        self.statics.get(name).copied()
+    }
+
+    /// Returns an iterator over the names of every function, type, global variable, and inline
+    /// module
+    ///
+    /// The names are returned in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = ir::Ident<'a>> + '_ {
+        self.functions.keys()
+            .chain(self.type_ids.keys())
+            .chain(self.statics.keys())
+            .chain(self.modules.keys())
+            .copied()
     }
 
     /// Gets the type info for the given ID
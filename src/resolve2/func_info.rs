@@ -8,6 +8,9 @@ pub struct FunctionInfo<'a> {
     pub sig: ir::FuncSig<'a>,
     /// True if the function is meant to be linked in externally
     pub is_extern: bool,
+    /// True if calling the function has no side effects (e.g. no I/O and no assignments to static
+    /// variables)
+    pub is_pure: bool,
 }
 
 impl<'a> FunctionInfo<'a> {
@@ -17,6 +20,15 @@ impl<'a> FunctionInfo<'a> {
             name,
             sig,
             is_extern: true,
+            is_pure: false,
+        }
+    }
+
+    /// Creates a new extern function that has no side effects
+    pub fn new_pure_extern(name: ir::Ident<'a>, sig: ir::FuncSig<'a>) -> Self {
+        Self {
+            is_pure: true,
+            ..Self::new_extern(name, sig)
         }
     }
 }
@@ -0,0 +1,203 @@
+use std::path::Path;
+use std::collections::HashMap;
+
+use crate::ir;
+use crate::ast2;
+
+use super::{DeclMap, Error};
+
+/// The names that the items of an imported module are declared with in the program
+///
+/// Each item is named by the path of its module followed by its own name (e.g.
+/// `package::shapes::square::area`). That way, two modules can declare items with the same name.
+/// The items of the root module keep the names they are written with, so they never need this.
+#[derive(Debug)]
+pub struct QualifiedNames {
+    /// The path that the module is imported with (e.g. `["shapes", "square"]`)
+    module_path: Vec<String>,
+    /// The qualified name of each item, indexed by the name it is written with
+    names: HashMap<String, String>,
+}
+
+impl QualifiedNames {
+    /// Creates the qualified names of the items declared in the module imported with the given
+    /// path
+    pub fn new(module_path: Vec<String>, module: &ast2::Module) -> Self {
+        // `package` is a keyword, so a qualified name can never be the same as any name written
+        // in the program (e.g. a function in an inline module)
+        let prefix = format!("package::{}", module_path.join("::"));
+        let names = declared_names(module)
+            .map(|name| (name.to_string(), format!("{}::{}", prefix, name)))
+            .collect();
+
+        Self {module_path, names}
+    }
+
+    /// Returns true if this module is the one imported with the given path
+    pub fn is_imported_with(&self, path: &[ir::Ident]) -> bool {
+        self.module_path.iter().map(String::as_str).eq(path.iter().copied())
+    }
+
+    /// Returns the qualified name of the given item
+    fn get(&self, name: &str) -> &str {
+        self.names.get(name).map(String::as_str)
+            .expect("bug: no qualified name was created for an item of the module")
+    }
+}
+
+/// The item that a name refers to within a module
+#[derive(Debug, Clone, Copy)]
+struct ScopeEntry<'a> {
+    /// The name that the item is declared with in the program
+    name: ir::Ident<'a>,
+    /// The file of the module that declared the item, or None if the item is from the prelude
+    module: Option<&'a Path>,
+    /// True if modules importing this module can import the item with this name. This is true
+    /// for every item declared in the module and for items imported with `pub use`.
+    is_exported: bool,
+}
+
+/// The items that can be referred to by name in a module
+///
+/// This includes the prelude, the items declared in the module, and the items imported from other
+/// modules. Each name is mapped to the name that the item is declared with in the program.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleScope<'a> {
+    names: HashMap<ir::Ident<'a>, ScopeEntry<'a>>,
+}
+
+impl<'a> ModuleScope<'a> {
+    /// Creates a scope containing every item that has been declared so far, each under its own
+    /// name
+    ///
+    /// This should be called before any module is extracted so that the scope only contains the
+    /// primitives and the prelude.
+    pub fn prelude(decls: &DeclMap<'a>) -> Self {
+        let names = decls.names()
+            .map(|name| (name, ScopeEntry {name, module: None, is_exported: false}))
+            .collect();
+
+        Self {names}
+    }
+
+    /// Returns the name that the item with the given name is declared with in the program, or
+    /// None if there is no item with that name in this scope
+    pub fn get(&self, name: &str) -> Option<ir::Ident<'a>> {
+        self.names.get(name).map(|entry| entry.name)
+    }
+
+    /// Returns an iterator over every name in this scope along with the name that the item is
+    /// declared with in the program
+    pub fn names(&self) -> impl Iterator<Item = (ir::Ident<'a>, ir::Ident<'a>)> + '_ {
+        self.names.iter().map(|(&name, entry)| (name, entry.name))
+    }
+
+    /// Adds the items declared in the given module, read from the file at `path`
+    ///
+    /// Items with qualified names are added under the name they are written with. An item that
+    /// is declared more than once in the same module is reported when the module is extracted.
+    /// The same goes for an item of the root module with the same name as an item of the prelude,
+    /// since both are declared with that name.
+    pub fn insert_declared(
+        &mut self,
+        path: &'a Path,
+        module: &'a ast2::Module<'a>,
+        qualified_names: Option<&'a QualifiedNames>,
+    ) -> Result<(), Error> {
+        for name in declared_names(module) {
+            let qualified_name = match qualified_names {
+                Some(names) => names.get(name),
+                None => name,
+            };
+            let entry = ScopeEntry {name: qualified_name, module: Some(path), is_exported: true};
+
+            match self.names.insert(name, entry) {
+                // Items of the prelude can't be shadowed
+                Some(prev) if prev.module.is_none() && qualified_names.is_some() => {
+                    return Err(Error::DuplicateDecl {duplicate: name.to_string()});
+                },
+                _ => {},
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the items selected by the given import from the module with the given scope
+    ///
+    /// Only items declared in the imported module or re-exported by it can be imported. If
+    /// `is_pub` is true, the imported items are re-exported from this module.
+    pub fn insert_imported(
+        &mut self,
+        imported: &ModuleScope<'a>,
+        selection: &ast2::ImportSelection<'a>,
+        is_pub: bool,
+        module_name: &str,
+    ) -> Result<(), Error> {
+        match selection {
+            ast2::ImportSelection::All => {
+                for (&name, entry) in &imported.names {
+                    if entry.is_exported {
+                        self.insert_imported_name(name, *entry, is_pub)?;
+                    }
+                }
+            },
+
+            ast2::ImportSelection::Names(names) => for import_name in names {
+                let entry = imported.names.get(import_name.name)
+                    .filter(|entry| entry.is_exported)
+                    .ok_or_else(|| Error::UnresolvedImport {
+                        name: import_name.name.to_string(),
+                        module: module_name.to_string(),
+                    })?;
+
+                self.insert_imported_name(import_name.local_name(), *entry, is_pub)?;
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Adds an imported item under the given name, reporting an error if a different item already
+    /// has that name in this scope
+    fn insert_imported_name(
+        &mut self,
+        name: ir::Ident<'a>,
+        imported: ScopeEntry<'a>,
+        is_pub: bool,
+    ) -> Result<(), Error> {
+        let entry = ScopeEntry {is_exported: is_pub, ..imported};
+        match self.names.insert(name, entry) {
+            // Importing the same item more than once is allowed, and it is exported if any of the
+            // imports re-export it
+            Some(prev) if prev.name == entry.name => {
+                self.names.insert(name, ScopeEntry {is_exported: prev.is_exported || is_pub, ..entry});
+                Ok(())
+            },
+
+            Some(prev) => Err(Error::DuplicateImportedDecl {
+                name: name.to_string(),
+                first_module: prev.module.unwrap_or_else(|| Path::new("<prelude>")).to_path_buf(),
+                second_module: entry.module.unwrap_or_else(|| Path::new("<prelude>")).to_path_buf(),
+            }),
+
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns the names of the top-level items declared in the given module
+fn declared_names<'a>(module: &'a ast2::Module<'a>) -> impl Iterator<Item = ir::Ident<'a>> + 'a {
+    let ast2::Module {decls} = module;
+
+    decls.iter().filter_map(|decl| match decl {
+        ast2::Decl::Module(ast2::ModuleDecl {name, ..}) |
+        ast2::Decl::Struct(ast2::Struct {name, ..}) |
+        ast2::Decl::Function(ast2::Function {name, ..}) |
+        ast2::Decl::Static(ast2::Static {name, ..}) => Some(*name),
+
+        // Methods are namespaced by their type, so they are not in the scope of the module
+        ast2::Decl::Import(_) |
+        ast2::Decl::Impl(_) => None,
+    })
+}
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 
 use crate::ir;
+use crate::ast2;
 
-use super::FunctionInfo;
+use super::{FunctionInfo, TyId};
 
 /// Represents the method types of a type
 ///
@@ -30,6 +31,26 @@ pub struct TypeInfo<'a> {
 
     /// The methods provided by this type.
     pub methods: MethodTys<'a>,
+
+    /// The default values of the fields that have one
+    ///
+    /// Each default is a constant expression that is type checked wherever it is used in place of
+    /// a field value missing from a struct literal.
+    pub field_defaults: HashMap<ir::Ident<'a>, &'a ast2::Expr<'a>>,
+
+    /// The associated constants declared in impl blocks for this type
+    pub consts: HashMap<ir::Ident<'a>, AssocConstInfo<'a>>,
+}
+
+/// Information about an associated constant (e.g. `int::MAX`)
+#[derive(Debug, Clone, Copy)]
+pub struct AssocConstInfo<'a> {
+    /// The declared type of the constant
+    pub ty: TyId,
+    /// The value of the constant
+    ///
+    /// The value is a constant expression that is inlined wherever the constant is used.
+    pub value: &'a ast2::Expr<'a>,
 }
 
 impl<'a> TypeInfo<'a> {
@@ -41,6 +62,8 @@ impl<'a> TypeInfo<'a> {
             constructors: LiteralConstructors::default(),
             fields,
             methods: MethodTys::default(),
+            field_defaults: HashMap::default(),
+            consts: HashMap::default(),
         }
     }
 }
@@ -2,17 +2,24 @@
 
 mod mangler;
 mod function;
-
-use std::iter;
+mod bstr_literals;
+mod merge_functions;
+mod bounds_checks;
+mod unreachable_functions;
 
 use snafu::Snafu;
 
-use crate::ir;
-use crate::resolve2::{ProgramDecls, DeclMap};
+use crate::ir::{self, IdentPath};
+use crate::runtime::ALLOCATE;
+use crate::resolve2::{ProgramDecls, DeclMap, TyId};
 use crate::primitives2::Primitives;
 use crate::codegen::*;
 
 use function::FunctionCodeGenerator;
+use mangler::mangle_path;
+use bstr_literals::BStrLiterals;
+use merge_functions::merge_identical_functions;
+use unreachable_functions::remove_unreachable_functions;
 
 /// Code generation errors
 #[derive(Debug, Snafu)]
@@ -21,79 +28,512 @@ pub enum Error {
     NoEntryPoint,
     #[snafu(display("`main` function has wrong type"))]
     InvalidEntryPointType,
+    #[snafu(display("test function `{}` must take no parameters and return ()", name))]
+    InvalidTestType {
+        name: String,
+    },
+    #[snafu(display("`debug_print` cannot print values of type `{}`", ty_name))]
+    UnsupportedDebugPrint {
+        ty_name: String,
+    },
+}
+
+/// Options that control the code that is generated
+#[derive(Debug, Default, Clone)]
+pub struct CodegenOptions {
+    /// If true, a `#line` directive is generated before the code for each statement so that the
+    /// diagnostics of the C compiler and debuggers refer to the original program
+    ///
+    /// Off by default since the directives make the generated code harder to read.
+    pub line_directives: bool,
+    /// If true, the bounds check of `int_list_get` is skipped in loops that obviously visit each
+    /// index of the list in order, e.g. `while i < int_list_len(list) { ...; i = i + 1; }`
+    ///
+    /// Off by default since only a small set of syntactic patterns is verified to be safe.
+    pub elide_bounds_checks: bool,
 }
 
 /// Generates an executable program from the given IR
-pub fn executable(prog: &ir::Program, program_scope: &ProgramDecls) -> Result<CExecutableProgram, Error> {
-    let ir::Program {top_level_module} = prog;
-    let ir::Module {types, functions} = top_level_module;
+pub fn executable(
+    prog: &ir::Program,
+    program_scope: &ProgramDecls,
+    options: &CodegenOptions,
+) -> Result<CExecutableProgram, Error> {
+    // Functions that can never be called once the program starts are not generated at all
+    let prog = remove_unreachable_functions(prog, std::iter::once(IdentPath::from("main")));
+    let ir::Program {top_level_module} = &prog;
+    let ir::Module {types, functions, module_functions, statics} = top_level_module;
 
     let ProgramDecls {top_level_decls: mod_scope, prims} = program_scope;
 
     let mut structs = Vec::new();
-    let methods = gen_types(types, mod_scope, prims, &mut structs)?;
+    let mut bstr_literals = BStrLiterals::default();
+    let mut symbols = CSymbolMap::default();
+    let methods = gen_types(types, mod_scope, prims, &mut structs, &mut bstr_literals,
+        &mut symbols, options)?;
+    let (globals, static_inits) = gen_statics(statics, mod_scope, prims, &mut bstr_literals,
+        &mut symbols)?;
 
     let mut entry_point = None;
-    let mut functions = gen_functions(functions, mod_scope, prims, &mut entry_point)?;
+    let mut functions = gen_functions(functions, mod_scope, prims, &mut bstr_literals,
+        &mut entry_point, &mut symbols, options)?;
     functions.extend(methods);
+    functions.extend(gen_module_functions(module_functions, mod_scope, prims, &mut bstr_literals,
+        &mut symbols, options)?);
 
-    let entry_point = match entry_point {
+    let mut entry_point = match entry_point {
         Some(entry_point) => entry_point,
         None => return Err(Error::NoEntryPoint),
     };
+    symbols.insert(CSymbolKind::Function, "main".to_string(), "main".to_string());
+    init_statics(&mut entry_point, static_inits);
+    merge_identical_functions(&mut functions, &mut entry_point, &mut symbols);
+
+    let str_constants = bstr_literals.into_constants();
+    Ok(CExecutableProgram {structs, str_constants, globals, functions, entry_point, symbols})
+}
+
+/// Generates a test harness from the given IR
+///
+/// The harness runs every function marked with `#[test]` in the order it was declared and reports
+/// how many tests passed and failed. Any `main` function in the program is ignored.
+pub fn test_harness(
+    prog: &ir::Program,
+    program_scope: &ProgramDecls,
+    options: &CodegenOptions,
+) -> Result<CExecutableProgram, Error> {
+    // Only the functions that can be called by one of the tests are generated
+    let tests = prog.top_level_module.functions.iter()
+        .filter(|func| func.is_test)
+        .map(|func| IdentPath::from(func.name));
+    let prog = remove_unreachable_functions(prog, tests);
+    let ir::Program {top_level_module} = &prog;
+    let ir::Module {types, functions, module_functions, statics} = top_level_module;
+
+    let ProgramDecls {top_level_decls: mod_scope, prims} = program_scope;
+
+    let mut structs = Vec::new();
+    let mut bstr_literals = BStrLiterals::default();
+    let mut symbols = CSymbolMap::default();
+    let methods = gen_types(types, mod_scope, prims, &mut structs, &mut bstr_literals,
+        &mut symbols, options)?;
+    let (globals, static_inits) = gen_statics(statics, mod_scope, prims, &mut bstr_literals,
+        &mut symbols)?;
+
+    // The `main` function is not needed since the harness provides its own entry point
+    let mut user_entry_point = None;
+    let mut cfunctions = gen_functions(functions, mod_scope, prims, &mut bstr_literals,
+        &mut user_entry_point, &mut symbols, options)?;
+    cfunctions.extend(methods);
+    cfunctions.extend(gen_module_functions(module_functions, mod_scope, prims, &mut bstr_literals,
+        &mut symbols, options)?);
+
+    let mut entry_point = gen_test_harness_entry_point(functions, mod_scope, prims)?;
+    init_statics(&mut entry_point, static_inits);
+    merge_identical_functions(&mut cfunctions, &mut entry_point, &mut symbols);
 
-    Ok(CExecutableProgram {structs, functions, entry_point})
+    let str_constants = bstr_literals.into_constants();
+    Ok(CExecutableProgram {structs, str_constants, globals, functions: cfunctions, entry_point,
+        symbols})
+}
+
+/// Returns the name of the C global variable generated for the static with the given name
+///
+/// A qualified name (see `resolve2::QualifiedNames`) is mangled since it can't be part of a C
+/// identifier as is.
+fn static_var_name(name: &str) -> String {
+    if name.contains("::") {
+        format!("__dino__static_{}", mangle_path(&[name]))
+    } else {
+        format!("__dino__static_{}", name)
+    }
+}
+
+/// Returns the name of the C type generated for the given type
+///
+/// Extern types are defined by the runtime or the standard library, so they keep their names.
+fn type_mangled_name(ty_id: TyId, mod_scope: &DeclMap) -> String {
+    let name = *mod_scope.type_name(ty_id);
+    if mod_scope.type_is_extern(ty_id) {
+        name.to_string()
+    } else {
+        mangle_path(&[name])
+    }
+}
+
+/// Returns the name of the C struct field generated for the field with the given name
+fn field_mangled_name(name: &str) -> String {
+    mangle_path(&[name])
+}
+
+/// Returns the name of the C struct field for the given field of the given type
+///
+/// Just like their type names, the fields of extern types are defined by the runtime or the
+/// standard library, so they keep their names.
+fn type_field_mangled_name(ty_id: TyId, name: &str, mod_scope: &DeclMap) -> String {
+    if mod_scope.type_is_extern(ty_id) {
+        name.to_string()
+    } else {
+        field_mangled_name(name)
+    }
+}
+
+/// Generates a global variable for each static, along with the statements that assign each
+/// global variable its initial value
+fn gen_statics(
+    statics: &[ir::Static],
+    mod_scope: &DeclMap,
+    prims: &Primitives,
+    bstr_literals: &mut BStrLiterals,
+    symbols: &mut CSymbolMap,
+) -> Result<(Vec<CGlobalVar>, Vec<CStmt>), Error> {
+    let mut globals = Vec::new();
+    let mut inits = Vec::new();
+
+    for static_decl in statics {
+        let ir::Static {name, ty, init: _} = static_decl;
+
+        symbols.insert(CSymbolKind::Static, name.to_string(), static_var_name(name));
+        globals.push(CGlobalVar {
+            mangled_name: static_var_name(name),
+            ty: CTy::pointer(type_mangled_name(*ty, mod_scope)),
+        });
+        inits.extend(FunctionCodeGenerator::generate_static_init(static_decl, mod_scope, prims,
+            bstr_literals)?);
+    }
+
+    Ok((globals, inits))
+}
+
+/// Initializes every static at the very start of the entry point, before any other code runs
+fn init_statics(entry_point: &mut CEntryPoint, static_inits: Vec<CStmt>) {
+    let CEntryPoint {body} = entry_point;
+    let CStmts(stmts) = body;
+    let rest = std::mem::replace(stmts, static_inits);
+    stmts.extend(rest);
+}
+
+/// Generates an entry point that runs each test function and reports the results
+///
+/// Each test is run in its own process by the runtime so that a test that traps or aborts is
+/// reported as a failure without stopping the remaining tests from running.
+fn gen_test_harness_entry_point(
+    functions: &[ir::Function],
+    mod_scope: &DeclMap,
+    prims: &Primitives,
+) -> Result<CEntryPoint, Error> {
+    let mut body = Vec::new();
+
+    for func in functions.iter().filter(|func| func.is_test) {
+        let ir::Function {name, sig, ..} = func;
+        // Test functions are called with no arguments and their result is ignored
+        if sig.return_type != prims.unit() || !sig.params.is_empty() {
+            return Err(Error::InvalidTestType {name: name.to_string()});
+        }
+
+        // Generates:
+        //
+        //     if (__dino__test_start("name")) {
+        //         dino_4name();
+        //         __dino__test_pass();
+        //     }
+        //
+        // The test only runs if `__dino__test_start` returns true (in the process created for the
+        // test). If the test returns normally, `__dino__test_pass` ends that process successfully.
+        body.push(CStmt::Cond(CCond {
+            cond_expr: CExpr::Call(CCallExpr {
+                mangled_func_name: "__dino__test_start".to_string(),
+                args: vec![CExpr::NTStrLiteral(name.as_bytes().to_vec())],
+            }),
+            if_body: CStmts(vec![
+                CStmt::Expr(CExpr::Call(CCallExpr {
+                    mangled_func_name: mangle_path(&[*name]),
+                    args: Vec::new(),
+                })),
+                CStmt::Expr(CExpr::Call(CCallExpr {
+                    mangled_func_name: "__dino__test_pass".to_string(),
+                    args: Vec::new(),
+                })),
+            ]),
+            else_body: None,
+        }));
+    }
+
+    // Prints the results and exits with a non-zero exit code if any of the tests failed
+    body.push(CStmt::Expr(CExpr::Call(CCallExpr {
+        mangled_func_name: "__dino__test_summary".to_string(),
+        args: Vec::new(),
+    })));
+
+    let unit_constructor = mod_scope.type_lit_constructors(prims.unit()).unit_literal_constructor
+        .expect("bug: no unit literal constructor defined for the unit type");
+    body.push(CStmt::Return(CExpr::Call(CCallExpr {
+        //TODO: Mangle function names
+        mangled_func_name: unit_constructor.to_string(),
+        args: Vec::new(),
+    })));
+
+    Ok(CEntryPoint {body: CStmts(body)})
 }
 
 /// Returns the functions generated for the methods of all the types
 fn gen_types(
     types: &[ir::Struct],
     mod_scope: &DeclMap,
-    _prims: &Primitives,
+    prims: &Primitives,
     structs: &mut Vec<CStruct>,
+    bstr_literals: &mut BStrLiterals,
+    symbols: &mut CSymbolMap,
+    options: &CodegenOptions,
 ) -> Result<Vec<CFunction>, Error> {
     types.iter().map(|struct_decl| {
-        let ir::Struct {name, is_extern: _, fields, methods} = struct_decl;
+        let ir::Struct {name, is_extern, fields, methods} = struct_decl;
 
-        //TODO: Mangle struct names based on `is_extern`
-        let struct_mangled_name = name.to_string();
+        // Extern types are defined by the runtime or the standard library
+        let struct_mangled_name = if *is_extern {
+            name.to_string()
+        } else {
+            mangle_path(&[*name])
+        };
+        symbols.insert(CSymbolKind::Struct, name.to_string(), struct_mangled_name.clone());
         structs.push(CStruct {
             mangled_name: struct_mangled_name.clone(),
+            // Fields must be generated in declaration order so the layout matches `offset_of`
             fields: fields.iter().map(|(name, &ty_id)| CStructField {
-                //TODO: Mangle struct field names
-                mangled_name: name.to_string(),
-                //TODO: Get mangled name
-                ty: CTy::pointer(mod_scope.type_name(ty_id).to_string()),
+                mangled_name: field_mangled_name(name),
+                ty: CTy::pointer(type_mangled_name(ty_id, mod_scope)),
             }).collect(),
         });
 
-        let methods = methods.iter().map(move |(method_name, func)| {
+        // Methods are stored in a hash map, so they are sorted by name to keep the generated code
+        // the same every time the program is compiled
+        let mut methods: Vec<_> = methods.iter().collect();
+        methods.sort_by_key(|&(method_name, _)| method_name);
+        // Collected right away so that the byte string literals are only borrowed once at a time
+        let methods: Vec<_> = methods.into_iter().map(|(method_name, func)| {
+            let mangled_name = mangle_path(&[*name, *method_name]);
+            symbols.insert(CSymbolKind::Method, format!("{}::{}", name, method_name),
+                mangled_name.clone());
             let func = ir::Function {
-                //TODO: Figure out a better way to generate this name
-                name: &format!("{}__{}", struct_mangled_name, method_name),
+                name: &mangled_name,
                 ..func.clone()
             };
-            FunctionCodeGenerator::generate(&func, mod_scope)
-        });
+            FunctionCodeGenerator::generate(&func, mod_scope, prims, bstr_literals, options)
+        }).collect();
 
-        iter::once({
-            // Generate a constructor that takes a value of this struct and puts it on the heap
-            //let constructor = ir::Function {
-            //    //TODO: Figure out a better way to generate this name and store it with the struct
-            //    // for use later on in code generation
-            //    name: &format!("init_{}", struct_mangled_name),
-            //};
-            //FunctionCodeGenerator::generate(&constructor, mod_scope)
-            unimplemented!()
-        }).chain(methods)
+        // Values of the struct are copied whenever they are used so that they never alias. Extern
+        // types are opaque, so they are always passed around as-is.
+        let copy_func = if *is_extern {
+            None
+        } else {
+            Some(Ok(gen_struct_copy(&struct_mangled_name, fields, mod_scope)))
+        };
+        // Only structs whose fields can all be printed can be passed to `debug_print`
+        let debug_print_func = if *is_extern {
+            None
+        } else {
+            fields.iter()
+                .map(|(_, &ty_id)| debug_print_func_name(ty_id, mod_scope, prims))
+                .collect::<Option<Vec<_>>>()
+                .map(|printers| Ok(gen_struct_debug_print(&struct_mangled_name, fields, printers,
+                    mod_scope, prims)))
+        };
+        copy_func.into_iter().chain(debug_print_func).chain(methods)
     }).flatten().collect()
 }
 
+/// Returns the name of the function that copies a value of the struct with the given name
+fn struct_copy_func_name(struct_mangled_name: &str) -> String {
+    format!("__dino__copy_{}", struct_mangled_name)
+}
+
+/// Generates a function that returns a new copy of a value of the given struct
+///
+/// Fields that are structs are copied as well. Every other value is never modified in place, so
+/// the copy can share those values with the original.
+fn gen_struct_copy(struct_mangled_name: &str, fields: &ir::FieldTys, mod_scope: &DeclMap) -> CFunction {
+    let value_name = "value".to_string();
+    let copy_name = "copy".to_string();
+
+    let mut body = vec![CStmt::VarDecl(CVarDecl {
+        mangled_name: copy_name.clone(),
+        ty: CTy::pointer(struct_mangled_name.to_string()),
+        init_expr: CInitializerExpr::Expr(CExpr::Call(CCallExpr {
+            mangled_func_name: ALLOCATE.to_string(),
+            args: vec![CExpr::Sizeof(CTy::Named {mangled_name: struct_mangled_name.to_string()})],
+        })),
+    })];
+
+    for (name, &ty_id) in fields.iter() {
+        let field_value = CExpr::FieldAccess(Box::new(CFieldAccess {
+            lhs: CExpr::Var(value_name.clone()),
+            field_mangled_name: field_mangled_name(name),
+        }));
+        let field_value = if mod_scope.type_is_extern(ty_id) {
+            field_value
+        } else {
+            CExpr::Call(CCallExpr {
+                mangled_func_name: struct_copy_func_name(&type_mangled_name(ty_id, mod_scope)),
+                args: vec![field_value],
+            })
+        };
+
+        body.push(CStmt::VarAssign(CVarAssign {
+            lvalue: CLValue::FieldAccess(CFieldAccess {
+                lhs: CExpr::Var(copy_name.clone()),
+                field_mangled_name: field_mangled_name(name),
+            }),
+            init_expr: CInitializerExpr::Expr(field_value),
+        }));
+    }
+
+    body.push(CStmt::Return(CExpr::Var(copy_name)));
+
+    CFunction {
+        sig: CFunctionSignature {
+            mangled_name: struct_copy_func_name(struct_mangled_name),
+            return_type: CTy::pointer(struct_mangled_name.to_string()),
+            params: vec![CFunctionParam {
+                mangled_name: value_name,
+                ty: CTy::pointer(struct_mangled_name.to_string()),
+            }],
+        },
+        body: CStmts(body),
+    }
+}
+
+/// Returns the name of the function that `debug_print` calls to print a value of the given type,
+/// or None if values of the type cannot be printed
+///
+/// Primitive types are printed with the `println_*` function for that type (e.g. `println_int`).
+/// Structs are printed with a generated function that prints each of their fields.
+fn debug_print_func_name(ty_id: TyId, mod_scope: &DeclMap, prims: &Primitives) -> Option<String> {
+    debug_print_func_name_within(ty_id, mod_scope, prims, &mut Vec::new())
+}
+
+/// Like `debug_print_func_name`, but only for a type nested within the fields of the given
+/// structs
+fn debug_print_func_name_within(
+    ty_id: TyId,
+    mod_scope: &DeclMap,
+    prims: &Primitives,
+    enclosing_structs: &mut Vec<TyId>,
+) -> Option<String> {
+    if mod_scope.type_is_extern(ty_id) {
+        let print_func_name = match prims.name_of(ty_id) {
+            // The unit type has no name, so its print function is named differently
+            Some(_) if ty_id == prims.unit() => "println_unit".to_string(),
+            Some(name) => format!("println_{}", name),
+            // Only primitive types can have a print function
+            None => return None,
+        };
+        let has_print_func = mod_scope.functions().any(|(name, _)| name == print_func_name);
+        return if has_print_func { Some(print_func_name) } else { None };
+    }
+
+    // A struct that contains itself can never be constructed, so it never needs to be printed
+    if enclosing_structs.contains(&ty_id) {
+        return None;
+    }
+
+    enclosing_structs.push(ty_id);
+    let printable = mod_scope.fields(ty_id)
+        .all(|(_, &field_ty)| {
+            debug_print_func_name_within(field_ty, mod_scope, prims, enclosing_structs).is_some()
+        });
+    enclosing_structs.pop();
+
+    if printable {
+        Some(struct_debug_print_func_name(&type_mangled_name(ty_id, mod_scope)))
+    } else {
+        None
+    }
+}
+
+/// Returns the name of the function that prints a value of the struct with the given name
+fn struct_debug_print_func_name(struct_mangled_name: &str) -> String {
+    format!("__dino__debug_print_{}", struct_mangled_name)
+}
+
+/// Generates a function that prints the value of each field of the given struct in the order the
+/// fields were declared
+///
+/// Each field is printed with the corresponding function in `printers`, so fields that are structs
+/// are printed recursively.
+fn gen_struct_debug_print(
+    struct_mangled_name: &str,
+    fields: &ir::FieldTys,
+    printers: Vec<String>,
+    mod_scope: &DeclMap,
+    prims: &Primitives,
+) -> CFunction {
+    let value_name = "value".to_string();
+
+    let mut body: Vec<_> = fields.iter().zip(printers).map(|((name, _), printer)| {
+        let field_value = CExpr::FieldAccess(Box::new(CFieldAccess {
+            lhs: CExpr::Var(value_name.clone()),
+            field_mangled_name: field_mangled_name(name),
+        }));
+
+        CStmt::Expr(CExpr::Call(CCallExpr {
+            mangled_func_name: printer,
+            args: vec![field_value],
+        }))
+    }).collect();
+
+    let unit_constructor = mod_scope.type_lit_constructors(prims.unit()).unit_literal_constructor
+        .expect("bug: no unit literal constructor defined for the unit type");
+    body.push(CStmt::Return(CExpr::Call(CCallExpr {
+        //TODO: Mangle function names
+        mangled_func_name: unit_constructor.to_string(),
+        args: Vec::new(),
+    })));
+
+    CFunction {
+        sig: CFunctionSignature {
+            mangled_name: struct_debug_print_func_name(struct_mangled_name),
+            return_type: CTy::pointer(type_mangled_name(prims.unit(), mod_scope)),
+            params: vec![CFunctionParam {
+                mangled_name: value_name,
+                ty: CTy::pointer(struct_mangled_name.to_string()),
+            }],
+        },
+        body: CStmts(body),
+    }
+}
+
+/// Generates the functions declared in inline modules
+///
+/// Each function is named after its module, the same way methods are named after their type. A
+/// `main` function in a module is never the entry point.
+fn gen_module_functions(
+    module_functions: &[(ir::Ident, ir::Function)],
+    mod_scope: &DeclMap,
+    prims: &Primitives,
+    bstr_literals: &mut BStrLiterals,
+    symbols: &mut CSymbolMap,
+    options: &CodegenOptions,
+) -> Result<Vec<CFunction>, Error> {
+    module_functions.iter().map(|(module_name, func)| {
+        let mangled_name = mangle_path(&[*module_name, func.name]);
+        symbols.insert(CSymbolKind::Function, format!("{}::{}", module_name, func.name),
+            mangled_name.clone());
+        let func = ir::Function {
+            name: &mangled_name,
+            ..func.clone()
+        };
+        FunctionCodeGenerator::generate(&func, mod_scope, prims, bstr_literals, options)
+    }).collect()
+}
+
 fn gen_functions(
     functions: &[ir::Function],
     mod_scope: &DeclMap,
     prims: &Primitives,
+    bstr_literals: &mut BStrLiterals,
     entry_point: &mut Option<CEntryPoint>,
+    symbols: &mut CSymbolMap,
+    options: &CodegenOptions,
 ) -> Result<Vec<CFunction>, Error> {
     functions.iter().filter_map(|func| {
         // A "main" function in the top level declarations of a program must be the entry point
@@ -110,7 +550,8 @@ fn gen_functions(
             debug_assert!(entry_point.is_none(), "bug: allowed multiple entry points");
 
             // Take the generated body and put it in the right struct
-            let CFunction {sig: _, body} = match FunctionCodeGenerator::generate(func, mod_scope) {
+            let func = FunctionCodeGenerator::generate(func, mod_scope, prims, bstr_literals, options);
+            let CFunction {sig: _, body} = match func {
                 Ok(func) => func,
                 Err(err) => return Some(Err(err)),
             };
@@ -120,7 +561,518 @@ fn gen_functions(
             None
 
         } else {
-            Some(FunctionCodeGenerator::generate(func, mod_scope))
+            let mangled_name = mangle_path(&[func.name]);
+            symbols.insert(CSymbolKind::Function, func.name.to_string(), mangled_name.clone());
+            let func = ir::Function {
+                name: &mangled_name,
+                ..func.clone()
+            };
+            Some(FunctionCodeGenerator::generate(&func, mod_scope, prims, bstr_literals, options))
         }
     }).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tycheck::check_source;
+
+    /// Generates the executable built from the given program
+    fn generate_program(source: &'static str) -> CExecutableProgram {
+        generate_program_with(source, &CodegenOptions::default())
+    }
+
+    /// Generates the executable built from the given program (read from `test.dino`) using the
+    /// given options
+    fn generate_program_with(source: &'static str, options: &CodegenOptions) -> CExecutableProgram {
+        let checked = check_source(source).unwrap();
+        executable(&checked.program, checked.decls, options).unwrap()
+    }
+
+    /// Generates the C code for the executable built from the given program
+    fn generate(source: &'static str) -> String {
+        generate_program(source).to_string()
+    }
+
+    /// Returns true if the last statement of every branch of the given conditional (including
+    /// any nested `else if` branches) assigns to the variable with the given name
+    fn assigns_in_every_branch(cond: &CCond, var_name: &str) -> bool {
+        let assigns_var = |stmts: &CStmts| match stmts.0.last() {
+            Some(CStmt::VarAssign(CVarAssign {lvalue: CLValue::Var {mangled_name}, ..})) => {
+                mangled_name == var_name
+            },
+            // An `else if` is nested in the `else` clause
+            Some(CStmt::Cond(cond)) => assigns_in_every_branch(cond, var_name),
+            _ => false,
+        };
+
+        let CCond {cond_expr: _, if_body, else_body} = cond;
+        assigns_var(if_body) && else_body.as_ref().map(assigns_var).unwrap_or(false)
+    }
+
+    #[test]
+    fn test_harness_runs_each_test() {
+        let source = "
+            fn double(x: int) -> int { x * 2 }
+
+            #[test]
+            fn double_passes() {
+                assert(double(2) == 4);
+            }
+
+            #[test]
+            fn double_fails() {
+                assert(double(2) == 5);
+            }
+
+            fn main() {
+                print_int(double(3));
+            }
+        ";
+        let checked = check_source(source).unwrap();
+        let code = test_harness(&checked.program, checked.decls, &CodegenOptions::default())
+            .unwrap().to_string();
+
+        // Every test runs in declaration order, whether it passes or fails, followed by the summary
+        let entry_point = "\
+            DUnit* __dino__main(void) {\n\
+            if (__dino__test_start((const unsigned char *)\"double_passes\")) {\n\
+            dino_13double_passes();\n\
+            __dino__test_pass();\n\
+            \n\
+            }\n\
+            \n\
+            if (__dino__test_start((const unsigned char *)\"double_fails\")) {\n\
+            dino_12double_fails();\n\
+            __dino__test_pass();\n\
+            \n\
+            }\n\
+            \n\
+            __dino__test_summary();\n";
+        assert!(code.contains(entry_point), "test entry point not found in generated code:\n{}", code);
+
+        // Functions called by the tests are generated, but `main` is not
+        assert!(code.contains("DInt* dino_6double("), "test helper not found in generated code:\n{}", code);
+        assert!(!code.contains("print_int"), "`main` should not be generated:\n{}", code);
+
+        // Tests cannot take parameters
+        let source = "
+            #[test]
+            fn takes_param(x: int) {
+                assert(x == 1);
+            }
+        ";
+        let checked = check_source(source).unwrap();
+        let err = test_harness(&checked.program, checked.decls, &CodegenOptions::default())
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidTestType {ref name} if name == "takes_param"), "{:?}", err);
+    }
+
+    #[test]
+    fn struct_definitions_and_field_access() {
+        let source = "
+            struct Point { x: int, y: int }
+            struct Line { start: Point, end: Point }
+
+            impl Point {
+                fn sum(self) -> int { self.x + self.y }
+                fn diff(self) -> int { self.x - self.y }
+                fn product(self) -> int { self.x * self.y }
+            }
+
+            fn main() {
+                let line = Line {start: Point {x: 1, y: 2}, end: Point {x: 3, y: 4}};
+                print_int(line.end.y);
+                print_int(line.start.sum());
+            }
+        ";
+        let code = generate(source);
+
+        // Structs are defined in the order they were declared and their fields are always in
+        // declaration order
+        let structs = "\
+            typedef struct dino_5Point dino_5Point;\n\
+            typedef struct dino_4Line dino_4Line;\n\
+            struct dino_5Point {\n\
+            DInt* dino_1x;\n\
+            DInt* dino_1y;\n\
+            };\n\
+            struct dino_4Line {\n\
+            dino_5Point* dino_5start;\n\
+            dino_5Point* dino_3end;\n\
+            };\n";
+        assert!(code.contains(structs), "structs not found in generated code:\n{}", code);
+
+        // Nested fields are read through each struct in turn and fields are initialized one by one
+        assert!(code.contains("->dino_3end->dino_1y"), "field access not found in generated code:\n{}", code);
+        assert!(code.contains("->dino_1x = "), "field initialization not found in generated code:\n{}", code);
+        assert!(code.contains("dino_5Point3sum("), "method call not found in generated code:\n{}", code);
+
+        // Methods are stored in hash maps, so this checks that their order does not depend on
+        // the order of the map
+        for _ in 0..5 {
+            assert_eq!(generate(source), code);
+        }
+    }
+
+    #[test]
+    fn extern_type_fields() {
+        let source = "
+            fn main() {
+                let x: int = 7;
+                let result = x.divmod(2);
+                print_int(result.quotient);
+                print_int(result.remainder);
+            }
+        ";
+        let code = generate(source);
+
+        // The fields of extern types are defined by the standard library, so they are not mangled
+        assert!(code.contains("int__divmod("), "divmod call not found in generated code:\n{}", code);
+        assert!(code.contains("->quotient"), "field access not found in generated code:\n{}", code);
+        assert!(code.contains("->remainder"), "field access not found in generated code:\n{}", code);
+    }
+
+    #[test]
+    fn while_loop() {
+        let source = "
+            fn main() {
+                let count: int = 3;
+                while count > 0 {
+                    print_int(count);
+                    count = count - 1;
+                }
+            }
+        ";
+        let CStmts(stmts) = generate_program(source).entry_point.body;
+
+        let body = stmts.iter().find_map(|stmt| match stmt {
+            CStmt::Loop(CInfiniteLoop {body}) => Some(body),
+            _ => None,
+        }).expect("bug: no loop generated for `while`");
+
+        // The condition is checked at the start of every iteration and exits the loop when it is
+        // false, before any of the statements in the body of the loop are run
+        let exit_index = body.0.iter().position(|stmt| matches!(stmt,
+            CStmt::Cond(CCond {if_body, else_body: Some(CStmts(else_stmts)), ..})
+                if if_body.0.is_empty() && matches!(&else_stmts[..], [CStmt::BreakLoop])
+        )).expect("bug: no loop exit generated for the condition of `while`");
+        let print_index = body.0.iter().position(|stmt| matches!(stmt,
+            CStmt::Expr(CExpr::Call(CCallExpr {mangled_func_name, ..})) if mangled_func_name == "print_int"
+        )).expect("bug: body of `while` not generated in loop");
+        assert!(exit_index < print_index);
+    }
+
+    #[test]
+    fn cond_expr() {
+        let source = "
+            fn main() {
+                let value: int = 3;
+                let parity = if value == 3 {
+                    b\"three\"
+                } else if value.rem(2) == 0 {
+                    b\"even\"
+                } else {
+                    b\"odd\"
+                };
+                print_bstr(parity);
+
+                // A conditional with no `else` can only be used as a value of type unit
+                let _nothing = if value > 5 {
+                    print_int(value);
+                };
+            }
+        ";
+        let CStmts(stmts) = generate_program(source).entry_point.body;
+
+        // Each conditional stores its result in a temporary variable that is declared without a
+        // value and then assigned in every branch
+        let mut results = Vec::new();
+        for (i, stmt) in stmts.iter().enumerate() {
+            if let CStmt::TempVarDecl(CTempVarDecl {mangled_name, init_expr: None, ..}) = stmt {
+                match &stmts[i + 1] {
+                    CStmt::Cond(cond) => assert!(assigns_in_every_branch(cond, mangled_name),
+                        "not every branch assigns the result of the conditional: {:?}", cond),
+                    stmt => panic!("expected a conditional after the result variable, got: {:?}", stmt),
+                }
+                results.push(mangled_name);
+            }
+        }
+        assert_eq!(results.len(), 2);
+
+        // The variable declared with the conditional is initialized with the temporary variable
+        let initialized_with_result = stmts.iter().any(|stmt| matches!(stmt,
+            CStmt::VarDecl(CVarDecl {init_expr: CInitializerExpr::Expr(CExpr::Var(name)), ..})
+                if name == results[0]
+        ));
+        assert!(initialized_with_result);
+    }
+
+    #[test]
+    fn symbol_map() {
+        let source = "
+            struct Point { x: int, y: int }
+            impl Point {
+                fn sum(self) -> int { self.x + self.y }
+            }
+
+            mod math {
+                fn double(x: int) -> int { x * 2 }
+            }
+
+            static mut COUNT: int = 0;
+
+            fn double(x: int) -> int { x * 2 }
+
+            fn main() {
+                COUNT = double(math::double(Point {x: 1, y: 2}.sum()));
+                print_int(COUNT);
+            }
+        ";
+        let symbols = generate_program(source).symbols;
+
+        assert_eq!(symbols.mangled_name("Point"), Some("dino_5Point"));
+        assert_eq!(symbols.mangled_name("Point::sum"), Some("dino_5Point3sum"));
+        assert_eq!(symbols.mangled_name("COUNT"), Some("__dino__static_COUNT"));
+        assert_eq!(symbols.mangled_name("main"), Some("main"));
+        // Both `double` functions have the same code, so the one generated last is merged into
+        // the one generated first
+        assert_eq!(symbols.mangled_name("double"), Some("dino_6double"));
+        assert_eq!(symbols.mangled_name("math::double"), Some("dino_6double"));
+
+        // One line per symbol
+        let map = symbols.to_string();
+        assert!(map.contains("method\tPoint::sum\tdino_5Point3sum\n"));
+        assert!(map.contains("static\tCOUNT\t__dino__static_COUNT\n"));
+    }
+
+    #[test]
+    fn unreachable_functions() {
+        let source = "
+            struct Percent { value: int } requires value >= 0
+            impl Percent {
+                fn new(value: int) -> Self { Percent {value: value} }
+                fn half(self) -> Self { Percent::new(self.value / 2) }
+            }
+
+            mod math {
+                fn triple(x: int) -> int { x * 3 }
+            }
+
+            fn helper() -> int { math::triple(2) }
+            fn unused() -> int { helper() }
+            fn used() -> int { Percent::new(3).value }
+
+            fn main() {
+                print_int(used());
+            }
+        ";
+        let symbols = generate_program(source).symbols;
+
+        assert!(symbols.mangled_name("used").is_some());
+        assert!(symbols.mangled_name("Percent::new").is_some());
+        // Invariants are called implicitly whenever the struct is created
+        assert!(symbols.mangled_name("Percent::__invariant").is_some());
+
+        assert_eq!(symbols.mangled_name("unused"), None);
+        assert_eq!(symbols.mangled_name("helper"), None);
+        assert_eq!(symbols.mangled_name("math::triple"), None);
+        assert_eq!(symbols.mangled_name("Percent::half"), None);
+    }
+
+    #[test]
+    fn c_keywords_and_runtime_names() {
+        let source = "
+            struct char { default: int }
+            impl char {
+                fn switch(self) -> int { self.default }
+            }
+
+            mod register {
+                fn case(x: int) -> int { x + 1 }
+            }
+
+            fn goto(x: int) -> int { x * 2 }
+            // Would collide with the function from the C standard library
+            fn exit(x: int) -> int { x * 3 }
+
+            fn main() {
+                let value = char {default: 2};
+                print_int(goto(exit(register::case(value.switch()))));
+                // Calling an extern method by its path
+                print_int(int::sub(3, 1));
+            }
+        ";
+        let program = generate_program(source);
+        let code = program.to_string();
+
+        for name in &["dino_4char", "dino_7default", "dino_4char6switch", "dino_8register4case",
+            "dino_4goto", "dino_4exit"] {
+            assert!(code.contains(name), "`{}` not found in generated code:\n{}", name, code);
+        }
+        // Extern functions are linked with the runtime, so they must keep their names
+        assert!(code.contains("print_int("), "extern call not found in generated code:\n{}", code);
+        assert!(code.contains("int__add("), "extern call not found in generated code:\n{}", code);
+        assert!(code.contains("int__sub("), "extern call not found in generated code:\n{}", code);
+        assert!(!code.contains("dino_3int3sub"), "extern method was mangled:\n{}", code);
+
+        let func_names: Vec<_> = program.functions.iter().map(|func| &*func.sig.mangled_name).collect();
+        assert!(!func_names.contains(&"goto") && !func_names.contains(&"exit"));
+    }
+
+    #[test]
+    fn line_directives() {
+        let source = "fn main() {\n    let x: int = 1;\n\n    if x > 0 {\n        print_int(x);\n    }\n    print_int(x + 1);\n}\n";
+
+        // Off by default
+        assert!(!generate(source).contains("#line"));
+
+        let options = CodegenOptions {line_directives: true, ..CodegenOptions::default()};
+        let code = generate_program_with(source, &options).to_string();
+        let lines: Vec<_> = code.lines().collect();
+        let directive_lines: Vec<_> = lines.iter().filter(|line| line.starts_with("#line")).collect();
+        assert_eq!(directive_lines, vec![
+            &"#line 2 \"test.dino\"",
+            &"#line 4 \"test.dino\"",
+            &"#line 5 \"test.dino\"",
+            &"#line 7 \"test.dino\"",
+        ]);
+
+        // Each directive comes before the code generated for its statement
+        let directive = lines.iter().position(|&line| line == "#line 7 \"test.dino\"").unwrap();
+        assert!(lines[directive + 1..].iter().any(|line| line.contains("print_int(")));
+        assert!(lines[..directive].iter().all(|line| !line.contains("int__add(")));
+    }
+
+    #[test]
+    fn self_tail_calls() {
+        // Both an explicit `return` and the final expression of the body are in tail position
+        let source = "
+            fn count_down(n: int) -> int {
+                if n == 0 {
+                    return 0;
+                }
+                count_down(n - 1)
+            }
+            fn sum(n: int, total: int) -> int {
+                if n == 0 {
+                    return total;
+                }
+                return sum(n - 1, total + n);
+            }
+            fn main() { print_int(count_down(3)); print_int(sum(3, 0)); }
+        ";
+        let program = generate_program(source);
+        let find_func = |program: &CExecutableProgram, name| program.functions.iter()
+            .find(|func| func.sig.mangled_name == name)
+            .map(|func| (matches!(&func.body.0[..], [CStmt::Loop(_)]), func.to_string()))
+            .unwrap();
+        for &name in &["dino_10count_down", "dino_3sum"] {
+            let (is_loop, code) = find_func(&program, name);
+            assert!(is_loop);
+            // Only the signature refers to the function
+            assert_eq!(code.matches(&format!("{}(", name)).count(), 1);
+            assert!(code.contains("continue;"));
+        }
+
+        // A recursive call whose result is used is not in tail position
+        let source = "
+            fn factorial(n: int) -> int {
+                if n == 0 {
+                    return 1;
+                }
+                n * factorial(n - 1)
+            }
+            fn main() { print_int(factorial(3)); }
+        ";
+        let (is_loop, _) = find_func(&generate_program(source), "dino_9factorial");
+        assert!(!is_loop);
+
+        // A `continue` inside of a loop would apply to that loop instead
+        let source = "
+            fn wait(n: int) -> int {
+                while true {
+                    return wait(n - 1);
+                }
+                n
+            }
+            fn main() { print_int(wait(3)); }
+        ";
+        let (is_loop, _) = find_func(&generate_program(source), "dino_4wait");
+        assert!(!is_loop);
+    }
+
+    #[test]
+    fn elide_bounds_checks() {
+        let options = CodegenOptions {elide_bounds_checks: true, ..CodegenOptions::default()};
+        let elided = |source| generate_program_with(source, &options).to_string()
+            .matches("int_list_get_unchecked(").count();
+
+        let source = "
+            fn print_list(list: int_list) {
+                let i: int = 0;
+                while i < int_list_len(list) {
+                    print_int(int_list_get(list, i));
+                    i = i + 1;
+                }
+            }
+            fn main() { print_list(int_list_new()); }
+        ";
+        assert_eq!(elided(source), 1);
+        // Off by default
+        assert!(!generate(source).contains("int_list_get_unchecked("));
+
+        // The index may start out negative
+        let source = "
+            fn print_list(list: int_list, i: int) {
+                while i < int_list_len(list) {
+                    print_int(int_list_get(list, i));
+                    i = i + 1;
+                }
+            }
+            fn main() { print_list(int_list_new(), 0); }
+        ";
+        assert_eq!(elided(source), 0);
+
+        // The index or the list is changed before the end of the loop body
+        let source = "
+            fn print_list(list: int_list) {
+                let i: int = 0;
+                while i < int_list_len(list) {
+                    i = i + 1;
+                    print_int(int_list_get(list, i));
+                    i = i + 1;
+                }
+            }
+            fn main() { print_list(int_list_new()); }
+        ";
+        assert_eq!(elided(source), 0);
+        let source = "
+            fn print_list(list: int_list) {
+                let i: int = 0;
+                while i < int_list_len(list) {
+                    list = int_list_new();
+                    print_int(int_list_get(list, i));
+                    i = i + 1;
+                }
+            }
+            fn main() { print_list(int_list_new()); }
+        ";
+        assert_eq!(elided(source), 0);
+
+        // A variable declared in the loop shadows the index
+        let source = "
+            fn print_list(list: int_list) {
+                let i: int = 0;
+                while i < int_list_len(list) {
+                    let i: int = 7;
+                    print_int(int_list_get(list, i));
+                    i = i + 1;
+                }
+            }
+            fn main() { print_list(int_list_new()); }
+        ";
+        assert_eq!(elided(source), 0);
+    }
+}
@@ -0,0 +1,134 @@
+//! Finds list indexing in loops where the index is always known to be in bounds
+
+use crate::ir::{self, visit::{self, Visitor}};
+
+/// The prelude function that returns an item of a list after checking its index
+pub const CHECKED_GET: &str = "int_list_get";
+/// The standard library function that returns an item of a list without checking its index
+pub const UNCHECKED_GET: &str = "int_list_get_unchecked";
+
+/// A list variable and an index variable that is always in bounds for that list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InBoundsIndex {
+    pub list: String,
+    pub index: String,
+}
+
+/// Returns the list and index variables of a loop that visits every index of a list once
+///
+/// Only the most obvious form of this loop is recognized:
+///
+/// ```text
+/// let i: int = 0;
+/// while i < int_list_len(list) {
+///     ... int_list_get(list, i) ...
+///     i = i + 1;
+/// }
+/// ```
+///
+/// The index must be initialized to a non-negative literal by the statement right before the
+/// loop, and incrementing it must be the last statement of the loop body. Neither variable may be
+/// assigned or redeclared anywhere else in the body. Lists are never modified in place, so the
+/// length of the list can only change if the list variable is assigned. Statics are never
+/// considered since any function called in the loop could assign to them.
+pub fn in_bounds_index(prev_stmt: Option<&ir::Stmt>, wloop: &ir::WhileLoop) -> Option<InBoundsIndex> {
+    let ir::WhileLoop {cond, body} = wloop;
+
+    let (index, list) = match cond {
+        ir::Expr::Call(ir::CallExpr {func_name, args}, _) => match (&func_name.components[..], &args[..]) {
+            (["int__lt"], [ir::Expr::Var(index, _), ir::Expr::Call(len_call, _)]) => {
+                match (&len_call.func_name.components[..], &len_call.args[..]) {
+                    (["int_list_len"], [ir::Expr::Var(list, _)]) => (*index, *list),
+                    _ => return None,
+                }
+            },
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    // The index and the list must be different variables for `i = i + 1` to keep `i` in bounds
+    if index == list {
+        return None;
+    }
+
+    let starts_non_negative = match prev_stmt {
+        Some(ir::Stmt::VarDecl(ir::VarDecl {ident, ty: _, expr: ir::Expr::IntegerLiteral(value, _)})) => {
+            *ident == index && *value >= 0
+        },
+        Some(ir::Stmt::Expr(ir::Expr::VarAssign(assign, _))) => match &**assign {
+            ir::VarAssign {lhs: ir::LValueExpr::Var(name, _), expr: ir::Expr::IntegerLiteral(value, _)} => {
+                *name == index && *value >= 0
+            },
+            _ => false,
+        },
+        _ => false,
+    };
+    if !starts_non_negative {
+        return None;
+    }
+
+    let ir::Block {stmts, stmt_lines: _, ret, ret_ty: _} = body;
+    let (last, rest) = stmts.split_last()?;
+    if ret.is_some() || !is_increment(last, index) {
+        return None;
+    }
+
+    let vars = [index, list];
+    let mut finder = AssignFinder {vars: &vars, found: false};
+    for stmt in rest {
+        finder.visit_stmt(stmt, None);
+    }
+    if finder.found {
+        return None;
+    }
+
+    Some(InBoundsIndex {list: list.to_string(), index: index.to_string()})
+}
+
+/// Returns true if the given statement is exactly `index = index + 1`
+fn is_increment(stmt: &ir::Stmt, index: &str) -> bool {
+    let assign = match stmt {
+        ir::Stmt::Expr(ir::Expr::VarAssign(assign, _)) => assign,
+        _ => return false,
+    };
+
+    match &**assign {
+        ir::VarAssign {lhs: ir::LValueExpr::Var(name, _), expr: ir::Expr::Call(call, _)} if *name == index => {
+            match (&call.func_name.components[..], &call.args[..]) {
+                (["int__add"], [ir::Expr::Var(lhs, _), ir::Expr::IntegerLiteral(1, _)]) => *lhs == index,
+                _ => false,
+            }
+        },
+        _ => false,
+    }
+}
+
+/// Searches for any assignment to or declaration of the given variables
+struct AssignFinder<'a> {
+    vars: &'a [&'a str],
+    found: bool,
+}
+
+impl<'a, 'f> Visitor<'f> for AssignFinder<'a> {
+    fn visit_stmt(&mut self, stmt: &ir::Stmt<'f>, _line: Option<usize>) {
+        if let ir::Stmt::VarDecl(ir::VarDecl {ident, ty: _, expr: _}) = stmt {
+            // A variable with the same name shadows the one being tracked
+            if self.vars.contains(ident) {
+                self.found = true;
+            }
+        }
+
+        visit::walk_stmt(self, stmt);
+    }
+
+    fn visit_lvalue(&mut self, lhs: &ir::LValueExpr<'f>) {
+        if let ir::LValueExpr::Var(name, _) = lhs {
+            if self.vars.contains(name) {
+                self.found = true;
+            }
+        }
+
+        visit::walk_lvalue(self, lhs);
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::codegen::CStrConstant;
+
+/// A table of the byte string literals used throughout the program
+///
+/// Each distinct sequence of bytes is generated once as a static constant and every literal with
+/// those bytes refers to that same constant.
+#[derive(Debug, Default)]
+pub struct BStrLiterals {
+    /// The name of the constant generated for each distinct sequence of bytes
+    names: HashMap<Vec<u8>, String>,
+    /// The constants in the order they were first used, so the generated code is deterministic
+    constants: Vec<CStrConstant>,
+}
+
+impl BStrLiterals {
+    /// Returns the name of the constant containing the given bytes, creating a new constant if
+    /// these bytes have not been used before
+    pub fn intern(&mut self, value: &[u8]) -> String {
+        if let Some(name) = self.names.get(value) {
+            return name.clone();
+        }
+
+        let mangled_name = format!("__dino__bstr_{}", self.constants.len());
+        self.names.insert(value.to_vec(), mangled_name.clone());
+        self.constants.push(CStrConstant {
+            mangled_name: mangled_name.clone(),
+            data: value.to_vec(),
+        });
+
+        mangled_name
+    }
+
+    /// Returns the constants for all of the literals in the table
+    pub fn into_constants(self) -> Vec<CStrConstant> {
+        self.constants
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_literals_are_shared() {
+        let mut literals = BStrLiterals::default();
+
+        let hello = literals.intern(b"hello");
+        let world = literals.intern(b"world");
+        assert_ne!(hello, world);
+        assert_eq!(literals.intern(b"hello"), hello);
+        // The empty string is a literal like any other
+        let empty = literals.intern(b"");
+        assert_eq!(literals.intern(b""), empty);
+
+        let constants = literals.into_constants();
+        let data: Vec<_> = constants.iter().map(|constant| &constant.data[..]).collect();
+        assert_eq!(data, vec![&b"hello"[..], &b"world"[..], &b""[..]]);
+    }
+}
@@ -1,9 +1,23 @@
 use crate::ir;
+use crate::ast2;
 use crate::resolve2::{TyId, DeclMap};
+use crate::primitives2::Primitives;
 use crate::codegen::*;
-
-use super::Error;
-use super::mangler::NameMangler;
+use crate::runtime::ALLOCATE;
+
+use super::{
+    CodegenOptions,
+    Error,
+    struct_copy_func_name,
+    static_var_name,
+    debug_print_func_name,
+    type_mangled_name,
+    field_mangled_name,
+    type_field_mangled_name,
+};
+use super::mangler::{NameMangler, mangle_path};
+use super::bstr_literals::BStrLiterals;
+use super::bounds_checks::{self, InBoundsIndex};
 
 /// Choices for what to do with the result of a block
 #[derive(Debug, Clone)]
@@ -18,37 +32,135 @@ enum BlockBehaviour {
     },
 }
 
+/// The function being generated, used to turn calls to itself in tail position into loops
+#[derive(Debug, Clone)]
+struct TailCallTarget {
+    /// The mangled name of the function
+    mangled_name: String,
+    /// The mangled names of the parameters of the function, in order
+    param_mangled_names: Vec<String>,
+}
+
 pub struct FunctionCodeGenerator<'a> {
     mod_scope: &'a DeclMap<'a>,
+    prims: &'a Primitives,
+    bstr_literals: &'a mut BStrLiterals,
     mangler: NameMangler,
+    /// The file named in the `#line` directive generated before each statement, or None if no
+    /// directives should be generated
+    line_directive_path: Option<String>,
+    /// True if bounds checks should be elided when an index is known to be in bounds
+    elide_bounds_checks: bool,
+    /// The list indexes known to be in bounds in the code currently being generated
+    in_bounds_indexes: Vec<InBoundsIndex>,
+    /// The function whose calls to itself in tail position are turned into loops, or None if no
+    /// function is being generated
+    tail_call_target: Option<TailCallTarget>,
+    /// True if a call to the function in tail position has been turned into a jump back to the
+    /// start of the function
+    has_self_tail_call: bool,
+    /// The number of loops in the original program that enclose the code being generated
+    loop_depth: usize,
 }
 
 impl<'a> FunctionCodeGenerator<'a> {
-    pub fn generate(func: &ir::Function, mod_scope: &'a DeclMap<'a>) -> Result<CFunction, Error> {
+    pub fn generate(
+        func: &ir::Function,
+        mod_scope: &'a DeclMap<'a>,
+        prims: &'a Primitives,
+        bstr_literals: &'a mut BStrLiterals,
+        options: &CodegenOptions,
+    ) -> Result<CFunction, Error> {
         // Each function body should have a single name mangler
         let mangler = NameMangler::new();
-        let mut generator = Self {mod_scope, mangler};
+        let line_directive_path = match func.source_path {
+            Some(path) if options.line_directives => Some(path.display().to_string()),
+            _ => None,
+        };
+        let mut generator = Self {
+            mod_scope,
+            prims,
+            bstr_literals,
+            mangler,
+            line_directive_path,
+            elide_bounds_checks: options.elide_bounds_checks,
+            in_bounds_indexes: Vec::new(),
+            tail_call_target: None,
+            has_self_tail_call: false,
+            loop_depth: 0,
+        };
         generator.gen_function(func)
     }
 
+    /// Generates the statements that assign the initial value of the given static
+    pub fn generate_static_init(
+        static_decl: &ir::Static,
+        mod_scope: &'a DeclMap<'a>,
+        prims: &'a Primitives,
+        bstr_literals: &'a mut BStrLiterals,
+    ) -> Result<Vec<CStmt>, Error> {
+        let ir::Static {name, ty: _, init} = static_decl;
+
+        let mangler = NameMangler::new();
+        let mut generator = Self {
+            mod_scope,
+            prims,
+            bstr_literals,
+            mangler,
+            line_directive_path: None,
+            elide_bounds_checks: false,
+            in_bounds_indexes: Vec::new(),
+            tail_call_target: None,
+            has_self_tail_call: false,
+            loop_depth: 0,
+        };
+
+        let mut stmts = Vec::new();
+        let init = generator.gen_expr(init, &mut stmts)?;
+        stmts.push(CStmt::VarAssign(CVarAssign {
+            lvalue: CLValue::Var {mangled_name: static_var_name(name)},
+            init_expr: CInitializerExpr::Expr(init),
+        }));
+
+        Ok(stmts)
+    }
+
     fn gen_function(&mut self, func: &ir::Function) -> Result<CFunction, Error> {
-        let ir::Function {name, sig, body} = func;
+        let ir::Function {name, sig, body, c_body, is_test: _, is_pure: _, source_path: _} = func;
 
         let ir::FuncSig {return_type, params} = sig;
-        // Add each parameter to the mangler so it can be used from within the function body
-        let cparams = params.iter().map(|ir::FuncParam {name, ty}| CFunctionParam {
-            mangled_name: self.mangler.mangle_name(name).to_string(),
+        let cparams: Vec<_> = params.iter().map(|ir::FuncParam {name, ty}| CFunctionParam {
+            mangled_name: match c_body {
+                // Raw C code refers to the parameters by the names they were declared with
+                Some(_) => name.to_string(),
+                // Add each parameter to the mangler so it can be used from within the function body
+                None => self.mangler.mangle_name(name).to_string(),
+            },
             ty: CTy::pointer(self.lookup_type_name(ty)),
         }).collect();
 
+        self.tail_call_target = Some(TailCallTarget {
+            mangled_name: name.to_string(),
+            param_mangled_names: cparams.iter().map(|param| param.mangled_name.clone()).collect(),
+        });
+
         let sig = CFunctionSignature {
-            //TODO: Mangle function names
+            // The name of the function is mangled before it gets here
             mangled_name: name.to_string(),
             return_type: CTy::pointer(self.lookup_type_name(return_type)),
             params: cparams,
         };
 
-        let body = self.gen_block(body, BlockBehaviour::Return)?;
+        let mut body = match c_body {
+            Some(code) => CStmts(vec![CStmt::Raw(code.to_string())]),
+            None => self.gen_block(body, BlockBehaviour::Return)?,
+        };
+
+        // Each call to the function in tail position continues this loop instead of growing the
+        // stack. The loop never exits on its own since every other path through the body returns.
+        if self.has_self_tail_call {
+            body = CStmts(vec![CStmt::Loop(CInfiniteLoop {body})]);
+        }
 
         Ok(CFunction {sig, body})
     }
@@ -58,24 +170,53 @@ impl<'a> FunctionCodeGenerator<'a> {
         block: &ir::Block,
         behaviour: BlockBehaviour,
     ) -> Result<CStmts, Error> {
-        let ir::Block {stmts, ret, ret_ty} = block;
+        // Variables declared in the block go out of scope at the end of the block, so any outer
+        // variables that they shadow must be restored afterwards
+        self.mangler.push_scope();
+        let cstmts = self.gen_block_contents(block, behaviour);
+        self.mangler.pop_scope();
+
+        cstmts
+    }
+
+    fn gen_block_contents(
+        &mut self,
+        block: &ir::Block,
+        behaviour: BlockBehaviour,
+    ) -> Result<CStmts, Error> {
+        let ir::Block {stmts, stmt_lines, ret, ret_ty} = block;
 
         let mut cstmts = Vec::new();
 
         // Statements must be traversed in order for our name mangling mechanism to work
-        for stmt in stmts {
+        for (i, stmt) in stmts.iter().enumerate() {
+            // The directive goes before any temporary variables generated for the statement
+            if let (Some(path), Some(&line)) = (&self.line_directive_path, stmt_lines.get(i)) {
+                cstmts.push(CStmt::Line {line, path: path.clone()});
+            }
+
             let gen_stmt = match stmt {
                 ir::Stmt::Cond(cond) => {
                     // Conditionals in statement position do not return a value from the block. They
                     // also do not cause a `return` unless there is an explicit return statement in
                     // one of the conditional branch bodies. Thus, the only remaining alternative is
-                    // to ignore the result of each block. These conditionals type check to unit
-                    // anyway, so we are just ignoring the unit value.
+                    // to ignore the result of each block. The branches may each produce a value of
+                    // a different type (e.g. `if c { 1 } else { b"one" }`) since the values are
+                    // always discarded.
 
                     CStmt::Cond(self.gen_cond_stmt(cond, &mut cstmts, BlockBehaviour::Ignore)?)
                 },
                 ir::Stmt::WhileLoop(wloop) => {
-                    CStmt::Loop(self.gen_while_loop(wloop, &mut cstmts)?)
+                    let prev_stmt = i.checked_sub(1).map(|prev| &stmts[prev]);
+                    CStmt::Loop(self.gen_while_loop(wloop, prev_stmt, &mut cstmts)?)
+                },
+                // Every loop is generated as a C loop, so these apply to the innermost loop just
+                // like they would in the original program
+                ir::Stmt::Break => CStmt::BreakLoop,
+                ir::Stmt::Continue => CStmt::ContinueLoop,
+                // The value of a nested block in statement position is always unit
+                ir::Stmt::Block(block) => {
+                    CStmt::Block(self.gen_block(block, BlockBehaviour::Ignore)?)
                 },
                 ir::Stmt::VarDecl(var_decl) => {
                     CStmt::VarDecl(self.gen_var_decl(var_decl, &mut cstmts)?)
@@ -87,6 +228,22 @@ impl<'a> FunctionCodeGenerator<'a> {
             cstmts.push(gen_stmt);
         }
 
+        // The `return` at the end of the block has already been generated, so there is no value
+        // to produce. This also avoids producing a unit value for a block that may not be of type
+        // unit. The same applies to a `break`, a `continue`, a call to a function that never
+        // returns, or a conditional where every branch returns.
+        if block.end_is_unreachable(self.prims.never()) {
+            return Ok(CStmts(cstmts));
+        }
+
+        // The final expression of the function body is in tail position, just like a `return`
+        if let (BlockBehaviour::Return, Some(ir::Expr::Call(call, _))) = (&behaviour, ret) {
+            if self.is_self_tail_call(call)? {
+                self.gen_self_tail_call(call, &mut cstmts)?;
+                return Ok(CStmts(cstmts));
+            }
+        }
+
         let ret_expr = ret.as_ref()
             .map(|ret| self.gen_expr(ret, &mut cstmts).map(|val| (ret.ty_id(), val)))
             .transpose()?;
@@ -122,8 +279,9 @@ impl<'a> FunctionCodeGenerator<'a> {
     ) -> Result<CCond, Error> {
         let ir::Cond {conds, else_body} = cond;
 
-        // Note that there is no need to generate a new mangler for each block because C does not
-        // support variable shadowing. A single scope is sufficient.
+        // Note that there is no need to generate a new mangler for each block because every
+        // mangled name is unique within the function. Each block only pushes a new scope onto the
+        // mangler so that names declared within it stop shadowing outer names once it ends.
 
         // This loop takes any else-if expressions and nests them in else clauses:
         //
@@ -180,6 +338,7 @@ impl<'a> FunctionCodeGenerator<'a> {
                 // to generate an empty else block and generate code for that.
                 let empty_block = ir::Block {
                     stmts: Vec::new(),
+                    stmt_lines: Vec::new(),
                     ret: None,
                     // Take the return type from the if-block
                     ret_ty: body.ret_ty,
@@ -211,7 +370,22 @@ impl<'a> FunctionCodeGenerator<'a> {
     fn gen_while_loop(
         &mut self,
         wloop: &ir::WhileLoop,
+        prev_stmt: Option<&ir::Stmt>,
         _prev_stmts: &mut Vec<CStmt>,
+    ) -> Result<CInfiniteLoop, Error> {
+        // A `continue` generated for a tail call would apply to this loop instead of the loop
+        // around the function body
+        self.loop_depth += 1;
+        let cloop = self.gen_while_loop_contents(wloop, prev_stmt);
+        self.loop_depth -= 1;
+
+        cloop
+    }
+
+    fn gen_while_loop_contents(
+        &mut self,
+        wloop: &ir::WhileLoop,
+        prev_stmt: Option<&ir::Stmt>,
     ) -> Result<CInfiniteLoop, Error> {
         let ir::WhileLoop {cond, body} = wloop;
 
@@ -229,9 +403,23 @@ impl<'a> FunctionCodeGenerator<'a> {
             else_body: Some(CStmts(vec![CStmt::BreakLoop])),
         }));
 
+        let in_bounds = match self.elide_bounds_checks {
+            true => bounds_checks::in_bounds_index(prev_stmt, wloop),
+            false => None,
+        };
+        let has_in_bounds = in_bounds.is_some();
+        if let Some(in_bounds) = in_bounds {
+            self.in_bounds_indexes.push(in_bounds);
+        }
+
         // Ignore the result of the body because it is currently guaranteed to be unit. We don't
         // support returning values from loops yet.
-        let while_body = self.gen_block(body, BlockBehaviour::Ignore)?;
+        let while_body = self.gen_block(body, BlockBehaviour::Ignore);
+
+        if has_in_bounds {
+            self.in_bounds_indexes.pop();
+        }
+        let while_body = while_body?;
 
         // The body of the loop is the conditional expression statements plus the while loop body
         let mut body = CStmts(cond_stmts);
@@ -263,7 +451,8 @@ impl<'a> FunctionCodeGenerator<'a> {
         Ok(match expr {
             ir::Expr::VarAssign(assign, ty) => self.gen_var_assign(assign, *ty, prev_stmts)?,
             ir::Expr::FieldAccess(access, ty) => {
-                CExpr::FieldAccess(Box::new(self.gen_field_access(access, *ty, prev_stmts)?))
+                let value = CExpr::FieldAccess(Box::new(self.gen_field_access(access, *ty, prev_stmts)?));
+                self.gen_copy(value, *ty)
             },
             ir::Expr::Cond(cond, ty) => self.gen_cond_expr(cond, ty, prev_stmts)?,
             ir::Expr::Call(call, _) => CExpr::Call(self.gen_call_expr(call, prev_stmts)?),
@@ -275,7 +464,29 @@ impl<'a> FunctionCodeGenerator<'a> {
             &ir::Expr::ComplexLiteral(value, ty) => self.gen_complex_literal(value, ty)?,
             &ir::Expr::BoolLiteral(value, ty) => self.gen_bool_literal(value, ty)?,
             &ir::Expr::UnitLiteral(ty) => self.gen_unit_literal(ty)?,
+            &ir::Expr::Var(name, ty) => {
+                let value = CExpr::Var(self.mangler.get(name).to_string());
+                self.gen_copy(value, ty)
+            },
+            &ir::Expr::StaticVar(name, ty) => self.gen_copy(CExpr::Var(static_var_name(name)), ty),
+        })
+    }
+
+    /// Generates an expression that is only used to access one of its fields
+    ///
+    /// Unlike `gen_expr`, a variable or field is never copied since the field is accessed in place.
+    fn gen_place_expr(
+        &mut self,
+        expr: &ir::Expr,
+        prev_stmts: &mut Vec<CStmt>,
+    ) -> Result<CExpr, Error> {
+        Ok(match expr {
+            ir::Expr::FieldAccess(access, ty) => {
+                CExpr::FieldAccess(Box::new(self.gen_field_access(access, *ty, prev_stmts)?))
+            },
             &ir::Expr::Var(name, _) => CExpr::Var(self.mangler.get(name).to_string()),
+            &ir::Expr::StaticVar(name, _) => CExpr::Var(static_var_name(name)),
+            _ => self.gen_expr(expr, prev_stmts)?,
         })
     }
 
@@ -311,19 +522,13 @@ impl<'a> FunctionCodeGenerator<'a> {
         expr: &ir::CallExpr,
         prev_stmts: &mut Vec<CStmt>,
     ) -> Result<CCallExpr, Error> {
-        let ir::CallExpr {func_name, args} = expr;
+        let ir::CallExpr {func_name: _, args} = expr;
 
-        let func_name = match &func_name.components[..] {
-            [type_name, method] => format!("{}__{}", type_name, method),
-            [func_name] => func_name.to_string(),
-            [] => unreachable!(),
-            _ => unimplemented!(),
-        };
+        let func_name = self.callee_mangled_name(expr)?;
 
         //TODO: In order to preserve execution order, calls should be lifted into a temporary variable
         // and the expression returned from here should be a CExpr::Var(temp_var)
         Ok(CCallExpr {
-            //TODO: Mangle function names
             mangled_func_name: func_name,
             args: args.iter()
                 .map(|expr| self.gen_expr(expr, prev_stmts))
@@ -331,6 +536,104 @@ impl<'a> FunctionCodeGenerator<'a> {
         })
     }
 
+    /// Returns the name of the C function called by the given call expression
+    fn callee_mangled_name(&self, expr: &ir::CallExpr) -> Result<String, Error> {
+        let ir::CallExpr {func_name, args} = expr;
+
+        Ok(match &func_name.components[..] {
+            // Methods and module functions. Extern methods called by path (e.g. `int::add(1, 2)`)
+            // must keep their names, just like extern functions.
+            [type_name, method] => match self.mod_scope.type_id(type_name)
+                .and_then(|ty| self.mod_scope.method(ty, method)) {
+                Some(func) if func.is_extern => func.name.to_string(),
+                _ => mangle_path(&[*type_name, *method]),
+            },
+            // The built-in `debug_print` calls a different function depending on the type of its
+            // argument
+            [name] if *name == ast2::DEBUG_PRINT_FUNC_NAME &&
+                self.mod_scope.func_sig(&ast2::DEBUG_PRINT_FUNC_NAME).is_none() => {
+                let arg_ty = args[0].ty_id();
+                debug_print_func_name(arg_ty, self.mod_scope, self.prims).ok_or_else(|| {
+                    let ty_name = self.prims.name_of(arg_ty).map(str::to_string)
+                        .unwrap_or_else(|| self.lookup_type_name(&arg_ty));
+                    Error::UnsupportedDebugPrint {ty_name}
+                })?
+            },
+            // Indexing that is known to be in bounds skips the bounds check
+            [func_name] if *func_name == bounds_checks::CHECKED_GET && self.is_in_bounds(args) => {
+                bounds_checks::UNCHECKED_GET.to_string()
+            },
+            // Extern functions (including extern methods) must keep their names so that they can
+            // be linked with the runtime and the standard library
+            [func_name] => match self.mod_scope.func(func_name) {
+                Some(func) if !func.is_extern => mangle_path(&[*func_name]),
+                _ => func_name.to_string(),
+            },
+            [] => unreachable!(),
+            _ => unimplemented!(),
+        })
+    }
+
+    /// Returns true if the given call in tail position calls the function being generated and can
+    /// be replaced with a jump back to the start of the function
+    fn is_self_tail_call(&self, call: &ir::CallExpr) -> Result<bool, Error> {
+        let target = match &self.tail_call_target {
+            Some(target) if self.loop_depth == 0 => target,
+            _ => return Ok(false),
+        };
+
+        Ok(self.callee_mangled_name(call)? == target.mangled_name)
+    }
+
+    /// Generates a call to the function being generated as an assignment to each of its
+    /// parameters followed by a jump back to the start of the function
+    fn gen_self_tail_call(
+        &mut self,
+        call: &ir::CallExpr,
+        prev_stmts: &mut Vec<CStmt>,
+    ) -> Result<(), Error> {
+        let ir::CallExpr {func_name: _, args} = call;
+
+        // Every argument must be evaluated before any parameter is assigned since the arguments
+        // may refer to the parameters
+        let mut arg_vars = Vec::new();
+        for arg in args {
+            let value = self.gen_expr(arg, prev_stmts)?;
+            let mangled_name = self.mangler.fresh_mangled_name();
+            prev_stmts.push(CStmt::TempVarDecl(CTempVarDecl {
+                mangled_name: mangled_name.clone(),
+                ty: CTy::pointer(self.lookup_type_name(&arg.ty_id())),
+                init_expr: Some(CInitializerExpr::Expr(value)),
+            }));
+            arg_vars.push(mangled_name);
+        }
+
+        let target = self.tail_call_target.as_ref()
+            .expect("bug: tail call generated outside of a function");
+        for (param, arg_var) in target.param_mangled_names.iter().zip(arg_vars) {
+            prev_stmts.push(CStmt::VarAssign(CVarAssign {
+                lvalue: CLValue::Var {mangled_name: param.clone()},
+                init_expr: CInitializerExpr::Expr(CExpr::Var(arg_var)),
+            }));
+        }
+        prev_stmts.push(CStmt::ContinueLoop);
+
+        self.has_self_tail_call = true;
+        Ok(())
+    }
+
+    /// Returns true if the given arguments to `int_list_get` are a list and an index variable
+    /// that are known to be in bounds
+    fn is_in_bounds(&self, args: &[ir::Expr]) -> bool {
+        match args {
+            [ir::Expr::Var(list, _), ir::Expr::Var(index, _)] => {
+                self.in_bounds_indexes.iter()
+                    .any(|in_bounds| in_bounds.list == *list && in_bounds.index == *index)
+            },
+            _ => false,
+        }
+    }
+
     fn gen_var_assign(
         &mut self,
         assign: &ir::VarAssign,
@@ -348,6 +651,8 @@ impl<'a> FunctionCodeGenerator<'a> {
                 let mangled_name = self.mangler.get(ident).to_string();
                 CLValue::Var {mangled_name}
             },
+
+            ir::LValueExpr::StaticVar(ident, _) => CLValue::Var {mangled_name: static_var_name(ident)},
         };
 
         // C doesn't support assignment in expression position, so the assignment must be lifted
@@ -370,9 +675,8 @@ impl<'a> FunctionCodeGenerator<'a> {
     ) -> Result<CFieldAccess, Error> {
         let ir::FieldAccess {lhs, field} = access;
 
-        let lhs = self.gen_expr(lhs, prev_stmts)?;
-        //TODO: Mangle struct field names
-        let field_mangled_name = field.to_string();
+        let field_mangled_name = type_field_mangled_name(lhs.ty_id(), field, self.mod_scope);
+        let lhs = self.gen_place_expr(lhs, prev_stmts)?;
 
         Ok(CFieldAccess {lhs, field_mangled_name})
     }
@@ -383,6 +687,13 @@ impl<'a> FunctionCodeGenerator<'a> {
         ty: TyId,
         prev_stmts: &mut Vec<CStmt>,
     ) -> Result<CExpr, Error> {
+        if let Some(ir::Expr::Call(call, _)) = ret_expr {
+            if self.is_self_tail_call(call)? {
+                self.gen_self_tail_call(call, prev_stmts)?;
+                return self.gen_unit_literal(ty);
+            }
+        }
+
         // C doesn't support return in expression position, so the return must be lifted into
         // a statement
         let assign = CStmt::Return(match ret_expr {
@@ -404,28 +715,87 @@ impl<'a> FunctionCodeGenerator<'a> {
     ) -> Result<CExpr, Error> {
         let ir::StructLiteral {ty_id, field_values} = struct_lit;
 
+        let struct_name = *self.mod_scope.type_name(ty);
         let struct_mangled_name = self.lookup_type_name(&ty);
         // The struct literal must be generated in a separate temporary variable because we need to
         // initialize each field
         let struct_var_mangled_name = self.mangler.fresh_mangled_name();
         prev_stmts.push(CStmt::TempVarDecl(CTempVarDecl {
             mangled_name: struct_var_mangled_name.clone(),
-            ty: CTy::Named {mangled_name: struct_mangled_name.clone()},
-            init_expr: None,
+            ty: CTy::pointer(struct_mangled_name.clone()),
+            init_expr: Some(CInitializerExpr::Expr(CExpr::Call(CCallExpr {
+                mangled_func_name: ALLOCATE.to_string(),
+                args: vec![CExpr::Sizeof(CTy::Named {mangled_name: struct_mangled_name.clone()})],
+            }))),
         }));
 
-        Ok(CExpr::Call(CCallExpr {
-            //TODO: Figure out a better way to get this name without hard-coding it
-            mangled_func_name: format!("init_{}", struct_mangled_name),
-            args: vec![CExpr::Var(struct_var_mangled_name)]
-        }))
+        // Fields are initialized in the order they were declared so that the generated code does
+        // not depend on the order of the map
+        let mod_scope = self.mod_scope;
+        for (field_name, _) in mod_scope.fields(*ty_id) {
+            // Any missing fields were already filled in with their default values
+            let value = field_values.get(field_name)
+                .expect("bug: struct literals should initialize every field");
+            let value = self.gen_expr(value, prev_stmts)?;
+
+            prev_stmts.push(CStmt::VarAssign(CVarAssign {
+                lvalue: CLValue::FieldAccess(CFieldAccess {
+                    lhs: CExpr::Var(struct_var_mangled_name.clone()),
+                    field_mangled_name: type_field_mangled_name(*ty_id, field_name, mod_scope),
+                }),
+                init_expr: CInitializerExpr::Expr(value),
+            }));
+        }
+
+        // The invariant (if any) must hold for every value of the struct that is constructed
+        if let Some(invariant) = mod_scope.method_sig(*ty_id, &ast2::INVARIANT_METHOD_NAME) {
+            // The invariant takes the value of each field in the order the fields were declared
+            let args = invariant.params.iter().map(|param| {
+                CExpr::FieldAccess(Box::new(CFieldAccess {
+                    lhs: CExpr::Var(struct_var_mangled_name.clone()),
+                    field_mangled_name: field_mangled_name(param.name),
+                }))
+            }).collect();
+
+            let check = CExpr::Call(CCallExpr {
+                mangled_func_name: mangle_path(&[struct_name, ast2::INVARIANT_METHOD_NAME]),
+                args,
+            });
+            prev_stmts.push(CStmt::Cond(CCond {
+                cond_expr: self.gen_bool_coercion(check, self.prims.bool()),
+                if_body: CStmts::default(),
+                else_body: Some(CStmts(vec![CStmt::Expr(CExpr::Call(CCallExpr {
+                    mangled_func_name: "__dino__invariant_violated".to_string(),
+                    args: vec![CExpr::NTStrLiteral(struct_name.as_bytes().to_vec())],
+                }))])),
+            }));
+        }
+
+        Ok(CExpr::Var(struct_var_mangled_name))
+    }
+
+    /// Produces a copy of the given value if it is a struct so that the value never aliases the
+    /// place it was read from
+    fn gen_copy(&self, value: CExpr, ty: TyId) -> CExpr {
+        // Every other type is never modified in place, so those values can be shared freely
+        if self.mod_scope.type_is_extern(ty) {
+            return value;
+        }
+
+        CExpr::Call(CCallExpr {
+            mangled_func_name: struct_copy_func_name(&self.lookup_type_name(&ty)),
+            args: vec![value],
+        })
     }
 
     fn gen_bstr_literal(
-        &self,
+        &mut self,
         value: &[u8],
         ty: TyId,
     ) -> Result<CExpr, Error> {
+        // Identical literals share the same static data
+        let data_name = self.bstr_literals.intern(value);
+
         let lit_constructors = self.mod_scope.type_lit_constructors(ty);
         Ok(CExpr::Call(CCallExpr {
             //TODO: Mangle function names
@@ -434,7 +804,7 @@ impl<'a> FunctionCodeGenerator<'a> {
                 .expect("bug: no byte string literal constructor defined for type that type checked to bstr")
                 .to_string(),
             args: vec![
-                CExpr::NTStrLiteral(value.to_vec()),
+                CExpr::Var(data_name),
                 CExpr::IntegerLiteral(value.len() as i64),
             ],
         }))
@@ -541,8 +911,6 @@ impl<'a> FunctionCodeGenerator<'a> {
         &self,
         &ty: &TyId,
     ) -> String {
-        let name = *self.mod_scope.type_name(ty);
-        //TODO: Mangle generated struct names
-        name.to_string()
+        type_mangled_name(ty, self.mod_scope)
     }
 }
@@ -2,11 +2,36 @@ use std::collections::HashMap;
 
 use rand::{Rng, SeedableRng, rngs::SmallRng};
 
-/// Represents a single level of local scope and maps the names of variables to their mangled
-/// equivalent
+/// The prefix of the C symbol generated for every item declared in the program
+const ITEM_PREFIX: &str = "dino_";
+
+/// Returns the C symbol for the item with the given path (e.g. `["Point", "new"]`)
+///
+/// Each component of the path is written after its length, so `a::b_c` and `a_b::c` become
+/// `dino_1a3b_c` and `dino_3a_b1c`. Different paths always produce different symbols and the
+/// original path can always be recovered from the symbol. The prefix ensures that the symbol is
+/// never a C keyword and never collides with a symbol from the runtime or the standard library.
+///
+/// A component may itself be a path (e.g. the qualified name of an item declared in an imported
+/// module), in which case each of its parts is written as a separate component.
+pub fn mangle_path(path: &[&str]) -> String {
+    let mut mangled_name = ITEM_PREFIX.to_string();
+    for component in path.iter().flat_map(|component| component.split("::")) {
+        mangled_name.push_str(&component.len().to_string());
+        mangled_name.push_str(component);
+    }
+
+    mangled_name
+}
+
+/// Maps the names of variables to their mangled equivalent
+///
+/// Keeps a stack of scopes so that a name declared in a nested block only shadows the name in
+/// outer blocks until the nested block ends.
 pub struct NameMangler {
     rng: SmallRng,
-    mangled_names: HashMap<String, String>,
+    /// The mangled names declared in each enclosing scope, innermost scope last
+    scopes: Vec<HashMap<String, String>>,
     next_fresh_name: u64,
 }
 
@@ -15,12 +40,24 @@ impl NameMangler {
         Self {
             // Want names to be deterministic across builds
             rng: SmallRng::seed_from_u64(2194920),
-            mangled_names: HashMap::new(),
+            scopes: vec![HashMap::new()],
             next_fresh_name: 0,
         }
     }
 
-    /// Mangles the given name, overwriting any mangled name previously stored for the same name
+    /// Starts a new innermost scope
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// Ends the innermost scope, restoring any names that it shadowed
+    pub fn pop_scope(&mut self) {
+        assert!(self.scopes.len() > 1, "bug: attempt to pop the outermost scope of the name mangler");
+        self.scopes.pop();
+    }
+
+    /// Mangles the given name, overwriting any mangled name previously stored for the same name in
+    /// the innermost scope
     pub fn mangle_name(&mut self, name: &str) -> &str {
         // Append some random bytes to the end of the name to differentiate this name from any
         // other shadowed variables with the same name
@@ -34,7 +71,8 @@ impl NameMangler {
             mangled_name.push(self.rng.gen_range(b'a', b'z') as char);
         }
 
-        self.mangled_names.insert(name.to_string(), mangled_name);
+        self.scopes.last_mut().expect("bug: name mangler has no scopes")
+            .insert(name.to_string(), mangled_name);
         self.get(name)
     }
 
@@ -51,8 +89,31 @@ impl NameMangler {
         mangled_name
     }
 
-    /// Returns the mangled name of the given name or panics
+    /// Returns the mangled name of the innermost declaration of the given name or panics
     pub fn get(&self, name: &str) -> &str {
-        self.mangled_names.get(name).expect("bug: unresolved name was allowed to get to codegen")
+        self.scopes.iter().rev()
+            .find_map(|scope| scope.get(name))
+            .expect("bug: unresolved name was allowed to get to codegen")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mangled_paths_are_unique() {
+        assert_eq!(mangle_path(&["while"]), "dino_5while");
+        assert_eq!(mangle_path(&["Point", "new"]), "dino_5Point3new");
+        assert_ne!(mangle_path(&["a", "b_c"]), mangle_path(&["a_b", "c"]));
+        assert_ne!(mangle_path(&["a__b"]), mangle_path(&["a", "b"]));
+    }
+
+    #[test]
+    fn qualified_names_are_split() {
+        assert_eq!(mangle_path(&["package::shapes::Square", "new"]),
+            "dino_7package6shapes6Square3new");
+        assert_eq!(mangle_path(&["package::shapes::Square"]),
+            mangle_path(&["package", "shapes", "Square"]));
     }
 }
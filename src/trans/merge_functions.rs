@@ -0,0 +1,245 @@
+use std::fmt::Write;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use crate::codegen::*;
+
+/// Removes every function whose generated code is identical to the code of a function that came
+/// before it, redirecting all calls to the removed function to the one that was kept
+///
+/// Two functions are only considered identical if they have the same return type, parameters, and
+/// body. Merging functions can make other functions identical (e.g. two functions that only
+/// differed by which of two identical functions they called), so merging continues until no more
+/// functions can be merged. A recursive function refers to itself by name, so it is never merged.
+///
+/// Calls in raw C code cannot be redirected, so a function is never removed if its name appears
+/// anywhere in raw C code.
+///
+/// The symbols of the removed functions are updated to refer to the functions replacing them.
+pub fn merge_identical_functions(
+    functions: &mut Vec<CFunction>,
+    entry_point: &mut CEntryPoint,
+    symbols: &mut CSymbolMap,
+) {
+    let raw_bodies: Vec<_> = functions.iter().flat_map(|func| raw_code(&func.body)).collect();
+    let used_by_raw_code = |name: &str| raw_bodies.iter().any(|code| code.contains(name));
+
+    loop {
+        // The name of the first function found with each body
+        let mut kept: HashMap<String, String> = HashMap::new();
+        // The name of each removed function mapped to the name of the function replacing it
+        let mut replaced = HashMap::new();
+
+        functions.retain(|func| match kept.entry(function_key(func)) {
+            Entry::Vacant(entry) => {
+                entry.insert(func.sig.mangled_name.clone());
+                true
+            },
+            Entry::Occupied(_) if used_by_raw_code(&func.sig.mangled_name) => true,
+            Entry::Occupied(entry) => {
+                replaced.insert(func.sig.mangled_name.clone(), entry.get().clone());
+                false
+            },
+        });
+
+        if replaced.is_empty() {
+            break;
+        }
+
+        for func in functions.iter_mut() {
+            rename_calls_stmts(&mut func.body, &replaced);
+        }
+        rename_calls_stmts(&mut entry_point.body, &replaced);
+
+        for sym in &mut symbols.symbols {
+            if let Some(new_name) = replaced.get(&sym.mangled_name) {
+                sym.mangled_name = new_name.clone();
+            }
+        }
+    }
+}
+
+/// Returns a string that is the same for two functions if and only if they only differ by name
+fn function_key(func: &CFunction) -> String {
+    let CFunction {sig, body} = func;
+    let CFunctionSignature {mangled_name: _, return_type, params} = sig;
+
+    let mut key = format!("{} (", return_type);
+    for CFunctionParam {mangled_name, ty} in params {
+        write!(key, "{} {}, ", ty, mangled_name).unwrap();
+    }
+    write!(key, ") {{\n{}}}", body).unwrap();
+
+    key
+}
+
+/// Returns all of the raw C code in the given statements
+fn raw_code(stmts: &CStmts) -> Vec<String> {
+    let CStmts(stmts) = stmts;
+    stmts.iter().filter_map(|stmt| match stmt {
+        CStmt::Raw(code) => Some(code.clone()),
+        _ => None,
+    }).collect()
+}
+
+fn rename_calls_stmts(stmts: &mut CStmts, replaced: &HashMap<String, String>) {
+    let CStmts(stmts) = stmts;
+    for stmt in stmts {
+        rename_calls_stmt(stmt, replaced);
+    }
+}
+
+fn rename_calls_stmt(stmt: &mut CStmt, replaced: &HashMap<String, String>) {
+    match stmt {
+        CStmt::Cond(cond) => rename_calls_cond(cond, replaced),
+        CStmt::Loop(CInfiniteLoop {body}) => rename_calls_stmts(body, replaced),
+        CStmt::Block(stmts) => rename_calls_stmts(stmts, replaced),
+        CStmt::BreakLoop |
+        CStmt::ContinueLoop => {},
+        // Functions called from raw C code are never removed, so there is nothing to rename
+        CStmt::Raw(_) |
+        CStmt::Line {..} => {},
+        CStmt::VarAssign(CVarAssign {lvalue, init_expr}) => {
+            match lvalue {
+                CLValue::FieldAccess(access) => rename_calls_expr(&mut access.lhs, replaced),
+                CLValue::Var {mangled_name: _} => {},
+            }
+            rename_calls_init_expr(init_expr, replaced);
+        },
+        CStmt::VarDecl(CVarDecl {init_expr, ..}) => rename_calls_init_expr(init_expr, replaced),
+        CStmt::TempVarDecl(CTempVarDecl {init_expr, ..}) => if let Some(init_expr) = init_expr {
+            rename_calls_init_expr(init_expr, replaced);
+        },
+        CStmt::Expr(expr) |
+        CStmt::Return(expr) => rename_calls_expr(expr, replaced),
+    }
+}
+
+fn rename_calls_cond(cond: &mut CCond, replaced: &HashMap<String, String>) {
+    let CCond {cond_expr, if_body, else_body} = cond;
+
+    rename_calls_expr(cond_expr, replaced);
+    rename_calls_stmts(if_body, replaced);
+    if let Some(else_body) = else_body {
+        rename_calls_stmts(else_body, replaced);
+    }
+}
+
+fn rename_calls_init_expr(init_expr: &mut CInitializerExpr, replaced: &HashMap<String, String>) {
+    match init_expr {
+        CInitializerExpr::Expr(expr) => rename_calls_expr(expr, replaced),
+    }
+}
+
+fn rename_calls_expr(expr: &mut CExpr, replaced: &HashMap<String, String>) {
+    match expr {
+        CExpr::Call(CCallExpr {mangled_func_name, args}) => {
+            if let Some(new_name) = replaced.get(mangled_func_name) {
+                *mangled_func_name = new_name.clone();
+            }
+
+            for arg in args {
+                rename_calls_expr(arg, replaced);
+            }
+        },
+        CExpr::FieldAccess(access) => rename_calls_expr(&mut access.lhs, replaced),
+        CExpr::NTStrLiteral(_) |
+        CExpr::IntegerLiteral(_) |
+        CExpr::DoubleLiteral(_) |
+        CExpr::BoolLiteral(_) |
+        CExpr::Var(_) |
+        CExpr::Sizeof(_) => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: Vec<CExpr>) -> CExpr {
+        CExpr::Call(CCallExpr {mangled_func_name: name.to_string(), args})
+    }
+
+    /// Creates a function that takes an int and returns the result of calling `callee` with it
+    fn func(name: &str, callee: &str) -> CFunction {
+        CFunction {
+            sig: CFunctionSignature {
+                mangled_name: name.to_string(),
+                return_type: CTy::pointer("DInt".to_string()),
+                params: vec![CFunctionParam {
+                    mangled_name: "x".to_string(),
+                    ty: CTy::pointer("DInt".to_string()),
+                }],
+            },
+            body: CStmts(vec![
+                CStmt::Return(call(callee, vec![CExpr::Var("x".to_string())])),
+            ]),
+        }
+    }
+
+    fn names(functions: &[CFunction]) -> Vec<&str> {
+        functions.iter().map(|func| &*func.sig.mangled_name).collect()
+    }
+
+    #[test]
+    fn merges_identical_functions() {
+        let mut functions = vec![
+            func("a", "int__neg"),
+            func("b", "int__neg"),
+            func("c", "print_int"),
+            // Only identical after `a` and `b` are merged
+            func("d", "a"),
+            func("e", "b"),
+            // Recursive functions refer to themselves, so they are never identical
+            func("f", "f"),
+            func("g", "g"),
+        ];
+        let mut entry_point = CEntryPoint {
+            body: CStmts(vec![
+                CStmt::Expr(call("e", vec![CExpr::IntegerLiteral(1)])),
+                CStmt::Expr(call("b", vec![CExpr::IntegerLiteral(2)])),
+            ]),
+        };
+
+        let mut symbols = CSymbolMap::default();
+        for name in &["a", "b", "e"] {
+            symbols.insert(CSymbolKind::Function, name.to_string(), name.to_string());
+        }
+
+        merge_identical_functions(&mut functions, &mut entry_point, &mut symbols);
+
+        assert_eq!(names(&functions), vec!["a", "c", "d", "f", "g"]);
+        // `e` was merged into `d` after `b` was merged into `a`
+        assert_eq!(symbols.mangled_name("a"), Some("a"));
+        assert_eq!(symbols.mangled_name("b"), Some("a"));
+        assert_eq!(symbols.mangled_name("e"), Some("d"));
+        let entry_calls: Vec<_> = entry_point.body.into_iter().map(|stmt| match stmt {
+            CStmt::Expr(CExpr::Call(call)) => call.mangled_func_name,
+            _ => unreachable!(),
+        }).collect();
+        assert_eq!(entry_calls, vec!["d", "a"]);
+    }
+
+    #[test]
+    fn keeps_functions_used_by_raw_code() {
+        let mut functions = vec![
+            func("a", "int__neg"),
+            func("b", "int__neg"),
+            func("c", "int__neg"),
+            CFunction {
+                sig: CFunctionSignature {
+                    mangled_name: "raw".to_string(),
+                    return_type: CTy::pointer("DInt".to_string()),
+                    params: Vec::new(),
+                },
+                body: CStmts(vec![CStmt::Raw("return b(NULL);".to_string())]),
+            },
+        ];
+        let mut entry_point = CEntryPoint {body: CStmts(Vec::new())};
+
+        merge_identical_functions(&mut functions, &mut entry_point, &mut CSymbolMap::default());
+
+        // `b` is called from the raw code, so it cannot be replaced with `a`
+        assert_eq!(names(&functions), vec!["a", "b", "raw"]);
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use crate::ast2::INVARIANT_METHOD_NAME;
+use crate::analysis::CallGraph;
+use crate::ir::{self, IdentPath};
+
+use super::mangler::mangle_path;
+
+/// Returns a copy of the given program without any of the functions or methods that can never be
+/// called, starting from the given root functions
+///
+/// Nothing is generated for the removed functions, so none of the runtime or standard library
+/// functions that only they call are linked into the final executable.
+///
+/// The invariant of a struct is checked whenever a value of the struct is created, so every
+/// invariant is also treated as a root. Calls in raw C code cannot be analyzed, so any function
+/// whose generated name appears anywhere in raw C code is kept as well.
+pub fn remove_unreachable_functions<'a>(
+    prog: &ir::Program<'a>,
+    roots: impl Iterator<Item = IdentPath<'a>>,
+) -> ir::Program<'a> {
+    let ir::Program {top_level_module} = prog;
+    let ir::Module {types, functions, module_functions, statics} = top_level_module;
+
+    let raw_bodies: Vec<_> = functions.iter()
+        .chain(types.iter().flat_map(|ty| ty.methods.values()))
+        .chain(module_functions.iter().map(|(_, func)| func))
+        .filter_map(|func| func.c_body)
+        .collect();
+    let used_by_raw_code = |path: &IdentPath| {
+        let mangled_name = mangle_path(&path.components);
+        raw_bodies.iter().any(|code| code.contains(&mangled_name))
+    };
+
+    let graph = CallGraph::new(prog);
+    let invariants = types.iter()
+        .filter(|ty| ty.methods.contains_key(&INVARIANT_METHOD_NAME))
+        .map(|ty| IdentPath::from(vec![ty.name, INVARIANT_METHOD_NAME]));
+    let raw_code_callees = graph.functions().filter(|&func| used_by_raw_code(func)).cloned();
+
+    let mut reachable = HashSet::new();
+    for root in roots.chain(invariants).chain(raw_code_callees) {
+        reachable.extend(graph.reachable_from(&root));
+    }
+
+    let types = types.iter().map(|ty| {
+        let mut ty = ty.clone();
+        let type_name = ty.name;
+        ty.methods.retain(|&method_name, _| {
+            reachable.contains(&IdentPath::from(vec![type_name, method_name]))
+        });
+        ty
+    }).collect();
+    let functions = functions.iter()
+        .filter(|func| reachable.contains(&IdentPath::from(func.name)))
+        .cloned()
+        .collect();
+    let module_functions = module_functions.iter()
+        .filter(|(module_name, func)| {
+            reachable.contains(&IdentPath::from(vec![*module_name, func.name]))
+        })
+        .cloned()
+        .collect();
+
+    ir::Program {
+        top_level_module: ir::Module {types, functions, module_functions, statics: statics.clone()},
+    }
+}
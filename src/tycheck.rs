@@ -5,7 +5,14 @@ mod scope;
 mod constraints;
 mod solve;
 mod tyir;
+mod suggest;
+mod reachability;
+mod unused_vars;
+mod failing_asserts;
+mod purity;
+mod unreachable_else;
 
+use std::fmt;
 use std::collections::HashMap;
 
 use snafu::Snafu;
@@ -13,7 +20,7 @@ use rayon::prelude::*;
 use parking_lot::RwLock;
 
 use crate::{ast2, ir};
-use crate::resolve2::{ModuleDecls, ProgramDecls, DeclMap, TyId};
+use crate::resolve2::{ModuleDecls, ModuleScope, ProgramDecls, DeclMap, TyId};
 use crate::primitives2::Primitives;
 
 use constraints::ConstraintSet;
@@ -29,19 +36,31 @@ pub enum Error {
     UnresolvedType {
         name: String,
     },
-    #[snafu(display("cannot find function '{}' in this scope", name))]
+    #[snafu(display("cannot find function '{}' in this scope{}", name, did_you_mean(suggestion)))]
     UnresolvedFunction {
         name: String,
+        /// A similarly named function that may have been intended
+        suggestion: Option<String>,
     },
-    #[snafu(display("no field named '{}' for type 'TODO'", field_name))]
+    #[snafu(display("no field '{}' on {}", field_name, ty_name))]
     UnresolvedField {
         field_name: String,
-        ty: TyId,
+        ty_name: String,
+    },
+    #[snafu(display("no associated constant '{}' on {}", const_name, ty_name))]
+    UnresolvedAssocConst {
+        const_name: String,
+        ty_name: String,
     },
-    #[snafu(display("no method named '{}' in the current scope for type 'TODO'", method_name))]
+    #[snafu(display("no method '{}' on {}{}{}", method_name, ty_name, did_you_mean(suggestion), available_methods(suggestion, available)))]
     UnresolvedMethod {
+        //TODO: Add span info for the method call
         method_name: String,
-        ty: TyId,
+        ty_name: String,
+        /// A similarly named method that may have been intended
+        suggestion: Option<String>,
+        /// The names of all the methods that can be called on the type, in alphabetical order
+        available: Vec<String>,
     },
     #[snafu(display("function '{}' takes {} parameter(s) but {} parameter(s) were supplied", func_name, expected, actual))]
     ArityMismatch {
@@ -49,6 +68,26 @@ pub enum Error {
         expected: usize,
         actual: usize,
     },
+    #[snafu(display("function '{}' has no parameter named '{}'", func_name, name))]
+    UnknownNamedArg {
+        func_name: String,
+        name: String,
+    },
+    #[snafu(display("argument for parameter '{}' of function '{}' was specified more than once", param, func_name))]
+    DuplicateArg {
+        func_name: String,
+        param: String,
+    },
+    #[snafu(display("argument for parameter '{}' of function '{}' would be evaluated before an argument written before it, but one of them may have side effects; pass the arguments in the order of the parameters or move the side effects into variables", param, func_name))]
+    ReorderedSideEffects {
+        func_name: String,
+        param: String,
+    },
+    #[snafu(display("missing argument for parameter '{}' of function '{}'", param, func_name))]
+    MissingArg {
+        func_name: String,
+        param: String,
+    },
     #[snafu(display("cannot infer type, type annotations needed"))]
     AmbiguousType {
         //TODO: Add span info
@@ -71,6 +110,29 @@ pub enum Error {
         expected: TyId,
         actual: TyId,
     },
+    #[snafu(display("branch {} of the conditional has type {}, but the first branch has type {}{}",
+        branch_index + 1, branch_ty, if_ty, on_line(line)))]
+    BranchTypeMismatch {
+        /// The name of the type of the first branch of the conditional
+        if_ty: String,
+        /// The name of the type of the branch that did not match the first branch
+        branch_ty: String,
+        /// The index of the branch that did not match, starting at 0 for the first branch (the
+        /// `else` branch is always last)
+        branch_index: usize,
+        /// The line (starting at 1) of the value produced by the branch that did not match (if
+        /// known)
+        line: Option<usize>,
+    },
+    #[snafu(display("`if` without an `else` used as a value of type {}{}; add an `else` that produces a value of the same type", if_ty, on_line(line)))]
+    MissingElse {
+        /// The name of the type of the branch that produced a value
+        if_ty: String,
+        /// The index of the branch that produced a value, starting at 0 for the first branch
+        branch_index: usize,
+        /// The line (starting at 1) of the value produced by the branch (if known)
+        line: Option<usize>,
+    },
     #[snafu(display("invalid type for integer literal"))]
     InvalidIntLitType {
         actual: TyId,
@@ -79,40 +141,267 @@ pub enum Error {
     InvalidRealLitType {
         actual: TyId,
     },
+    #[snafu(display("`{}` outside of a loop", keyword))]
+    BreakOutsideLoop {
+        //TODO: Add span info
+        /// Either `break` or `continue`
+        keyword: &'static str,
+    },
+    #[snafu(display("division by zero{}", on_line(line)))]
+    DivisionByZero {
+        /// The line of the statement containing the division (if known)
+        line: Option<usize>,
+    },
+    #[snafu(display("not every path through the body of `{}` returns a value{}", func_name,
+        on_line(line)))]
+    MissingReturn {
+        /// The name of the function with the missing return
+        func_name: String,
+        /// The line of the last statement or expression in the body of the function (if any)
+        line: Option<usize>,
+    },
+    #[snafu(display("unreachable code after a `return`{}", on_line(line)))]
+    UnreachableCode {
+        /// The line of the first statement that can never run (if known)
+        line: Option<usize>,
+    },
     #[snafu(display("field `{}` specified more than once", duplicate))]
     DuplicateField {
         /// The name of the repeated field
         duplicate: String,
     },
+    #[snafu(display("missing field `{}` in initializer of `{}`", field, type_name))]
+    MissingField {
+        /// The type of the struct literal
+        type_name: String,
+        /// The name of the field that was not initialized
+        field: String,
+    },
+    #[snafu(display("cannot call `{}` from `{}` because it is not marked `#[pure]`", callee, func_name))]
+    ImpureCall {
+        //TODO: Add span info for the call
+        /// The name of the pure function containing the call
+        func_name: String,
+        /// The name of the function that may have side effects
+        callee: String,
+    },
+    #[snafu(display("cannot assign to static variable `{}` from pure function `{}`", name, func_name))]
+    ImpureStaticAssign {
+        //TODO: Add span info for the assignment
+        /// The name of the pure function containing the assignment
+        func_name: String,
+        /// The name of the static variable
+        name: String,
+    },
+}
+
+/// Formats the given line (if any) so that it can be added to the end of an error message
+fn on_line(line: &Option<usize>) -> String {
+    match line {
+        Some(line) => format!(" on line {}", line),
+        None => String::new(),
+    }
 }
 
+/// Problems that do not prevent the program from compiling but are likely to be mistakes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    UnusedVariable {
+        /// The name of the variable that is never read
+        name: String,
+        /// The name of the function or method containing the variable
+        func_name: String,
+    },
+    AssertionAlwaysFails {
+        /// The name of the function or method containing the assertion
+        func_name: String,
+        /// The line (starting at 1) of the statement containing the assertion (if known)
+        line: Option<usize>,
+    },
+    UnusedPureCall {
+        /// The name of the pure function whose result is discarded
+        callee: String,
+        /// The name of the function or method containing the call
+        func_name: String,
+    },
+    RawCBody {
+        /// The name of the function or method whose body is written in raw C code
+        func_name: String,
+    },
+    UnreachableElse {
+        /// The name of the function or method containing the conditional
+        func_name: String,
+        /// The line (starting at 1) of the statement containing the conditional (if known)
+        line: Option<usize>,
+    },
+}
+
+impl Warning {
+    /// Returns the name of the function or method that the warning was found in
+    pub fn func_name(&self) -> &str {
+        match self {
+            Warning::UnusedVariable {func_name, ..} |
+            Warning::AssertionAlwaysFails {func_name, ..} |
+            Warning::UnusedPureCall {func_name, ..} |
+            Warning::RawCBody {func_name} |
+            Warning::UnreachableElse {func_name, ..} => func_name,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::UnusedVariable {name, func_name} => write!(f, "unused variable `{}` in `{}`; \
+                if this is intentional, prefix it with an underscore: `_{}`", name, func_name, name),
+            Warning::AssertionAlwaysFails {func_name, line} => write!(f, "assertion always fails in \
+                `{}`{}", func_name, on_line(line)),
+            Warning::UnusedPureCall {callee, func_name} => write!(f, "unused result of pure \
+                function `{}` in `{}`; the call has no effect", callee, func_name),
+            Warning::RawCBody {func_name} => write!(f, "the body of `{}` is raw C code, so it is \
+                not checked by the compiler", func_name),
+            Warning::UnreachableElse {func_name, line} => write!(f, "else branch is unreachable in \
+                `{}`{}; a condition and its negation cover every possible value", func_name, on_line(line)),
+        }
+    }
+}
+
+/// Formats the suggested name (if any) so it can be appended to an error message
+fn did_you_mean(suggestion: &Option<String>) -> String {
+    match suggestion {
+        Some(name) => format!("; did you mean '{}'?", name),
+        None => String::new(),
+    }
+}
+
+/// Formats the list of available methods (if any) so it can be appended to an error message
+/// after the suggested name (if any)
+fn available_methods(suggestion: &Option<String>, available: &[String]) -> String {
+    if available.is_empty() {
+        return String::new();
+    }
+
+    // The suggestion already ends the previous part of the message with a question mark
+    let separator = if suggestion.is_some() { " " } else { "; " };
+    format!("{}available: {}", separator, available.join(", "))
+}
+
+/// Infers and checks the types in the given module, producing its IR along with any warnings
+/// found in the checked code
+///
+/// The IR only contains the items declared in the module, so the IR of every module in the
+/// program must be merged (see `merge_modules`) before code can be generated for it.
 pub fn infer_and_check<'a>(
-    module_decls: ModuleDecls<'a>,
+    mut module_decls: ModuleDecls<'a>,
     decls: &'a ProgramDecls<'a>,
-) -> Result<ir::Program<'a>, Error> {
+) -> Result<(ir::Program<'a>, Vec<Warning>), Error> {
     let ProgramDecls {top_level_decls, prims} = decls;
 
+    // The scope is borrowed while the rest of the declarations are consumed
+    let module_scope = std::mem::take(&mut module_decls.scope);
     let mod_tycheck = ModuleTycheck {
         decls: top_level_decls,
+        module_scope: &module_scope,
         prims,
     };
     let top_level_module = mod_tycheck.infer_and_check_module(module_decls)?;
+    let warnings = module_warnings(&top_level_module, top_level_decls);
+
+    Ok((ir::Program {top_level_module}, warnings))
+}
+
+/// Merges the IR of every module of a program into a single program
+///
+/// A type may have methods declared in more than one module, so the methods of every struct with
+/// the same name are combined.
+pub fn merge_modules<'a>(programs: Vec<ir::Program<'a>>, decls: &ProgramDecls<'a>) -> ir::Program<'a> {
+    let mut types: Vec<ir::Struct> = Vec::new();
+    let mut functions = Vec::new();
+    let mut module_functions = Vec::new();
+    let mut statics = Vec::new();
+
+    for program in programs {
+        let ir::Module {types: module_types, functions: module_funcs, module_functions: module_mod_funcs,
+            statics: module_statics} = program.top_level_module;
+
+        for ty in module_types {
+            match types.iter_mut().find(|other| other.name == ty.name) {
+                Some(other) => other.methods.extend(ty.methods),
+                None => types.push(ty),
+            }
+        }
+        functions.extend(module_funcs);
+        module_functions.extend(module_mod_funcs);
+        statics.extend(module_statics);
+    }
+
+    // Type IDs are assigned in declaration order, so sorting by ID keeps the generated code the
+    // same every time the program is compiled
+    types.sort_by_key(|ty| decls.top_level_decls.type_id(&ty.name));
+
+    ir::Program {
+        top_level_module: ir::Module {types, functions, module_functions, statics},
+    }
+}
+
+/// Returns the warnings for every function and method in the given module
+///
+/// Methods are stored in hash maps, so the warnings are sorted by function name to keep the
+/// output deterministic. Warnings within the same function stay in the order they were found.
+fn module_warnings(module: &ir::Module, decls: &DeclMap) -> Vec<Warning> {
+    let ir::Module {types, functions, module_functions, statics: _} = module;
+
+    let methods = types.iter().flat_map(|ty| ty.methods.values());
+    let module_functions = module_functions.iter().map(|(_, func)| func);
+    let mut warnings: Vec<_> = functions.iter().chain(methods).chain(module_functions)
+        .flat_map(|func| {
+            // Raw C code is emitted as is, so none of the other checks apply to it
+            if func.c_body.is_some() {
+                return vec![Warning::RawCBody {func_name: func.name.to_string()}];
+            }
 
-    Ok(ir::Program {top_level_module})
+            let mut warnings = unused_vars::check_function(func);
+            warnings.extend(failing_asserts::check_function(func));
+            warnings.extend(purity::check_unused_calls(func, decls));
+            warnings.extend(unreachable_else::check_function(func));
+            warnings
+        })
+        .collect();
+    // Sorting is stable, so warnings within the same function stay in order
+    warnings.sort_by(|a, b| a.func_name().cmp(b.func_name()));
+
+    warnings
 }
 
 #[derive(Debug)]
-struct ModuleTycheck<'a> {
+struct ModuleTycheck<'a, 's> {
     decls: &'a DeclMap<'a>,
+    /// The items that can be referred to by name in the module being checked
+    module_scope: &'s ModuleScope<'a>,
     prims: &'a Primitives,
 }
 
-impl<'a> ModuleTycheck<'a> {
+impl<'a, 's> ModuleTycheck<'a, 's> {
     fn infer_and_check_module(
         &self,
         module_decls: ModuleDecls<'a>,
     ) -> Result<ir::Module<'a>, Error> {
-        let ModuleDecls {types, methods, functions} = module_decls;
+        let ModuleDecls {scope: _, mut types, methods, functions, module_functions, statics,
+            assoc_consts, field_defaults} = module_decls;
+
+        // A module may declare methods for a type declared in another module. The methods are
+        // stored on a copy of that type which is combined with the original type later.
+        for &self_ty in methods.keys() {
+            if self.prims.name_of(self_ty).is_none() {
+                types.entry(self_ty).or_insert_with(|| {
+                    let mut fields = ir::FieldTys::new();
+                    for (&name, &ty) in self.decls.fields(self_ty) {
+                        fields.insert(name, ty);
+                    }
+                    ir::Struct::new(self.decls.type_name(self_ty), fields)
+                });
+            }
+        }
 
         // Able to use concurrency here because types can be checked in any order
 
@@ -134,15 +423,41 @@ impl<'a> ModuleTycheck<'a> {
 
         let functions = functions.into_par_iter()
             // No need to check external functions
-            .filter(|(_, func)| !func.is_extern)
-            .map(|(sig, func)| self.infer_and_check_func(sig, func))
+            .filter(|(_, _, func)| !func.is_extern)
+            .map(|(name, sig, func)| self.infer_and_check_func(name, sig, func))
             .collect::<Result<Vec<_>, _>>()?;
 
-        let types = types.into_par_iter()
+        let module_functions = module_functions.into_par_iter()
+            .map(|(module_name, sig, func)| {
+                let func = self.infer_and_check_module_func(module_name, sig, func)?;
+                Ok((module_name, func))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let statics = statics.into_par_iter()
+            .map(|(name, ty, static_decl)| self.infer_and_check_static(name, ty, static_decl))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Constants are inlined wherever they are used, but they are still checked here so that
+        // an unused constant with an invalid value is reported
+        assoc_consts.into_par_iter()
+            .map(|(ty, assoc_const)| self.check_constant(ty, &assoc_const.value))
+            .collect::<Result<(), _>>()?;
+        // The same applies to the default values of fields, which are checked once for each struct
+        // instead of only where a default is used
+        field_defaults.into_par_iter()
+            .map(|(ty, default)| self.check_constant(ty, default))
+            .collect::<Result<(), _>>()?;
+
+        // Type IDs are assigned in declaration order, so sorting by ID keeps the generated code the
+        // same every time the program is compiled
+        let mut types: Vec<_> = types.into_iter().collect();
+        types.sort_by_key(|&(ty_id, _)| ty_id);
+        let types = types.into_iter()
             .map(|(_, struct_decl)| struct_decl.into_inner())
             .collect();
 
-        Ok(ir::Module {types, functions})
+        Ok(ir::Module {types, functions, module_functions, statics})
     }
 
     fn infer_and_check_method(
@@ -152,19 +467,270 @@ impl<'a> ModuleTycheck<'a> {
         method: &'a ast2::Function<'a>,
     ) -> Result<ir::Function<'a>, Error> {
         // `ty_ir_method` is a copy of the function's AST with any generated type variables placed inline
-        let (constraints, ty_ir_method) = ConstraintSet::method(self_ty, sig, method, self.decls, self.prims)?;
+        let (constraints, ty_ir_method) = ConstraintSet::method(self_ty, sig, method, self.decls,
+            self.module_scope, self.prims)?;
+        let solution = constraints.solve(self.prims)?;
+        let method = ty_ir_method.apply_subst(&solution);
+        reachability::check_function(&method, self.prims)?;
+        purity::check_function(&method, self.decls)?;
+        Ok(method)
+    }
+
+    fn infer_and_check_static(
+        &self,
+        name: ir::Ident<'a>,
+        ty: TyId,
+        static_decl: &'a ast2::Static<'a>,
+    ) -> Result<ir::Static<'a>, Error> {
+        let (constraints, ty_ir_static) = ConstraintSet::static_var(name, ty, static_decl,
+            self.decls, self.module_scope, self.prims)?;
         let solution = constraints.solve(self.prims)?;
-        Ok(ty_ir_method.apply_subst(&solution))
+        Ok(ty_ir_static.apply_subst(&solution))
+    }
+
+    fn check_constant(
+        &self,
+        ty: TyId,
+        value: &'a ast2::Expr<'a>,
+    ) -> Result<(), Error> {
+        let constraints = ConstraintSet::constant(ty, value, self.decls, self.module_scope,
+            self.prims)?;
+        constraints.solve(self.prims)?;
+        Ok(())
     }
 
     fn infer_and_check_func(
         &self,
+        name: ir::Ident<'a>,
         sig: ir::FuncSig<'a>,
         func: &'a ast2::Function<'a>,
     ) -> Result<ir::Function<'a>, Error> {
         // `ty_ir_func` is a copy of the function's AST with any generated type variables placed inline
-        let (constraints, ty_ir_func) = ConstraintSet::function(sig, func, self.decls, self.prims)?;
+        let (constraints, ty_ir_func) = ConstraintSet::function(name, sig, func, self.decls,
+            self.module_scope, self.prims)?;
+        let solution = constraints.solve(self.prims)?;
+        let func = ty_ir_func.apply_subst(&solution);
+        reachability::check_function(&func, self.prims)?;
+        purity::check_function(&func, self.decls)?;
+        Ok(func)
+    }
+
+    fn infer_and_check_module_func(
+        &self,
+        module_name: ir::Ident<'a>,
+        sig: ir::FuncSig<'a>,
+        func: &'a ast2::Function<'a>,
+    ) -> Result<ir::Function<'a>, Error> {
+        let (constraints, ty_ir_func) = ConstraintSet::module_function(module_name, sig, func,
+            self.decls, self.module_scope, self.prims)?;
         let solution = constraints.solve(self.prims)?;
-        Ok(ty_ir_func.apply_subst(&solution))
+        let func = ty_ir_func.apply_subst(&solution);
+        reachability::check_function(&func, self.prims)?;
+        purity::check_function(&func, self.decls)?;
+        Ok(func)
+    }
+}
+
+/// A program that was type checked by `check_source`
+#[cfg(test)]
+pub(crate) struct CheckedSource {
+    pub program: ir::Program<'static>,
+    pub decls: &'static ProgramDecls<'static>,
+    pub warnings: Vec<Warning>,
+}
+
+/// Parses the given program (read from `test.dino`) and type checks it along with the prelude
+///
+/// The parsed program and its declarations are leaked so that the IR, which borrows from both of
+/// them, can be returned. The program must parse and its declarations must resolve.
+#[cfg(test)]
+pub(crate) fn check_source(source: &'static str) -> Result<CheckedSource, Error> {
+    let mut program = ast2::Program::parse(source).unwrap();
+    program.top_level_module.set_source_path(std::path::Path::new("test.dino"));
+    let program = Box::leak(Box::new(program));
+
+    let decls = Box::leak(Box::new(ProgramDecls::new()));
+    crate::insert_prelude(decls).unwrap();
+    let module_decls = decls.extract(program).unwrap();
+    let decls = &*decls;
+
+    let (program, warnings) = infer_and_check(module_decls, decls)?;
+    Ok(CheckedSource {program, decls, warnings})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Type checks the given program
+    fn check(source: &'static str) -> Result<(), Error> {
+        check_source(source).map(|_| ())
+    }
+
+    #[test]
+    fn statics() {
+        let source = "
+            static mut COUNT: int = 0;
+            static mut NAME: bstr = b\"dino\";
+            fn main() {
+                COUNT = COUNT + 1;
+                print_int(COUNT);
+                print_bstr(NAME);
+            }
+        ";
+        assert!(check(source).is_ok());
+
+        let source = "static mut NAME: bstr = 1; fn main() {}";
+        assert!(matches!(check(source), Err(Error::InvalidIntLitType {..})));
+    }
+
+    #[test]
+    fn assoc_consts() {
+        let source = "
+            struct Circle { radius: real }
+            impl Circle {
+                const UNIT_RADIUS: real = 1.0;
+                fn unit() -> Self { Self {radius: Self::UNIT_RADIUS} }
+            }
+            impl int { const ZERO: int = 0; }
+            fn main() {
+                print_int(int::ZERO);
+                print_real(Circle::unit().radius);
+            }
+        ";
+        assert!(check(source).is_ok());
+
+        // The value is checked even if the constant is never used
+        let source = "impl int { const ZERO: int = b\"0\"; } fn main() {}";
+        assert!(matches!(check(source), Err(Error::MismatchedTypes {..})));
+    }
+
+    #[test]
+    fn field_defaults() {
+        let source = "
+            struct Config { retries: int = 3, scale: real = 1.5, name: bstr }
+            fn main() {
+                let config = Config {name: b\"dino\"};
+                print_int(config.retries);
+            }
+        ";
+        assert!(check(source).is_ok());
+
+        // The default is checked against the type of the field even if it is never used
+        let source = "struct Config { name: bstr = 1 } fn main() {}";
+        assert!(matches!(check(source), Err(Error::InvalidIntLitType {..})));
+        let source = "
+            struct Config { retries: int = true }
+            fn main() { print_int(Config {retries: 2}.retries); }
+        ";
+        assert!(matches!(check(source), Err(Error::MismatchedTypes {..})));
+    }
+
+    #[test]
+    fn branch_type_mismatch() {
+        // The `else` branch is reported even though the first two branches match
+        let source = "fn main() {
+            let c = true;
+            let x = if c { 1 } else if c { 2 } else {
+                b\"three\"
+            };
+        }";
+        assert!(matches!(check(source), Err(Error::BranchTypeMismatch {
+            branch_index: 2,
+            line: Some(4),
+            ..
+        })));
+    }
+
+    #[test]
+    fn raw_c_body() {
+        let source = r#"
+            extern "C" fn double(x: int) -> int = "return int__add(x, x);";
+            fn main() { print_int(double(21)); }
+        "#;
+        let warnings = check_source(source).unwrap().warnings;
+        assert_eq!(warnings, vec![Warning::RawCBody {func_name: "double".to_string()}]);
+    }
+
+    #[test]
+    fn reordered_named_args() {
+        // Only calls to impure functions prevent named arguments from being reordered
+        let source = "
+            fn area(width: int, height: int) -> int { width * height }
+            #[pure] fn double(x: int) -> int { x * 2 }
+            fn main() { print_int(area(height: double(2) + 1, width: 3)); }
+        ";
+        assert!(check(source).is_ok());
+
+        let source = "
+            fn area(width: int, height: int) -> int { width * height }
+            fn read() -> int { bstr_len(read_line_bstr()) }
+            fn main() {
+                print_int(area(width: read(), height: read()));
+                print_int(area(height: read(), width: 3));
+            }
+        ";
+        assert!(matches!(check(source), Err(Error::ReorderedSideEffects {param, ..}) if param == "width"));
+    }
+
+    #[test]
+    fn const_bstr_len() {
+        let source = "fn len() -> int { bstr_len(b\"dino\") } fn main() { print_int(len()); }";
+        let checked = check_source(source).unwrap();
+        let len = &checked.program.top_level_module.functions[0];
+        assert!(matches!(len.body.ret, Some(ir::Expr::IntegerLiteral(4, _))));
+
+        let source = "fn main() { let x: int = 3; print_int(x / bstr_len(b\"\")); }";
+        assert!(matches!(check(source), Err(Error::DivisionByZero {..})));
+
+        // A function in an inline module that shadows the prelude's `bstr_len` is called normally
+        let source = "
+            mod m {
+                fn bstr_len(x: bstr) -> int { 1 }
+                fn len() -> int { bstr_len(b\"\") }
+                fn ratio(x: int) -> int { x / bstr_len(b\"\") }
+            }
+            fn main() { print_int(m::len()); print_int(m::ratio(3)); }
+        ";
+        let checked = check_source(source).unwrap();
+        let (_, len) = checked.program.top_level_module.module_functions.iter()
+            .find(|(_, func)| func.name == "len")
+            .unwrap();
+        assert!(matches!(len.body.ret, Some(ir::Expr::Call(..))));
+    }
+
+    #[test]
+    fn missing_return() {
+        // Every path ends in a `return`, including through a nested block
+        let source = "
+            fn sign(x: int) -> int {
+                if x < 0 {
+                    return -1;
+                } else {
+                    { return 1; }
+                }
+            }
+            fn main() { print_int(sign(3)); }
+        ";
+        assert!(check(source).is_ok());
+
+        let source = "
+            fn sign(x: int) -> int {
+                if x < 0 {
+                    return -1;
+                }
+            }
+            fn main() { print_int(sign(3)); }
+        ";
+        assert!(matches!(check(source), Err(Error::MissingReturn {line: Some(3), ..})));
+
+        // Any other block must also produce a value if its end can be reached
+        let source = "
+            fn main() {
+                let x: int = if true { print_int(1); } else { 2 };
+                print_int(x);
+            }
+        ";
+        assert!(matches!(check(source), Err(Error::MismatchedTypes {..})));
     }
 }
@@ -4,7 +4,7 @@ use snafu::OptionExt;
 use maplit::hashset;
 use ena::unify::{InPlaceUnificationTable, UnifyKey, EqUnifyValue};
 
-use crate::resolve2::{DeclMap, TyId};
+use crate::resolve2::{DeclMap, ModuleScope, TyId, AssocConstInfo, FunctionInfo};
 use crate::primitives2::Primitives;
 use crate::{ast2, ir};
 
@@ -12,13 +12,14 @@ use super::{
     Error,
     UnresolvedName,
     UnresolvedType,
-    UnresolvedFunction,
-    UnresolvedMethod,
     AmbiguousMethodCall,
-    UnresolvedField,
     AmbiguousFieldAccess,
+    UnknownNamedArg,
+    MissingArg,
+    MissingField,
     tyir,
     solve::{build_substitution, verify_valid_tys_or_default},
+    suggest::closest_name,
 };
 use super::scope::Scope;
 use super::subst::TypeSubst;
@@ -54,19 +55,42 @@ pub struct ConstraintSet {
     int_vars: HashSet<TyVar>,
     /// A list of variables associated with real literals (real, complex)
     real_vars: HashSet<TyVar>,
+    /// A list of variables associated with blocks that have no return expression (unit, unless
+    /// the end of the block can never be reached)
+    unit_vars: HashSet<TyVar>,
 }
 
 impl ConstraintSet {
-    /// Generates a constraint set for the given function declaration. Any fresh type variables
-    /// created are annotated inline into the returned `tyir::Function`
+    /// Generates a constraint set for the given function declaration, declared in the program with
+    /// the given name. Any fresh type variables created are annotated inline into the returned
+    /// `tyir::Function`
     pub fn function<'a>(
+        name: ir::Ident<'a>,
         sig: ir::FuncSig<'a>,
         func: &'a ast2::Function<'a>,
         decls: &'a DeclMap<'a>,
+        module_scope: &ModuleScope<'a>,
         prims: &Primitives,
     ) -> Result<(Self, tyir::Function<'a>), Error> {
         let mut constraints = Self::default();
-        let func = FunctionConstraintGenerator::generate(None, sig, func, decls, prims, &mut constraints)?;
+        let func = FunctionConstraintGenerator::generate(None, None, name, sig, func, decls,
+            module_scope, prims, &mut constraints)?;
+        Ok((constraints, func))
+    }
+
+    /// Generates a constraint set for the given function declared in the given inline module. Any
+    /// fresh type variables created are annotated inline into the returned `tyir::Function`
+    pub fn module_function<'a>(
+        module_name: ir::Ident<'a>,
+        sig: ir::FuncSig<'a>,
+        func: &'a ast2::Function<'a>,
+        decls: &'a DeclMap<'a>,
+        module_scope: &ModuleScope<'a>,
+        prims: &Primitives,
+    ) -> Result<(Self, tyir::Function<'a>), Error> {
+        let mut constraints = Self::default();
+        let func = FunctionConstraintGenerator::generate(None, Some(module_name), func.name, sig,
+            func, decls, module_scope, prims, &mut constraints)?;
         Ok((constraints, func))
     }
 
@@ -74,19 +98,53 @@ impl ConstraintSet {
     /// created are annotated inline into the returned `tyir::Function`
     pub fn method<'a>(
         self_ty: TyId,
-    sig: ir::FuncSig<'a>,
+        sig: ir::FuncSig<'a>,
         func: &'a ast2::Function<'a>,
         decls: &'a DeclMap<'a>,
+        module_scope: &ModuleScope<'a>,
         prims: &Primitives,
     ) -> Result<(Self, tyir::Function<'a>), Error> {
         let mut constraints = Self::default();
-        let method = FunctionConstraintGenerator::generate(Some(self_ty), sig, func, decls, prims, &mut constraints)?;
+        let method = FunctionConstraintGenerator::generate(Some(self_ty), None, func.name, sig, func,
+            decls, module_scope, prims, &mut constraints)?;
         Ok((constraints, method))
     }
 
+    /// Generates a constraint set for the initializer of the given global variable, declared in
+    /// the program with the given name. Any fresh type variables created are annotated inline
+    /// into the returned `tyir::Static`
+    pub fn static_var<'a>(
+        name: ir::Ident<'a>,
+        ty: TyId,
+        static_decl: &'a ast2::Static<'a>,
+        decls: &'a DeclMap<'a>,
+        module_scope: &ModuleScope<'a>,
+        prims: &Primitives,
+    ) -> Result<(Self, tyir::Static<'a>), Error> {
+        let mut constraints = Self::default();
+        let static_var = FunctionConstraintGenerator::generate_static(name, ty, static_decl, decls,
+            module_scope, prims, &mut constraints)?;
+        Ok((constraints, static_var))
+    }
+
+    /// Generates a constraint set for a constant value (e.g. the value of an associated constant
+    /// or the default value of a field) that must have the given type
+    pub fn constant<'a>(
+        ty: TyId,
+        value: &'a ast2::Expr<'a>,
+        decls: &'a DeclMap<'a>,
+        module_scope: &ModuleScope<'a>,
+        prims: &Primitives,
+    ) -> Result<Self, Error> {
+        let mut constraints = Self::default();
+        FunctionConstraintGenerator::generate_constant(ty, value, decls, module_scope, prims,
+            &mut constraints)?;
+        Ok(constraints)
+    }
+
     /// Attempts to solve the constraint set and return the solution as a substitution map
     pub fn solve(self, prims: &Primitives) -> Result<TypeSubst, Error> {
-        let Self {mut ty_var_table, int_vars, real_vars} = self;
+        let Self {mut ty_var_table, int_vars, real_vars, unit_vars} = self;
 
         // Assert that the literals are one of the expected types for that kind of literal
         verify_valid_tys_or_default(
@@ -102,6 +160,15 @@ impl ConstraintSet {
             &mut ty_var_table,
         ).map_err(|actual| Error::InvalidRealLitType {actual})?;
 
+        // A block without a return expression may have any type if its end can never be reached
+        // (checked once the types are known), so it only defaults to unit
+        for &ty_var in &unit_vars {
+            if ty_var_table.probe_value(ty_var).is_none() {
+                // unwrap() is safe because the type hasn't been inserted yet
+                ty_var_table.unify_var_value(ty_var, Some(prims.unit())).unwrap();
+            }
+        }
+
         // The resulting substitution must contain all variables
         let ty_vars = (0..ty_var_table.len()).map(|id| TyVar(id as u32));
         let ty_vars = ty_vars.map(|ty_var| (ty_var, ty_var_table.probe_value(ty_var)));
@@ -142,46 +209,173 @@ impl ConstraintSet {
     pub fn ty_var_is_real(&mut self, ty_var: TyVar) {
         self.real_vars.insert(ty_var);
     }
+
+    /// Records this type variable as the type of a block with no return expression so that it
+    /// defaults to unit if nothing else determines its type
+    pub fn ty_var_is_unit_block(&mut self, ty_var: TyVar) {
+        self.unit_vars.insert(ty_var);
+    }
+
+    /// Returns true if the given type variable has been unified with the variable of an integer
+    /// literal
+    pub fn is_int_var(&mut self, ty_var: TyVar) -> bool {
+        let Self {ty_var_table, int_vars, ..} = self;
+        int_vars.iter().any(|&int_var| ty_var_table.unioned(int_var, ty_var))
+    }
+
+    /// Returns true if the given type variable has been unified with the variable of a real
+    /// literal
+    pub fn is_real_var(&mut self, ty_var: TyVar) -> bool {
+        let Self {ty_var_table, real_vars, ..} = self;
+        real_vars.iter().any(|&real_var| ty_var_table.unioned(real_var, ty_var))
+    }
+}
+
+/// What is known so far about the type of a branch of a conditional
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BranchTy {
+    /// The branch is known to have this type
+    Known(TyId),
+    /// The branch results in an integer literal whose type has not been determined yet
+    IntLiteral,
+    /// The branch results in a real literal whose type has not been determined yet
+    RealLiteral,
+    /// Nothing is known about the type of the branch yet
+    Unknown,
+}
+
+/// A branch of a conditional, used to report which branch has the wrong type
+#[derive(Debug, Clone, Copy)]
+struct CondBranch<'a> {
+    body: &'a ast2::Block<'a>,
+    /// The index of the branch, starting at 0 for the first branch (the `else` branch is last)
+    index: usize,
 }
 
 #[derive(Debug)]
 struct FunctionConstraintGenerator<'a, 'b, 'c> {
     self_ty: Option<TyId>,
+    /// The inline module containing the function being type checked, if any
+    module_name: Option<ir::Ident<'a>>,
     decls: &'a DeclMap<'a>,
+    /// The items that can be referred to by name in the module containing the code
+    module_scope: &'b ModuleScope<'a>,
     prims: &'b Primitives,
     constraints: &'c mut ConstraintSet,
     /// The return type of the function being type checked
     func_return_type: TyVar,
+    /// The number of loops enclosing the code currently being type checked
+    loop_depth: usize,
+    /// The line of the statement currently being type checked (if known)
+    line: Option<usize>,
 }
 
 impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
+    #[allow(clippy::too_many_arguments)]
     pub fn generate(
         self_ty: Option<TyId>,
+        module_name: Option<ir::Ident<'a>>,
+        name: ir::Ident<'a>,
         sig: ir::FuncSig<'a>,
         func: &'a ast2::Function<'a>,
         decls: &'a DeclMap<'a>,
+        module_scope: &'b ModuleScope<'a>,
         prims: &'b Primitives,
         constraints: &'c mut ConstraintSet,
     ) -> Result<tyir::Function<'a>, Error> {
         let func_return_type = constraints.fresh_type_var();
         let mut generator = Self {
             self_ty,
+            module_name,
             decls,
+            module_scope,
             prims,
             constraints,
             func_return_type,
+            loop_depth: 0,
+            line: None,
         };
 
-        generator.append_func(sig, func)
+        generator.append_func(name, sig, func)
+    }
+
+    pub fn generate_static(
+        name: ir::Ident<'a>,
+        ty: TyId,
+        static_decl: &'a ast2::Static<'a>,
+        decls: &'a DeclMap<'a>,
+        module_scope: &'b ModuleScope<'a>,
+        prims: &'b Primitives,
+        constraints: &'c mut ConstraintSet,
+    ) -> Result<tyir::Static<'a>, Error> {
+        // The initializer is a constant, so it can never contain a `return`. The return type is
+        // still constrained (arbitrarily to unit) since every type variable must be solved.
+        let func_return_type = constraints.fresh_type_var();
+        constraints.ty_var_is_ty(func_return_type, prims.unit())?;
+        let mut generator = Self {
+            self_ty: None,
+            module_name: None,
+            decls,
+            module_scope,
+            prims,
+            constraints,
+            func_return_type,
+            loop_depth: 0,
+            line: None,
+        };
+
+        let ast2::Static {name: _, ty: _, init} = static_decl;
+
+        // The initial value must have the declared type of the global variable
+        let init_ty_var = generator.constraints.fresh_type_var();
+        generator.constraints.ty_var_is_ty(init_ty_var, ty)?;
+        // No local variables are in scope in the initializer
+        let init = generator.append_expr(init, init_ty_var, &mut Scope::default())?;
+
+        Ok(tyir::Static {name, ty, init})
+    }
+
+    pub fn generate_constant(
+        ty: TyId,
+        value: &'a ast2::Expr<'a>,
+        decls: &'a DeclMap<'a>,
+        module_scope: &'b ModuleScope<'a>,
+        prims: &'b Primitives,
+        constraints: &'c mut ConstraintSet,
+    ) -> Result<(), Error> {
+        // The value is a constant, so it can never contain a `return`. As with statics, the
+        // return type is still constrained so that every type variable is solved.
+        let func_return_type = constraints.fresh_type_var();
+        constraints.ty_var_is_ty(func_return_type, prims.unit())?;
+        let mut generator = Self {
+            self_ty: None,
+            module_name: None,
+            decls,
+            module_scope,
+            prims,
+            constraints,
+            func_return_type,
+            loop_depth: 0,
+            line: None,
+        };
+
+        // The value must have the given type. The generated code is discarded since the value is
+        // inlined (and checked again) wherever it is used.
+        let value_ty_var = generator.constraints.fresh_type_var();
+        generator.constraints.ty_var_is_ty(value_ty_var, ty)?;
+        generator.append_expr(value, value_ty_var, &mut Scope::default())?;
+
+        Ok(())
     }
 
     /// Appends constrains for the given function
     fn append_func(
         &mut self,
+        name: ir::Ident<'a>,
         sig: ir::FuncSig<'a>,
         func: &'a ast2::Function<'a>,
     ) -> Result<tyir::Function<'a>, Error> {
-        let ast2::Function {name, sig: _, body, is_extern} = func;
+        let ast2::Function {name: _, sig: _, body, is_extern, c_body, is_test, is_pure, source_path} = func;
         assert!(!is_extern, "bug: attempt to type check an extern function");
 
         let ir::FuncSig {return_type: func_return_type, ref params} = sig;
@@ -190,6 +384,20 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
         let return_type = self.func_return_type;
         self.constraints.ty_var_is_ty(return_type, func_return_type)?;
 
+        // Raw C code is trusted to match the signature of the function, so there is nothing else
+        // to check
+        if let Some(c_body) = c_body {
+            let body = tyir::Block {
+                stmts: Vec::new(),
+                stmt_lines: Vec::new(),
+                ret: None,
+                ret_ty_var: return_type,
+            };
+            let c_body = Some(c_body.as_str());
+            return Ok(tyir::Function {name, sig, body, c_body, is_test: *is_test,
+                is_pure: *is_pure, source_path: *source_path});
+        }
+
         // Add each parameter as a local variable in the function scope
         let mut scope = Scope::default();
         for &ir::FuncParam {name, ty} in params {
@@ -201,7 +409,8 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
 
         // Type expected from block is the same as the type expected from the function
         let body = self.append_block(body, return_type, &mut scope)?;
-        Ok(tyir::Function {name, sig, body})
+        Ok(tyir::Function {name, sig, body, c_body: None, is_test: *is_test, is_pure: *is_pure,
+            source_path: *source_path})
     }
 
 
@@ -216,23 +425,46 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
         return_type: TyVar,
         scope: &mut Scope<'a, 's>,
     ) -> Result<tyir::Block<'a>, Error> {
-        let ast2::Block {stmts, ret} = block;
-
-        Ok(tyir::Block {
-            stmts: stmts.iter()
-                .map(|stmt| self.append_stmt(stmt, scope))
-                .collect::<Result<Vec<_>, _>>()?,
-            ret: match ret {
-                // The returned expression must have the same type as the block
-                Some(ret) => Some(self.append_expr(ret, return_type, scope)?),
+        let ast2::Block {stmts, stmt_lines, ret} = block;
+        // The rest of the enclosing statement continues after the end of this block
+        let outer_line = self.line;
+
+        let mut stmts = stmts.iter().enumerate()
+            .map(|(i, stmt)| {
+                self.line = stmt_lines.get(i).copied();
+                self.append_stmt(stmt, scope)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The line of the return expression (if any) comes after the lines of the statements
+        self.line = stmt_lines.get(stmts.len()).copied();
+
+        let ret = match ret {
+            // A conditional without an `else` at the end of the function body does not produce a
+            // value when none of its branches run, so it is treated as a statement. That way, a
+            // missing return is reported (once the types are known) instead of a type mismatch.
+            Some(ast2::Expr::Cond(cond)) if cond.else_body.is_none() &&
+                return_type == self.func_return_type => {
+                stmts.push(tyir::Stmt::Cond(self.append_cond(cond, None, scope)?));
+                None
+            },
 
-                None => {
-                    // No return expression, so the return type of this block should be unit
-                    self.constraints.ty_var_is_ty(return_type, self.prims.unit())?;
+            // The returned expression must have the same type as the block
+            Some(ret) => Some(self.append_expr(ret, return_type, scope)?),
 
-                    None
-                },
+            // No return expression, so the type of this block is unit unless its end can never be
+            // reached. Whether that is the case is checked once the types are known.
+            None => {
+                self.constraints.ty_var_is_unit_block(return_type);
+                None
             },
+        };
+        self.line = outer_line;
+
+        Ok(tyir::Block {
+            stmts,
+            stmt_lines: stmt_lines.clone(),
+            ret,
             ret_ty_var: return_type,
         })
     }
@@ -248,8 +480,29 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
                 .map(tyir::Stmt::Cond),
             ast2::Stmt::WhileLoop(wloop) => self.append_while_loop(wloop, scope)
                 .map(tyir::Stmt::WhileLoop),
+            ast2::Stmt::Break if self.loop_depth == 0 => {
+                Err(Error::BreakOutsideLoop {keyword: "break"})
+            },
+            ast2::Stmt::Break => Ok(tyir::Stmt::Break),
+            ast2::Stmt::Continue if self.loop_depth == 0 => {
+                Err(Error::BreakOutsideLoop {keyword: "continue"})
+            },
+            ast2::Stmt::Continue => Ok(tyir::Stmt::Continue),
+            ast2::Stmt::Block(block) => {
+                // Nested blocks are only allowed in statement position, so they must result in ()
+                let block_var = self.constraints.fresh_type_var();
+                self.constraints.ty_var_is_ty(block_var, self.prims.unit())?;
+                // Variables declared within the block shadow any outer variables with the same name
+                // until the end of the block
+                let mut child_scope = scope.child_scope();
+                self.append_block(block, block_var, &mut child_scope).map(tyir::Stmt::Block)
+            },
             ast2::Stmt::VarDecl(decl) => self.append_var_decl(decl, scope)
                 .map(tyir::Stmt::VarDecl),
+            // A conditional followed by a semi-colon is still in statement position since its
+            // value is discarded
+            ast2::Stmt::Expr(ast2::Expr::Cond(cond)) => self.append_cond(cond, None, scope)
+                .map(tyir::Stmt::Cond),
             ast2::Stmt::Expr(expr) => {
                 // Generate a fresh variable that is never used after this point. By not using the
                 // type variable, we indicate that the type of this expression does not matter.
@@ -280,9 +533,13 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
         // The body of the loop gets a new inner scope so that variables declared within it aren't
         // accessible after the loop has finished running
         let mut child_scope = scope.child_scope();
-        let body = self.append_block(body, loop_body_var, &mut child_scope)?;
+        // `break` and `continue` are only allowed within the body of the loop, including within any
+        // conditionals nested in the body
+        self.loop_depth += 1;
+        let body = self.append_block(body, loop_body_var, &mut child_scope);
+        self.loop_depth -= 1;
 
-        Ok(tyir::WhileLoop {cond, body})
+        Ok(tyir::WhileLoop {cond, body: body?})
     }
 
     /// Appends constraints for the given variable declaration
@@ -293,13 +550,10 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
     ) -> Result<tyir::VarDecl<'a>, Error> {
         let ast2::VarDecl {ident, ty, expr} = var_decl;
 
-        if scope.contains(ident) {
-            //TODO: To support variable shadowing in this algorithm we just need to make sure that
-            // statements are walked in order and that new variable declarations overwrite the
-            // recorded types of previous declarations with a *fresh* variable.
-            // Need to be a bit careful to make sure this works with nested scopes.
-            panic!("TODO: Variable shadowing is not supported yet.");
-        }
+        // Statements are walked in order, so a new variable declaration shadows any previous
+        // declaration with the same name by overwriting its recorded type with a *fresh* variable.
+        // Declarations in a child scope never modify the parent scope, so the previous declaration
+        // comes back into scope once the child scope ends.
 
         // Generate a fresh variable for the var decl
         let var_decl_ty_var = self.constraints.fresh_type_var();
@@ -354,10 +608,7 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
                     .map(|cond| tyir::Expr::Cond(Box::new(cond), return_type))
             },
 
-            ast2::Expr::Call(call) => {
-                self.append_func_call(call, return_type, scope)
-                    .map(|call| tyir::Expr::Call(call, return_type))
-            },
+            ast2::Expr::Call(call) => self.append_func_call(call, return_type, scope),
 
             ast2::Expr::Return(ret_expr) => {
                 self.append_return(ret_expr.as_ref().map(|x| x.as_ref()), return_type, scope)
@@ -369,6 +620,13 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
                     .map(|struct_lit| tyir::Expr::StructLiteral(struct_lit, return_type))
             },
 
+            ast2::Expr::OffsetOf(offset_of) => {
+                self.append_offset_of(offset_of, return_type)
+                    .map(|offset| tyir::Expr::IntegerLiteral(offset, return_type))
+            },
+
+            ast2::Expr::AssocConst(path) => self.append_assoc_const(path, return_type),
+
             ast2::Expr::BStrLiteral(value) => {
                 // Assert that the literal is one of the expected types for this kind of literal
                 self.constraints.ty_var_is_ty(return_type, self.prims.bstr())?;
@@ -377,11 +635,20 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
             },
 
             &ast2::Expr::IntegerLiteral(ast2::IntegerLiteral {value, type_hint}) => {
-                // Check if the user specified a specific type for the integer literal
+                // Check if the user specified a specific type for the integer literal. The hint
+                // fixes the type of the literal, so it must agree with the type expected from the
+                // context.
                 if let Some(ty_name) = type_hint {
                     let expected_type = self.decls.type_id(&ty_name)
                         .expect("bug: parser allowed an invalid integer type hint");
                     self.constraints.ty_var_is_ty(return_type, expected_type)?;
+
+                    // An integer explicitly typed as `real` is just another way to write a real
+                    // number literal
+                    if expected_type == self.prims.real() {
+                        self.constraints.ty_var_is_real(return_type);
+                        return Ok(tyir::Expr::RealLiteral(value as f64, return_type));
+                    }
                 }
 
                 self.constraints.ty_var_is_int(return_type);
@@ -425,13 +692,22 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
                 Ok(tyir::Expr::Var(name, var_ty_var))
             },
 
-            &ast2::Expr::Var(name) => {
-                let var_ty_var = scope.get(name).context(UnresolvedName {name})?;
-                // Assert that the type of the variable must be equal to the type expected from the
-                // expression
-                self.constraints.ty_var_equals(var_ty_var, return_type)?;
+            &ast2::Expr::Var(name) => match scope.get(name) {
+                Some(var_ty_var) => {
+                    // Assert that the type of the variable must be equal to the type expected from
+                    // the expression
+                    self.constraints.ty_var_equals(var_ty_var, return_type)?;
 
-                Ok(tyir::Expr::Var(name, var_ty_var))
+                    Ok(tyir::Expr::Var(name, var_ty_var))
+                },
+
+                // Local variables shadow any global variable with the same name
+                None => {
+                    let (name, static_ty) = self.lookup_static(name).context(UnresolvedName {name})?;
+                    self.constraints.ty_var_is_ty(return_type, static_ty)?;
+
+                    Ok(tyir::Expr::StaticVar(name, return_type))
+                },
             },
         }
     }
@@ -459,7 +735,14 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
             .with_context(|| AmbiguousMethodCall {})?;
 
         let func = self.decls.method(lhs_ty, method_name)
-            .context(UnresolvedMethod {method_name: *method_name, ty: lhs_ty})?;
+            .ok_or_else(|| self.unresolved_method(lhs_ty, method_name))?;
+
+        // Integer division by a constant zero would always trap at runtime
+        let is_int_division = lhs_ty == self.prims.int() &&
+            (*method_name == "div" || *method_name == "rem" || *method_name == "divmod");
+        if is_int_division && matches!(&args[..], [divisor] if self.const_int_value(divisor) == Some(0)) {
+            return Err(Error::DivisionByZero {line: self.line});
+        }
 
         let has_self = func.sig.params.get(0).map(|param| param.name == "self").unwrap_or(false);
         if !has_self {
@@ -477,7 +760,8 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
         };
 
         // Append the `self` argument as the lhs expression
-        self.append_func_call_sig(&func.sig, func_name, args, Some(lhs),
+        let args: Vec<_> = args.iter().collect();
+        self.append_func_call_sig(&func.sig, func_name, &args, Some(lhs),
             return_type, scope)
     }
 
@@ -504,7 +788,7 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
             .with_context(|| AmbiguousFieldAccess {})?;
 
         let field_ty = self.decls.field_type(lhs_ty, field)
-            .context(UnresolvedField {field_name: *field, ty: lhs_ty})?;
+            .ok_or_else(|| self.unresolved_field(lhs_ty, field))?;
         self.constraints.ty_var_is_ty(return_type, field_ty)?;
 
         Ok(tyir::FieldAccess {
@@ -515,7 +799,8 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
 
     /// Appends constraints for the given conditional
     ///
-    /// If return_type is None, the conditional must result in a unit type
+    /// If return_type is None, the conditional is in statement position and its value is
+    /// discarded, so each branch may result in a value of any type.
     fn append_cond<'s>(
         &mut self,
         cond: &'a ast2::Cond<'a>,
@@ -526,61 +811,350 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
         let ast2::Cond {conds, else_body} = cond;
         debug_assert!(!conds.is_empty(), "bug: conditional had no initial if block");
 
-        let no_return_type = return_type.is_none();
-        // Create a fresh type variable if none was provided
-        let return_type = return_type.unwrap_or_else(|| self.constraints.fresh_type_var());
+        // If the conditional is used as an expression and there is no else clause, the if condition
+        // must return unit. That is checked after the branches so that a branch producing some
+        // other value can be reported as a missing `else`.
+        let is_unit = else_body.is_none();
 
-        // If the condition is used as a statement (no return type) or if there is no else clause,
-        // the if condition must return unit
-        if no_return_type || cond.else_body.is_none() {
-            self.constraints.ty_var_is_ty(return_type, self.prims.unit())?;
-        }
+        // The type of the first branch, used to check that all the other branches match it
+        let mut first_branch_ty = None;
 
-        let conds = conds.iter().map(|(cond, body)| {
+        let conds = conds.iter().enumerate().map(|(branch_index, (cond, body))| {
             // Every condition must evaluate to a value of type bool
             let cond_var = self.constraints.fresh_type_var();
             self.constraints.ty_var_is_ty(cond_var, self.prims.bool())?;
             let cond = self.append_expr(cond, cond_var, scope)?;
 
-            // The body of every condition must evaluate to the same type
-            let mut child_scope = scope.child_scope();
-            let body = self.append_block(body, return_type, &mut child_scope)?;
+            let branch = CondBranch {body, index: branch_index};
+            let body = self.append_cond_branch(branch, return_type, is_unit, &mut first_branch_ty, scope)?;
 
             Ok((cond, body))
         }).collect::<Result<Vec<_>, _>>()?;
 
-        // The body of the else clause must evaluate to the same type as the other condition bodies
-        let else_body = else_body.as_ref().map(|else_body| {
-            let mut child_scope = scope.child_scope();
-            self.append_block(else_body, return_type, &mut child_scope)
-        }).transpose()?;
+        let else_body = else_body.as_ref()
+            .map(|else_body| {
+                let branch = CondBranch {body: else_body, index: conds.len()};
+                self.append_cond_branch(branch, return_type, is_unit, &mut first_branch_ty, scope)
+            })
+            .transpose()?;
+
+        if let Some(return_type) = return_type {
+            if is_unit {
+                self.constraints.ty_var_is_ty(return_type, self.prims.unit())?;
+            }
+        }
 
         Ok(tyir::Cond {conds, else_body})
     }
 
+    /// Appends constraints for the body of a single branch of a conditional
+    ///
+    /// If return_type is None, the value of the branch is discarded.
+    fn append_cond_branch<'s>(
+        &mut self,
+        branch: CondBranch<'a>,
+        // The type expected from the entire conditional
+        return_type: Option<TyVar>,
+        // True if every branch must result in unit
+        is_unit: bool,
+        first_branch_ty: &mut Option<TyVar>,
+        scope: &mut Scope<'a, 's>,
+    ) -> Result<tyir::Block<'a>, Error> {
+        let CondBranch {body, index: branch_index} = branch;
+        let mut child_scope = scope.child_scope();
+
+        match return_type {
+            // Without an `else`, the value of the conditional is unit when no branch runs, so
+            // every branch must also produce unit
+            Some(_) if is_unit => {
+                let branch_ty = self.constraints.fresh_type_var();
+                let body = self.append_block(body, branch_ty, &mut child_scope)?;
+
+                let branch_ty_so_far = self.branch_ty_so_far(branch_ty);
+                if !self.branch_tys_compatible(branch_ty_so_far, BranchTy::Known(self.prims.unit())) {
+                    return Err(Error::MissingElse {
+                        if_ty: self.branch_ty_name(branch_ty_so_far),
+                        branch_index,
+                        line: body.stmt_lines.last().copied().or(self.line),
+                    });
+                }
+                self.constraints.ty_var_is_ty(branch_ty, self.prims.unit())?;
+
+                Ok(body)
+            },
+
+            // The body of every condition must evaluate to the same type
+            Some(return_type) => {
+                let branch_ty = self.constraints.fresh_type_var();
+                let body = self.append_block(body, branch_ty, &mut child_scope)?;
+                self.append_branch_ty(branch, first_branch_ty, branch_ty, return_type)?;
+                Ok(body)
+            },
+
+            // The type of a discarded branch is only determined by its return expression. Without
+            // one, the branch results in unit (or never finishes, in which case its type does not
+            // matter either).
+            None => {
+                let branch_ty = self.constraints.fresh_type_var();
+                if body.ret.is_none() {
+                    self.constraints.ty_var_is_ty(branch_ty, self.prims.unit())?;
+                }
+                self.append_block(body, branch_ty, &mut child_scope)
+            },
+        }
+    }
+
+    /// Asserts that the type of a branch of a conditional is the same as the type of the first
+    /// branch and the type expected from the entire conditional
+    ///
+    /// Produces a `BranchTypeMismatch` error instead of a generic type mismatch if the branch is
+    /// already known to have a different type from the first branch.
+    fn append_branch_ty(
+        &mut self,
+        branch: CondBranch<'a>,
+        first_branch_ty: &mut Option<TyVar>,
+        branch_ty: TyVar,
+        // The type expected from the conditional
+        return_type: TyVar,
+    ) -> Result<(), Error> {
+        let first_ty = match *first_branch_ty {
+            Some(first_ty) => first_ty,
+            None => {
+                *first_branch_ty = Some(branch_ty);
+                return self.constraints.ty_var_equals(branch_ty, return_type);
+            },
+        };
+
+        let if_ty = self.branch_ty_so_far(first_ty);
+        let other_ty = self.branch_ty_so_far(branch_ty);
+        if !self.branch_tys_compatible(if_ty, other_ty) {
+            let CondBranch {body, index} = branch;
+            return Err(Error::BranchTypeMismatch {
+                if_ty: self.branch_ty_name(if_ty),
+                branch_ty: self.branch_ty_name(other_ty),
+                branch_index: index,
+                // The value of a block is produced by its last statement or return expression
+                line: body.stmt_lines.last().copied().or(self.line),
+            });
+        }
+
+        self.constraints.ty_var_equals(branch_ty, return_type)
+    }
+
+    /// Returns what is known so far about the type of a conditional branch
+    fn branch_ty_so_far(&mut self, branch_ty: TyVar) -> BranchTy {
+        match self.constraints.ty_so_far(branch_ty) {
+            Some(ty) => BranchTy::Known(ty),
+            None if self.constraints.is_int_var(branch_ty) => BranchTy::IntLiteral,
+            None if self.constraints.is_real_var(branch_ty) => BranchTy::RealLiteral,
+            None => BranchTy::Unknown,
+        }
+    }
+
+    /// Returns false if the two branch types can never be unified
+    fn branch_tys_compatible(&self, ty1: BranchTy, ty2: BranchTy) -> bool {
+        let prims = self.prims;
+        use BranchTy::*;
+        match (ty1, ty2) {
+            (Known(ty1), Known(ty2)) => ty1 == ty2,
+            (IntLiteral, Known(ty)) | (Known(ty), IntLiteral) => {
+                ty == prims.int() || ty == prims.real() || ty == prims.complex()
+            },
+            (RealLiteral, Known(ty)) | (Known(ty), RealLiteral) => {
+                ty == prims.real() || ty == prims.complex()
+            },
+            // Literals can always default to a common type and anything unknown will be checked
+            // when the type variables are unified
+            _ => true,
+        }
+    }
+
+    /// Returns the name of a branch type for use in error messages
+    ///
+    /// Literals are named after the type they would default to.
+    fn branch_ty_name(&self, ty: BranchTy) -> String {
+        let ty = match ty {
+            BranchTy::Known(ty) => ty,
+            BranchTy::IntLiteral => self.prims.int(),
+            BranchTy::RealLiteral => self.prims.real(),
+            BranchTy::Unknown => return "_".to_string(),
+        };
+
+        self.ty_name(ty)
+    }
+
+    /// Returns the name of the given type as it would be written in source code
+    fn ty_name(&self, ty: TyId) -> String {
+        self.decls.source_type_name(ty, self.prims).to_string()
+    }
+
+    /// Returns an error for a method that could not be found on the given type
+    fn unresolved_method(&self, ty: TyId, method_name: &str) -> Error {
+        // Only methods that take `self` can be called with method call syntax
+        let mut available: Vec<_> = self.decls.methods(ty)
+            .filter(|(_, sig)| sig.params.first().map(|param| param.name == "self").unwrap_or(false))
+            .map(|(name, _)| name)
+            .collect();
+        // Sorted so that the error message does not depend on iteration order
+        available.sort_unstable();
+
+        Error::UnresolvedMethod {
+            method_name: method_name.to_string(),
+            ty_name: self.ty_name(ty),
+            suggestion: closest_name(method_name, available.iter().copied()).map(str::to_string),
+            available: available.into_iter().map(str::to_string).collect(),
+        }
+    }
+
+    /// Returns an error for a field that could not be found on the given type
+    fn unresolved_field(&self, ty: TyId, field_name: &str) -> Error {
+        Error::UnresolvedField {
+            field_name: field_name.to_string(),
+            ty_name: self.ty_name(ty),
+        }
+    }
+
+    /// Returns an error for a function that could not be found
+    fn unresolved_function(&self, func_name: &str) -> Error {
+        let decls = self.decls;
+        let func_names = self.module_scope.names()
+            .filter(|(_, declared_name)| decls.func(declared_name).is_some())
+            .map(|(name, _)| name);
+        Error::UnresolvedFunction {
+            name: func_name.to_string(),
+            suggestion: closest_name(func_name, func_names).map(str::to_string),
+        }
+    }
+
+    /// Returns the name of the method of the given type that is most similar to `method_name`
+    fn closest_method(&self, ty: TyId, method_name: &str) -> Option<String> {
+        let method_names = self.decls.methods(ty).map(|(name, _)| name);
+        closest_name(method_name, method_names).map(str::to_string)
+    }
+
+    /// Returns the function called using the given path along with the full path of that
+    /// function, or None if no function with that path exists
+    fn callee(&self, func_name: &ast2::IdentPath<'a>) -> Option<(&'a FunctionInfo<'a>, ast2::IdentPath<'a>)> {
+        // Functions in the same inline module can be called without the name of the module
+        if let (&[name], Some(module_name)) = (&func_name.components[..], self.module_name) {
+            if let Some(func) = self.decls.module_func(&module_name, &name) {
+                return Some((func, ast2::IdentPath::from(vec![module_name, name])));
+            }
+        }
+
+        lookup_callee(func_name, self.decls, self.module_scope)
+    }
+
+    /// Returns the error for a call to a function that does not exist
+    fn unresolved_call(&self, func_name: &ast2::IdentPath<'a>) -> Error {
+        match func_name.components[..] {
+            [func_name] => self.unresolved_function(func_name),
+            [prefix, func_name] => match self.module_scope.get(prefix) {
+                Some(module_name) if self.decls.is_module(&module_name) => Error::UnresolvedFunction {
+                    name: format!("{}::{}", prefix, func_name),
+                    suggestion: closest_name(func_name, self.decls.module_functions(&module_name)
+                        .map(|(name, _)| name)).map(|name| format!("{}::{}", prefix, name)),
+                },
+                ty_name => match ty_name.and_then(|ty_name| self.decls.type_id(&ty_name)) {
+                    Some(ty_id) => Error::UnresolvedFunction {
+                        name: func_name.to_string(),
+                        suggestion: self.closest_method(ty_id, func_name),
+                    },
+                    None => Error::UnresolvedType {name: prefix.to_string()},
+                },
+            },
+            _ => Error::UnresolvedFunction {
+                name: func_name.to_string(),
+                suggestion: None,
+            },
+        }
+    }
+
     /// Appends constraints for the given function call
+    ///
+    /// Calls whose result is known at compile-time are replaced with that result.
     fn append_func_call<'s>(
         &mut self,
         call: &'a ast2::CallExpr<'a>,
         // The type expected from the call expression
         return_type: TyVar,
         scope: &mut Scope<'a, 's>,
-    ) -> Result<tyir::CallExpr<'a>, Error> {
-        let ast2::CallExpr {func_name, args} = call;
-
-        let sig = match &func_name.components[..] {
-            [] => unreachable!(),
-            [func_name] => self.decls.func_sig(func_name)
-                .context(UnresolvedFunction {name: *func_name})?,
-            [ty_name, func_name] => {
-                let ty_id = self.decls.type_id(ty_name).context(UnresolvedType {name: *ty_name})?;
-                self.decls.method_sig(ty_id, func_name)
-                    .context(UnresolvedFunction {name: *func_name})?
-            },
-            _ => return Err(Error::UnresolvedFunction {name: func_name.to_string()}),
+    ) -> Result<tyir::Expr<'a>, Error> {
+        let ast2::CallExpr {func_name, args, named_args} = call;
+
+        let (func, func_name) = match self.callee(func_name) {
+            Some(callee) => callee,
+            None => match &func_name.components[..] {
+                [] => unreachable!(),
+                [name] if *name == ast2::DEBUG_PRINT_FUNC_NAME => {
+                    return self.append_debug_print(call, return_type, scope)
+                        .map(|call| tyir::Expr::Call(call, return_type));
+                },
+                _ => return Err(self.unresolved_call(func_name)),
+            },
         };
 
-        self.append_func_call_sig(sig, func_name.clone(), args, None, return_type, scope)
+        // The result of the call is known at compile-time, so no call needs to be made
+        if let Some(value) = fold_const_call(func, args, named_args) {
+            self.constraints.ty_var_is_ty(return_type, self.prims.int())?;
+            return Ok(tyir::Expr::IntegerLiteral(value, return_type));
+        }
+
+        let args = order_call_args(&func.sig, &func_name, args, named_args, self.decls,
+            self.module_scope)?;
+        self.append_func_call_sig(&func.sig, func_name, &args, None, return_type, scope)
+            .map(|call| tyir::Expr::Call(call, return_type))
+    }
+
+    /// Returns the value of the given expression if it is an `int` that is known at compile-time
+    fn const_int_value(&self, expr: &ast2::Expr<'a>) -> Option<i64> {
+        match expr {
+            &ast2::Expr::IntegerLiteral(ast2::IntegerLiteral {value, type_hint: _}) => Some(value),
+            ast2::Expr::Call(ast2::CallExpr {func_name, args, named_args}) => {
+                let (func, _) = self.callee(func_name)?;
+                fold_const_call(func, args, named_args)
+            },
+            _ => None,
+        }
+    }
+
+    /// Appends constraints for a call to the built-in `debug_print` function
+    ///
+    /// The function takes a single argument of any type and returns unit. The code that prints
+    /// the argument is chosen based on its type during code generation.
+    fn append_debug_print<'s>(
+        &mut self,
+        call: &'a ast2::CallExpr<'a>,
+        // The type expected from the call expression
+        return_type: TyVar,
+        scope: &mut Scope<'a, 's>,
+    ) -> Result<tyir::CallExpr<'a>, Error> {
+        let ast2::CallExpr {func_name, args, named_args} = call;
+
+        if let Some(ast2::NamedArg {name, value: _}) = named_args.first() {
+            return Err(Error::UnknownNamedArg {
+                func_name: func_name.to_string(),
+                name: name.to_string(),
+            });
+        }
+        if args.len() != 1 {
+            return Err(Error::ArityMismatch {
+                func_name: func_name.to_string(),
+                expected: 1,
+                actual: args.len(),
+            });
+        }
+
+        self.constraints.ty_var_is_ty(return_type, self.prims.unit())?;
+
+        // The argument may have any type, so its type variable is left unconstrained
+        let arg_ty_var = self.constraints.fresh_type_var();
+        let arg = self.append_expr(&args[0], arg_ty_var, scope)?;
+
+        Ok(tyir::CallExpr {
+            func_name: func_name.clone(),
+            args: vec![arg],
+        })
     }
 
     /// Appends constraints for the given function call given the signature
@@ -589,7 +1163,7 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
         sig: &ir::FuncSig,
         // The function name to call, not necessarily the original function/method name
         func_name: ast2::IdentPath<'a>,
-        args: &'a [ast2::Expr<'a>],
+        args: &[&'a ast2::Expr<'a>],
         // An extra argument to prepend on to the list of arguments passed to the call
         // Used to implement methods with a `self` parameter
         mut extra_first_arg: Option<tyir::Expr<'a>>,
@@ -661,10 +1235,21 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
                 (field_lvalue, field_ty_var)
             },
 
-            ast2::LValueExpr::Var(ident) => {
-                let var_ty_var = scope.get(ident).context(UnresolvedName {name: *ident})?;
-                let var_lvalue = tyir::LValueExpr::Var(ident, var_ty_var);
-                (var_lvalue, var_ty_var)
+            ast2::LValueExpr::Var(ident) => match scope.get(ident) {
+                Some(var_ty_var) => {
+                    let var_lvalue = tyir::LValueExpr::Var(ident, var_ty_var);
+                    (var_lvalue, var_ty_var)
+                },
+
+                // Local variables shadow any global variable with the same name
+                None => {
+                    let (name, static_ty) = self.lookup_static(ident)
+                        .context(UnresolvedName {name: *ident})?;
+                    let static_ty_var = self.constraints.fresh_type_var();
+                    self.constraints.ty_var_is_ty(static_ty_var, static_ty)?;
+                    let static_lvalue = tyir::LValueExpr::StaticVar(name, static_ty_var);
+                    (static_lvalue, static_ty_var)
+                },
             },
         };
 
@@ -712,7 +1297,7 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
 
         let struct_ty = match name {
             ast2::NamedTy::SelfType => self.self_ty.context(UnresolvedType {name: "Self"})?,
-            ast2::NamedTy::Named(name) => self.decls.type_id(name).context(UnresolvedType {name: *name})?,
+            ast2::NamedTy::Named(name) => self.lookup_type(&ast2::Ty::Named(name))?,
         };
         // The return type of this expression is a value of the struct type
         self.constraints.ty_var_is_ty(return_type, struct_ty)?;
@@ -722,7 +1307,7 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
         for field in parsed_fields {
             let ast2::StructFieldValue {name: field_name, value} = field;
             let field_ty = self.decls.field_type(struct_ty, field_name)
-                .context(UnresolvedField {field_name: *field_name, ty: struct_ty})?;
+                .ok_or_else(|| self.unresolved_field(struct_ty, field_name))?;
 
             // The type of the value expression must equal the field type
             let rhs_ty_var = self.constraints.fresh_type_var();
@@ -736,18 +1321,246 @@ impl<'a, 'b, 'c> FunctionConstraintGenerator<'a, 'b, 'c> {
             }
         }
 
-        //TODO: Validate that the number of de-duplicated field_values is exactly equal to the
-        // number of fields in the struct decl
+        // Any fields that were not specified are initialized with their default value
+        let decls = self.decls;
+        for (field_name, &field_ty) in decls.fields(struct_ty) {
+            if field_values.contains_key(field_name) {
+                continue;
+            }
+
+            let default = decls.field_default(struct_ty, field_name)
+                .with_context(|| MissingField {
+                    type_name: decls.type_name(struct_ty).to_string(),
+                    field: field_name.to_string(),
+                })?;
+
+            // The default was already checked against the type of the field along with the
+            // struct, but it is inlined here so it still needs to be lowered like any other value
+            let default_ty_var = self.constraints.fresh_type_var();
+            self.constraints.ty_var_is_ty(default_ty_var, field_ty)?;
+            let value = self.append_expr(default, default_ty_var, scope)?;
+            field_values.insert(*field_name, value);
+        }
 
         Ok(tyir::StructLiteral {ty_id: struct_ty, field_values})
     }
 
+    /// Computes the offset of the given field, which is known at compile-time
+    fn append_offset_of(
+        &mut self,
+        offset_of: &ast2::OffsetOf<'a>,
+        // The type expected from the offset_of expression
+        return_type: TyVar,
+    ) -> Result<i64, Error> {
+        let ast2::OffsetOf {self_ty, field} = offset_of;
+
+        let self_ty = self.lookup_type(self_ty)?;
+        let offset = self.decls.field_offset(self_ty, field)
+            .ok_or_else(|| self.unresolved_field(self_ty, field))?;
+
+        // The offset is always an int, regardless of the platform
+        self.constraints.ty_var_is_ty(return_type, self.prims.int())?;
+
+        Ok(offset as i64)
+    }
+
+    /// Appends constraints for the given associated constant and returns its inlined value
+    fn append_assoc_const(
+        &mut self,
+        path: &ast2::AssocConstPath<'a>,
+        // The type expected from the associated constant
+        return_type: TyVar,
+    ) -> Result<tyir::Expr<'a>, Error> {
+        let ast2::AssocConstPath {self_ty, name} = path;
+
+        let self_ty = self.lookup_type(self_ty)?;
+        let &AssocConstInfo {ty, value} = self.decls.assoc_const(self_ty, name)
+            .ok_or_else(|| Error::UnresolvedAssocConst {
+                const_name: name.to_string(),
+                ty_name: self.ty_name(self_ty),
+            })?;
+
+        self.constraints.ty_var_is_ty(return_type, ty)?;
+
+        // The value is a constant, so it is inlined in place of the path. No local variables are
+        // in scope in the value.
+        self.append_expr(value, return_type, &mut Scope::default())
+    }
+
     /// Resolves a single type to either a declared type or a primitive
     fn lookup_type(&self, ty: &ast2::Ty) -> Result<TyId, Error> {
         match ty {
             ast2::Ty::Unit => Ok(self.prims.unit()),
             ast2::Ty::SelfType => self.self_ty.context(UnresolvedType {name: "Self"}),
-            ast2::Ty::Named(ty) => self.decls.type_id(ty).context(UnresolvedType {name: *ty}),
+            ast2::Ty::Named(ty) => self.module_scope.get(ty)
+                .and_then(|name| self.decls.type_id(&name))
+                .context(UnresolvedType {name: *ty}),
+            ast2::Ty::Never => Ok(self.prims.never()),
         }
     }
+
+    /// Returns the name that the global variable with the given name is declared with in the
+    /// program along with its type, or None if there is no global variable with that name in scope
+    fn lookup_static(&self, name: ir::Ident<'a>) -> Option<(ir::Ident<'a>, TyId)> {
+        let name = self.module_scope.get(name)?;
+        self.decls.static_ty(&name).map(|ty| (name, ty))
+    }
+}
+
+/// Evaluates a call to the given function at compile-time if its arguments are all literals and
+/// the function is a primitive whose result only depends on those arguments
+///
+/// Currently, this only applies to the prelude's `bstr_len` called on a byte string literal. Any
+/// other function with that name (e.g. one declared in an inline module) is never folded. The
+/// result is the value of the `int` produced by the call.
+fn fold_const_call(func: &FunctionInfo, args: &[ast2::Expr], named_args: &[ast2::NamedArg]) -> Option<i64> {
+    if !func.is_extern || !named_args.is_empty() {
+        return None;
+    }
+
+    match (func.name, args) {
+        ("bstr_len", [ast2::Expr::BStrLiteral(value)]) => Some(value.len() as i64),
+        _ => None,
+    }
+}
+
+/// Returns the function called using the given path, along with the path of that function using
+/// the names declared in the program (see `ModuleScope`), or None if no function with that path is
+/// in scope
+fn lookup_callee<'a>(
+    func_name: &ast2::IdentPath<'a>,
+    decls: &'a DeclMap<'a>,
+    module_scope: &ModuleScope<'a>,
+) -> Option<(&'a FunctionInfo<'a>, ast2::IdentPath<'a>)> {
+    match func_name.components[..] {
+        [name] => {
+            let name = module_scope.get(name)?;
+            let func = decls.func(&name)?;
+            Some((func, ast2::IdentPath::from(name)))
+        },
+        [prefix, name] => {
+            // Types and inline modules share the same namespace
+            let prefix = module_scope.get(prefix)?;
+            let func = if decls.is_module(&prefix) {
+                decls.module_func(&prefix, &name)
+            } else {
+                decls.type_id(&prefix).and_then(|ty_id| decls.method(ty_id, &name))
+            }?;
+            Some((func, ast2::IdentPath::from(vec![prefix, name])))
+        },
+        _ => None,
+    }
+}
+
+/// Combines the positional and named arguments of a call into a single list of arguments, ordered
+/// by the parameters of the function being called
+///
+/// Arguments are evaluated in the order of the parameters, not in the order they were written. To
+/// keep that from being observable, a named argument may only be evaluated before an argument
+/// written before it if neither of them may have side effects.
+fn order_call_args<'a>(
+    sig: &ir::FuncSig,
+    func_name: &ast2::IdentPath<'a>,
+    args: &'a [ast2::Expr<'a>],
+    named_args: &'a [ast2::NamedArg<'a>],
+    decls: &'a DeclMap<'a>,
+    module_scope: &ModuleScope<'a>,
+) -> Result<Vec<&'a ast2::Expr<'a>>, Error> {
+    // Arity is checked later, so there is nothing to reorder if every argument is positional
+    if named_args.is_empty() {
+        return Ok(args.iter().collect());
+    }
+
+    let ir::FuncSig {return_type: _, params} = sig;
+    if args.len() + named_args.len() > params.len() {
+        return Err(Error::ArityMismatch {
+            func_name: func_name.to_string(),
+            expected: params.len(),
+            actual: args.len() + named_args.len(),
+        });
+    }
+
+    // Positional arguments fill the first parameters in order. Each argument is stored with its
+    // position in the call as it was written.
+    let mut ordered_args: Vec<_> = args.iter().enumerate().map(Some).collect();
+    ordered_args.resize(params.len(), None);
+
+    for (i, ast2::NamedArg {name, value}) in named_args.iter().enumerate() {
+        let index = params.iter().position(|param| param.name == *name)
+            .with_context(|| UnknownNamedArg {func_name, name: *name})?;
+
+        if ordered_args[index].is_some() {
+            return Err(Error::DuplicateArg {
+                func_name: func_name.to_string(),
+                param: name.to_string(),
+            });
+        }
+        ordered_args[index] = Some((args.len() + i, value));
+    }
+
+    let ordered_args = ordered_args.into_iter().zip(params).map(|(arg, param)| {
+        arg.with_context(|| MissingArg {func_name, param: param.name})
+    }).collect::<Result<Vec<_>, _>>()?;
+
+    for (i, &(position, arg)) in ordered_args.iter().enumerate() {
+        let is_reordered_with = |&(later_position, later_arg): &(usize, &ast2::Expr)| {
+            later_position < position &&
+                (may_have_side_effects(arg, decls, module_scope) ||
+                    may_have_side_effects(later_arg, decls, module_scope))
+        };
+        if ordered_args[i+1..].iter().any(is_reordered_with) {
+            return Err(Error::ReorderedSideEffects {
+                func_name: func_name.to_string(),
+                param: params[i].name.to_string(),
+            });
+        }
+    }
+
+    Ok(ordered_args.into_iter().map(|(_, arg)| arg).collect())
+}
+
+/// Returns false if evaluating the given expression is known to have no side effects
+///
+/// This is conservative since types are not known yet: calls are only free of side effects if they
+/// call a function marked with `#[pure]`, and method calls (including operators) are only free of
+/// side effects if every method with the same name is pure. Conditionals and blocks are never
+/// considered.
+fn may_have_side_effects<'a>(
+    expr: &ast2::Expr<'a>,
+    decls: &'a DeclMap<'a>,
+    module_scope: &ModuleScope<'a>,
+) -> bool {
+    use ast2::Expr::*;
+    match expr {
+        MethodCall(call) => {
+            let ast2::MethodCall {lhs, method_name, args} = &**call;
+            !decls.method_is_always_pure(method_name) ||
+                may_have_side_effects(lhs, decls, module_scope) ||
+                args.iter().any(|arg| may_have_side_effects(arg, decls, module_scope))
+        },
+        FieldAccess(access) => may_have_side_effects(&access.lhs, decls, module_scope),
+        Call(ast2::CallExpr {func_name, args, named_args}) => {
+            let callee = lookup_callee(func_name, decls, module_scope);
+
+            !callee.map(|(func, _)| func.is_pure).unwrap_or(false) ||
+                args.iter().any(|arg| may_have_side_effects(arg, decls, module_scope)) ||
+                named_args.iter().any(|arg| may_have_side_effects(&arg.value, decls, module_scope))
+        },
+        StructLiteral(ast2::StructLiteral {name: _, field_values}) => {
+            field_values.iter().any(|field| may_have_side_effects(&field.value, decls, module_scope))
+        },
+        VarAssign(_) |
+        Cond(_) |
+        Return(_) => true,
+        OffsetOf(_) |
+        AssocConst(_) |
+        BStrLiteral(_) |
+        IntegerLiteral(_) |
+        RealLiteral(_) |
+        ComplexLiteral(_) |
+        BoolLiteral(_) |
+        UnitLiteral |
+        SelfLiteral |
+        Var(_) => false,
+    }
 }
@@ -0,0 +1,131 @@
+//! Finds calls to `assert` whose condition is a constant that is always false
+
+use crate::ir::{self, visit::{self, Visitor}};
+
+use super::Warning;
+
+/// The name of the prelude function that checks an assertion
+const ASSERT_FUNC_NAME: &str = "assert";
+
+/// Returns a warning for each assertion in the given function that can never pass
+///
+/// Only conditions that can be evaluated at compile-time are checked: `false` and comparisons
+/// between two `int` constants. Constant calls such as `bstr_len(b"abc")` are already folded into
+/// literals by the time this check runs, so they are handled too.
+pub fn check_function(func: &ir::Function) -> Vec<Warning> {
+    let ir::Function {name, body, ..} = func;
+
+    let mut finder = FailingAssertFinder {line: None, failing: Vec::new()};
+    finder.visit_block(body);
+
+    finder.failing.into_iter().map(|line| Warning::AssertionAlwaysFails {
+        func_name: name.to_string(),
+        line,
+    }).collect()
+}
+
+struct FailingAssertFinder {
+    /// The line of the statement currently being visited (if known)
+    line: Option<usize>,
+    /// The line of each assertion found that always fails
+    failing: Vec<Option<usize>>,
+}
+
+impl<'a> Visitor<'a> for FailingAssertFinder {
+    fn visit_stmt(&mut self, stmt: &ir::Stmt<'a>, line: Option<usize>) {
+        // Restore the line afterwards since nested blocks overwrite it
+        let outer_line = std::mem::replace(&mut self.line, line);
+        visit::walk_stmt(self, stmt);
+        self.line = outer_line;
+    }
+
+    fn visit_ret(&mut self, ret: &ir::Expr<'a>, line: Option<usize>) {
+        let outer_line = std::mem::replace(&mut self.line, line);
+        self.visit_expr(ret);
+        self.line = outer_line;
+    }
+
+    fn visit_expr(&mut self, expr: &ir::Expr<'a>) {
+        if let ir::Expr::Call(ir::CallExpr {func_name, args}, _) = expr {
+            if let ([name], [cond]) = (&func_name.components[..], &args[..]) {
+                if *name == ASSERT_FUNC_NAME && const_bool_value(cond) == Some(false) {
+                    self.failing.push(self.line);
+                }
+            }
+        }
+
+        visit::walk_expr(self, expr);
+    }
+}
+
+/// Returns the value of the given expression if it is a `bool` that is known at compile-time
+fn const_bool_value(expr: &ir::Expr) -> Option<bool> {
+    match expr {
+        &ir::Expr::BoolLiteral(value, _) => Some(value),
+
+        ir::Expr::Call(ir::CallExpr {func_name, args}, _) => match (&func_name.components[..], &args[..]) {
+            (["bool__not"], [value]) => const_bool_value(value).map(|value| !value),
+
+            (&[name], [ir::Expr::IntegerLiteral(lhs, _), ir::Expr::IntegerLiteral(rhs, _)]) => {
+                match name {
+                    "int__eq" => Some(lhs == rhs),
+                    "int__gt" => Some(lhs > rhs),
+                    "int__gte" => Some(lhs >= rhs),
+                    "int__lt" => Some(lhs < rhs),
+                    "int__lte" => Some(lhs <= rhs),
+                    _ => None,
+                }
+            },
+
+            _ => None,
+        },
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tycheck::check_source;
+
+    /// Returns the line of each assertion in the given program that always fails
+    fn failing_asserts(source: &'static str) -> Vec<Option<usize>> {
+        let warnings = check_source(source).unwrap().warnings;
+
+        warnings.into_iter().filter_map(|warning| match warning {
+            Warning::AssertionAlwaysFails {func_name: _, line} => Some(line),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn constant_false() {
+        assert_eq!(failing_asserts("fn main() { assert(false); assert(true); }"), vec![Some(1)]);
+    }
+
+    #[test]
+    fn constant_comparison() {
+        assert_eq!(failing_asserts("fn main() { assert(1int == 2); assert(2int > 1); }"), vec![Some(1)]);
+        assert!(failing_asserts("fn main() { assert(bstr_len(b\"abc\") == 3); }").is_empty());
+    }
+
+    #[test]
+    fn non_constant_condition() {
+        assert!(failing_asserts("fn check(x: bool) { assert(x); }").is_empty());
+    }
+
+    #[test]
+    fn assertion_lines() {
+        let source = "fn main() {
+            assert(true);
+            assert(false);
+            if true {
+                assert(1int > 2);
+            }
+            assert(false)
+        }";
+        assert_eq!(failing_asserts(source), vec![Some(3), Some(5), Some(7)]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Checks that functions marked with `#[pure]` have no side effects and finds calls to pure
+//! functions whose results are never used
+
+use crate::ir::{self, visit::{self, Visitor}};
+use crate::resolve2::{DeclMap, FunctionInfo};
+
+use super::{Error, Warning};
+
+/// Returns an error if the given function is marked with `#[pure]` but may have side effects
+///
+/// The check is conservative: a pure function may only call other pure functions and may not
+/// assign to any static variables. Functions that cannot be found (e.g. the built-in
+/// `debug_print`) are never considered pure.
+pub fn check_function(func: &ir::Function, decls: &DeclMap) -> Result<(), Error> {
+    let ir::Function {name, body, is_pure, ..} = func;
+
+    if !is_pure {
+        return Ok(());
+    }
+
+    let mut checker = PurityChecker {func_name: name, decls, result: Ok(())};
+    checker.visit_block(body);
+    checker.result
+}
+
+/// Returns a warning for each statement in the given function that only calls a function marked
+/// with `#[pure]`
+///
+/// Calling a pure function has no side effects, so discarding its result means that the call
+/// does nothing.
+pub fn check_unused_calls(func: &ir::Function, decls: &DeclMap) -> Vec<Warning> {
+    let ir::Function {name, body, ..} = func;
+
+    let mut finder = UnusedCallFinder {decls, unused: Vec::new()};
+    finder.visit_block(body);
+
+    finder.unused.into_iter().map(|callee| Warning::UnusedPureCall {
+        callee,
+        func_name: name.to_string(),
+    }).collect()
+}
+
+/// Returns the function called using the given path, if any
+fn callee<'a>(func_name: &'a ir::IdentPath<'a>, decls: &'a DeclMap<'a>) -> Option<&'a FunctionInfo<'a>> {
+    match &func_name.components[..] {
+        // Calls to extern methods use the name that the method is linked in with
+        [name] => decls.func(name).or_else(|| decls.extern_method(name)),
+        [module_name, name] if decls.is_module(module_name) => decls.module_func(module_name, name),
+        [ty_name, name] => decls.type_id(ty_name).and_then(|ty_id| decls.method(ty_id, name)),
+        _ => None,
+    }
+}
+
+struct PurityChecker<'a> {
+    /// The name of the pure function being checked
+    func_name: &'a str,
+    decls: &'a DeclMap<'a>,
+    /// The first side effect found (if any)
+    result: Result<(), Error>,
+}
+
+impl<'a, 'f> Visitor<'f> for PurityChecker<'a> {
+    fn visit_expr(&mut self, expr: &ir::Expr<'f>) {
+        if self.result.is_err() {
+            return;
+        }
+
+        if let ir::Expr::Call(ir::CallExpr {func_name, args: _}, _) = expr {
+            let is_pure = callee(func_name, self.decls).map(|func| func.is_pure).unwrap_or(false);
+            if !is_pure {
+                self.result = Err(Error::ImpureCall {
+                    func_name: self.func_name.to_string(),
+                    callee: func_name.to_string(),
+                });
+                return;
+            }
+        }
+
+        visit::walk_expr(self, expr);
+    }
+
+    fn visit_lvalue(&mut self, lhs: &ir::LValueExpr<'f>) {
+        if self.result.is_err() {
+            return;
+        }
+
+        if let &ir::LValueExpr::StaticVar(name, _) = lhs {
+            self.result = Err(Error::ImpureStaticAssign {
+                func_name: self.func_name.to_string(),
+                name: name.to_string(),
+            });
+            return;
+        }
+
+        visit::walk_lvalue(self, lhs);
+    }
+}
+
+/// Finds statements that only call a pure function declared in the program
+struct UnusedCallFinder<'a> {
+    decls: &'a DeclMap<'a>,
+    /// The name of the function called by each statement found
+    unused: Vec<String>,
+}
+
+impl<'a, 'f> Visitor<'f> for UnusedCallFinder<'a> {
+    fn visit_stmt(&mut self, stmt: &ir::Stmt<'f>, _line: Option<usize>) {
+        if let ir::Stmt::Expr(ir::Expr::Call(ir::CallExpr {func_name, args: _}, _)) = stmt {
+            // Only functions declared in the program are reported so that the name in the
+            // warning is the same as the one that was written
+            let is_declared_pure = callee(func_name, self.decls)
+                .map(|func| func.is_pure && !func.is_extern)
+                .unwrap_or(false);
+            if is_declared_pure {
+                self.unused.push(func_name.to_string());
+            }
+        }
+
+        visit::walk_stmt(self, stmt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tycheck::check_source;
+
+    /// Type checks the given program, returning its warnings
+    fn check(source: &'static str) -> Result<Vec<Warning>, Error> {
+        check_source(source).map(|checked| checked.warnings)
+    }
+
+    #[test]
+    fn pure_calls() {
+        let source = "
+            #[pure] fn double(x: int) -> int { x * 2 }
+            #[pure] fn quadruple(x: int) -> int { double(double(x)) }
+            fn main() { print_int(quadruple(3)); }
+        ";
+        assert!(check(source).is_ok());
+    }
+
+    #[test]
+    fn impure_calls() {
+        let source = "#[pure] fn double(x: int) -> int { print_int(x); x * 2 }";
+        assert!(matches!(check(source), Err(Error::ImpureCall {..})));
+
+        // Calling a function that is not marked as pure is not allowed, even if it has no side
+        // effects
+        let source = "
+            fn double(x: int) -> int { x * 2 }
+            #[pure] fn quadruple(x: int) -> int { double(double(x)) }
+        ";
+        assert!(matches!(check(source), Err(Error::ImpureCall {..})));
+    }
+
+    #[test]
+    fn static_assign() {
+        let source = "
+            static mut COUNT: int = 0;
+            #[pure] fn count() -> int { COUNT }
+        ";
+        assert!(check(source).is_ok());
+
+        let source = "
+            static mut COUNT: int = 0;
+            #[pure] fn increment() { COUNT = 1; }
+        ";
+        assert!(matches!(check(source), Err(Error::ImpureStaticAssign {..})));
+    }
+
+    #[test]
+    fn unused_pure_call() {
+        let source = "
+            #[pure] fn double(x: int) -> int { x * 2 }
+            fn main() { double(2); print_int(double(3)); }
+        ";
+        let warnings = check(source).unwrap();
+        assert_eq!(warnings, vec![Warning::UnusedPureCall {
+            callee: "double".to_string(),
+            func_name: "main".to_string(),
+        }]);
+    }
+}
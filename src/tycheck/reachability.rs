@@ -0,0 +1,79 @@
+//! Checks for code that can never run because it comes after a `return` and for blocks whose
+//! end can be reached without producing a value
+
+use crate::ir::{self, visit::Visitor};
+use crate::primitives2::Primitives;
+
+use super::Error;
+
+/// Returns an error if any block in the given function has code after a statement that always
+/// returns from the function, or if the end of a block that must produce a value can be reached
+pub fn check_function(func: &ir::Function, prims: &Primitives) -> Result<(), Error> {
+    let ir::Function {name, body, c_body, ..} = func;
+
+    // Raw C code is trusted to return a value
+    if c_body.is_some() {
+        return Ok(());
+    }
+
+    if is_missing_value(body, prims) {
+        return Err(Error::MissingReturn {
+            func_name: name.to_string(),
+            line: body.stmt_lines.last().copied(),
+        });
+    }
+
+    check_block(body, prims)
+}
+
+/// Returns true if the end of the block can be reached without producing a value of the type
+/// of the block
+fn is_missing_value(block: &ir::Block, prims: &Primitives) -> bool {
+    let ir::Block {stmts: _, stmt_lines: _, ret, ret_ty} = block;
+    ret.is_none() && *ret_ty != prims.unit() && !block.end_is_unreachable(prims.never())
+}
+
+fn check_block(block: &ir::Block, prims: &Primitives) -> Result<(), Error> {
+    let mut checker = BlockChecker {prims, result: Ok(())};
+    checker.visit_block(block);
+    checker.result
+}
+
+/// Checks every block nested within the visited block, stopping at the first error
+struct BlockChecker<'p> {
+    prims: &'p Primitives,
+    result: Result<(), Error>,
+}
+
+impl<'a, 'p> Visitor<'a> for BlockChecker<'p> {
+    fn visit_block(&mut self, block: &ir::Block<'a>) {
+        if self.result.is_err() {
+            return;
+        }
+
+        let ir::Block {stmts, stmt_lines, ret, ret_ty} = block;
+
+        if is_missing_value(block, self.prims) {
+            self.result = Err(Error::MismatchedTypes {expected: *ret_ty, actual: self.prims.unit()});
+            return;
+        }
+
+        for (i, stmt) in stmts.iter().enumerate() {
+            self.visit_stmt(stmt, stmt_lines.get(i).copied());
+            if self.result.is_err() {
+                return;
+            }
+
+            let is_last = i + 1 == stmts.len() && ret.is_none();
+            if !is_last && stmt.always_returns() {
+                // The next statement (or the return expression) is the first one that can never run
+                self.result = Err(Error::UnreachableCode {line: stmt_lines.get(i + 1).copied()});
+                return;
+            }
+        }
+
+        if let Some(ret) = ret {
+            self.visit_ret(ret, stmt_lines.get(stmts.len()).copied());
+        }
+    }
+}
@@ -27,13 +27,9 @@ impl<'a, 's> Scope<'a, 's> {
         }
     }
 
-    /// Returns true if the given variable name is in scope at the current level, or in any parent
-    /// scope.
-    pub fn contains(&self, name: &ast2::Ident<'a>) -> bool {
-        self.get(name).is_some()
-    }
-
     /// Returns the type variable associated with a given variable name
+    ///
+    /// The innermost declaration of the name is used if it is declared in more than one scope.
     pub fn get(&self, name: ast2::Ident<'a>) -> Option<TyVar> {
         self.current.get(name).copied().or_else(|| match self.parent {
             Some(parent) => parent.get(name),
@@ -0,0 +1,87 @@
+//! Finds similarly named items to suggest when a name cannot be resolved.
+
+/// Returns the candidate closest to `name`, if any candidate is close enough to be a likely typo
+///
+/// Ties are broken alphabetically so that the suggestion does not depend on iteration order.
+pub fn closest_name<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+    where I: IntoIterator<Item = &'a str>,
+{
+    // Allow roughly one edit for every three characters, but always allow at least one edit
+    let max_dist = (name.chars().count() / 3).max(1);
+
+    candidates.into_iter()
+        .filter(|&candidate| candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(dist, _)| dist <= max_dist)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Computes the edit distance between two strings
+///
+/// This is the minimum number of single character insertions, deletions, substitutions, or
+/// transpositions of two adjacent characters required to turn one string into the other (the
+/// "optimal string alignment" variant of the Damerau-Levenshtein distance). Counting a
+/// transposition as a single edit means that common typos like `valeu` are still close to `value`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Only the previous two rows of the distance matrix are needed to compute the next one
+    let mut prev_prev_row = vec![0; b.len() + 1];
+    let mut prev_row: Vec<usize> = (0..=b.len()).collect();
+    let mut row = vec![0; b.len() + 1];
+
+    for (i, &a_ch) in a.iter().enumerate() {
+        row[0] = i + 1;
+        for (j, &b_ch) in b.iter().enumerate() {
+            let subst_cost = if a_ch == b_ch { 0 } else { 1 };
+            row[j+1] = (prev_row[j] + subst_cost)
+                .min(prev_row[j+1] + 1)
+                .min(row[j] + 1);
+
+            if i > 0 && j > 0 && a_ch == b[j-1] && a[i-1] == b_ch {
+                row[j+1] = row[j+1].min(prev_prev_row[j-1] + 1);
+            }
+        }
+
+        std::mem::swap(&mut prev_prev_row, &mut prev_row);
+        std::mem::swap(&mut prev_row, &mut row);
+    }
+
+    prev_row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("add", ""), 3);
+        assert_eq!(edit_distance("", "add"), 3);
+        assert_eq!(edit_distance("add", "add"), 0);
+        assert_eq!(edit_distance("addd", "add"), 1);
+        assert_eq!(edit_distance("prnt_int", "print_int"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        // Transposing two adjacent characters is a single edit
+        assert_eq!(edit_distance("valeu", "value"), 1);
+        assert_eq!(edit_distance("ab", "ba"), 1);
+        assert_eq!(edit_distance("abc", "ca"), 3);
+    }
+
+    #[test]
+    fn closest_candidate() {
+        let names = &["add", "sub", "mul", "div", "print_int", "print_bool"];
+
+        assert_eq!(closest_name("addd", names.iter().copied()), Some("add"));
+        assert_eq!(closest_name("prnt_int", names.iter().copied()), Some("print_int"));
+        assert_eq!(closest_name("pritn_int", names.iter().copied()), Some("print_int"));
+        // Ties are broken alphabetically
+        assert_eq!(closest_name("sul", names.iter().copied()), Some("mul"));
+        // Too far from anything to be a typo
+        assert_eq!(closest_name("foo", names.iter().copied()), None);
+        assert_eq!(closest_name("multiply", names.iter().copied()), None);
+    }
+}
@@ -2,6 +2,7 @@
 //! variables and type IDs directly without having to invent a way to uniquely address AST nodes.
 
 use std::collections::HashMap;
+use std::path::Path;
 
 use crate::ir;
 use crate::ast2::{Ident, IdentPath};
@@ -15,16 +16,43 @@ pub struct Function<'a> {
     pub name: Ident<'a>,
     pub sig: ir::FuncSig<'a>,
     pub body: Block<'a>,
+    pub c_body: Option<&'a str>,
+    pub is_test: bool,
+    pub is_pure: bool,
+    pub source_path: Option<&'a Path>,
 }
 
 impl<'a> Function<'a> {
     /// Applies the given substitution to this function and returns the corresponding IR
     pub fn apply_subst(self, subst: &TypeSubst) -> ir::Function<'a> {
-        let Self {name, sig, body} = self;
+        let Self {name, sig, body, c_body, is_test, is_pure, source_path} = self;
         ir::Function {
             name,
             sig,
             body: body.apply_subst(subst),
+            c_body,
+            is_test,
+            is_pure,
+            source_path,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Static<'a> {
+    pub name: Ident<'a>,
+    pub ty: TyId,
+    pub init: Expr<'a>,
+}
+
+impl<'a> Static<'a> {
+    /// Applies the given substitution to this global variable and returns the corresponding IR
+    pub fn apply_subst(self, subst: &TypeSubst) -> ir::Static<'a> {
+        let Self {name, ty, init} = self;
+        ir::Static {
+            name,
+            ty,
+            init: init.apply_subst(subst),
         }
     }
 }
@@ -32,6 +60,9 @@ impl<'a> Function<'a> {
 #[derive(Debug)]
 pub struct Block<'a> {
     pub stmts: Vec<Stmt<'a>>,
+    /// The line (starting at 1) of the start of each statement in `stmts`, followed by the line
+    /// of `ret` (if any)
+    pub stmt_lines: Vec<usize>,
     /// The final statement of the block, used as the return value of the block
     pub ret: Option<Expr<'a>>,
     /// The type variable of the return expression (still provided even if the return expression
@@ -42,9 +73,10 @@ pub struct Block<'a> {
 impl<'a> Block<'a> {
     /// Applies the given substitution to this block and returns the corresponding IR
     pub fn apply_subst(self, subst: &TypeSubst) -> ir::Block<'a> {
-        let Self {stmts, ret, ret_ty_var} = self;
+        let Self {stmts, stmt_lines, ret, ret_ty_var} = self;
         ir::Block {
             stmts: stmts.into_iter().map(|stmt| stmt.apply_subst(subst)).collect(),
+            stmt_lines,
             ret: ret.map(|ret| ret.apply_subst(subst)),
             ret_ty: ret_ty_var.apply_subst(subst),
         }
@@ -56,6 +88,10 @@ pub enum Stmt<'a> {
     /// A conditional in statement position always has type unit
     Cond(Cond<'a>),
     WhileLoop(WhileLoop<'a>),
+    Break,
+    Continue,
+    /// A nested block in statement position always has type unit
+    Block(Block<'a>),
     VarDecl(VarDecl<'a>),
     Expr(Expr<'a>),
 }
@@ -67,6 +103,9 @@ impl<'a> Stmt<'a> {
         match self {
             Cond(cond) => ir::Stmt::Cond(cond.apply_subst(subst)),
             WhileLoop(wloop) => ir::Stmt::WhileLoop(wloop.apply_subst(subst)),
+            Break => ir::Stmt::Break,
+            Continue => ir::Stmt::Continue,
+            Block(block) => ir::Stmt::Block(block.apply_subst(subst)),
             VarDecl(decl) => ir::Stmt::VarDecl(decl.apply_subst(subst)),
             Expr(expr) => ir::Stmt::Expr(expr.apply_subst(subst)),
         }
@@ -131,6 +170,7 @@ pub enum Expr<'a> {
     BoolLiteral(bool, TyVar),
     UnitLiteral(TyVar),
     Var(Ident<'a>, TyVar),
+    StaticVar(Ident<'a>, TyVar),
 }
 
 impl<'a> Expr<'a> {
@@ -189,6 +229,10 @@ impl<'a> Expr<'a> {
             Var(var_name, ty_var) => {
                 ir::Expr::Var(var_name, ty_var.apply_subst(subst))
             },
+
+            StaticVar(var_name, ty_var) => {
+                ir::Expr::StaticVar(var_name, ty_var.apply_subst(subst))
+            },
         }
     }
 }
@@ -198,6 +242,7 @@ impl<'a> Expr<'a> {
 pub enum LValueExpr<'a> {
     FieldAccess(FieldAccess<'a>, TyVar),
     Var(Ident<'a>, TyVar),
+    StaticVar(Ident<'a>, TyVar),
 }
 
 impl<'a> LValueExpr<'a> {
@@ -212,6 +257,10 @@ impl<'a> LValueExpr<'a> {
             Var(var_name, ty_var) => {
                 ir::LValueExpr::Var(var_name, ty_var.apply_subst(subst))
             },
+
+            StaticVar(var_name, ty_var) => {
+                ir::LValueExpr::StaticVar(var_name, ty_var.apply_subst(subst))
+            },
         }
     }
 }
@@ -0,0 +1,206 @@
+//! Finds `else` branches that can never run because an earlier condition and its negation cover
+//! every possible value
+
+use crate::ir::{self, visit::{self, Visitor}};
+
+use super::Warning;
+
+/// The name of the method that negates a `bool` (i.e. the `!` operator)
+const BOOL_NOT_FUNC_NAME: &str = "bool__not";
+
+/// Returns a warning for each conditional in the given function whose `else` branch is unreachable
+/// because the conditions of the `if`/`else if` chain include both a value and its negation (e.g.
+/// `if b { ... } else if !b { ... } else { ... }`)
+///
+/// This check is conservative. Only conditions that are a local variable or a field access on a
+/// local variable are considered, since evaluating those cannot change their value. Statics may be
+/// modified by any call made between two conditions, so they are never considered.
+pub fn check_function(func: &ir::Function) -> Vec<Warning> {
+    let ir::Function {name, body, ..} = func;
+
+    let mut finder = UnreachableElseFinder {line: None, unreachable: Vec::new()};
+    finder.visit_block(body);
+
+    finder.unreachable.into_iter().map(|line| Warning::UnreachableElse {
+        func_name: name.to_string(),
+        line,
+    }).collect()
+}
+
+struct UnreachableElseFinder {
+    /// The line of the statement currently being visited (if known)
+    line: Option<usize>,
+    /// The line of the statement containing each conditional with an unreachable `else` branch
+    unreachable: Vec<Option<usize>>,
+}
+
+impl<'a> Visitor<'a> for UnreachableElseFinder {
+    fn visit_stmt(&mut self, stmt: &ir::Stmt<'a>, line: Option<usize>) {
+        // Restore the line afterwards since nested blocks overwrite it
+        let outer_line = std::mem::replace(&mut self.line, line);
+        visit::walk_stmt(self, stmt);
+        self.line = outer_line;
+    }
+
+    fn visit_ret(&mut self, ret: &ir::Expr<'a>, line: Option<usize>) {
+        let outer_line = std::mem::replace(&mut self.line, line);
+        self.visit_expr(ret);
+        self.line = outer_line;
+    }
+
+    fn visit_cond(&mut self, cond: &ir::Cond<'a>) {
+        let ir::Cond {conds, else_body} = cond;
+
+        if else_body.is_some() && has_negated_pair(conds) {
+            self.unreachable.push(self.line);
+        }
+
+        visit::walk_cond(self, cond);
+    }
+}
+
+/// Returns true if one of the given conditions is the negation of another
+fn has_negated_pair(conds: &[(ir::Expr, ir::Block)]) -> bool {
+    conds.iter().enumerate().any(|(i, (cond_expr, _))| {
+        conds[i+1..].iter().any(|(other, _)| {
+            is_negation_of(other, cond_expr) || is_negation_of(cond_expr, other)
+        })
+    })
+}
+
+/// Returns true if `expr` is `!value` and `value` is a place expression that is the same as `other`
+fn is_negation_of(expr: &ir::Expr, other: &ir::Expr) -> bool {
+    match expr {
+        ir::Expr::Call(ir::CallExpr {func_name, args}, _) => match (&func_name.components[..], &args[..]) {
+            ([name], [value]) => *name == BOOL_NOT_FUNC_NAME && same_place(value, other),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns true if both expressions refer to the same local variable or to the same field of a
+/// local variable
+///
+/// Any other kind of expression is never considered the same, even if the expressions are
+/// identical, since evaluating it may produce a different value each time.
+fn same_place(expr1: &ir::Expr, expr2: &ir::Expr) -> bool {
+    match (expr1, expr2) {
+        (ir::Expr::Var(name1, _), ir::Expr::Var(name2, _)) => name1 == name2,
+        (ir::Expr::FieldAccess(access1, _), ir::Expr::FieldAccess(access2, _)) => {
+            access1.field == access2.field && same_place(&access1.lhs, &access2.lhs)
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tycheck::check_source;
+
+    /// Returns the line of each conditional in the given program with an unreachable `else`
+    fn unreachable_elses(source: &'static str) -> Vec<Option<usize>> {
+        let warnings = check_source(source).unwrap().warnings;
+
+        warnings.into_iter().filter_map(|warning| match warning {
+            Warning::UnreachableElse {func_name: _, line} => Some(line),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn unreachable_else_not_operator() {
+        let source = "fn main() {
+            let b = true;
+            if b {
+                print_int(1);
+            } else if !b {
+                print_int(2);
+            } else {
+                print_int(3);
+            }
+        }";
+        assert_eq!(unreachable_elses(source), vec![Some(3)]);
+
+        // The negation may come first
+        let source = "
+            struct Task { done: bool }
+            impl Task {
+                fn check(self) {
+                    if !self.done {
+                        print_int(1);
+                    } else if self.done {
+                        print_int(2);
+                    } else {
+                        print_int(3);
+                    }
+                }
+            }
+            fn main() { Task {done: true}.check(); }
+        ";
+        assert_eq!(unreachable_elses(source), vec![Some(5)]);
+    }
+
+    #[test]
+    fn reachable_else() {
+        // Different variables
+        let source = "fn main() {
+            let a = true;
+            let b = true;
+            if a {
+                print_int(1);
+            } else if !b {
+                print_int(2);
+            } else {
+                print_int(3);
+            }
+        }";
+        assert!(unreachable_elses(source).is_empty());
+
+        // Calls may return a different value each time they are evaluated
+        let source = "
+            fn next() -> bool { bstr_len(read_line_bstr()) > 0 }
+            fn main() {
+                if next() {
+                    print_int(1);
+                } else if !next() {
+                    print_int(2);
+                } else {
+                    print_int(3);
+                }
+            }
+        ";
+        assert!(unreachable_elses(source).is_empty());
+
+        // Statics may be modified between the conditions
+        let source = "
+            static mut READY: bool = false;
+            fn ready() -> bool { READY = true; false }
+            fn main() {
+                if READY {
+                    print_int(1);
+                } else if ready() {
+                    print_int(2);
+                } else if !READY {
+                    print_int(3);
+                } else {
+                    print_int(4);
+                }
+            }
+        ";
+        assert!(unreachable_elses(source).is_empty());
+
+        // Without an `else`, there is nothing to warn about
+        let source = "fn main() {
+            let b = true;
+            if b {
+                print_int(1);
+            } else if !b {
+                print_int(2);
+            }
+        }";
+        assert!(unreachable_elses(source).is_empty());
+    }
+}
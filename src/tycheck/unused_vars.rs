@@ -0,0 +1,130 @@
+//! Finds local variables that are declared but never read
+
+use crate::ir::{self, visit::{self, Visitor}};
+
+use super::Warning;
+
+/// Returns a warning for each variable declared in the given function that is never read
+///
+/// Assigning to a variable does not count as reading it. Variables whose names start with `_` are
+/// never reported.
+pub fn check_function(func: &ir::Function) -> Vec<Warning> {
+    let ir::Function {name, body, ..} = func;
+
+    let mut finder = UnusedVarFinder {scopes: Vec::new(), unused: Vec::new()};
+    finder.visit_block(body);
+
+    finder.unused.into_iter().map(|var_name| Warning::UnusedVariable {
+        name: var_name.to_string(),
+        func_name: name.to_string(),
+    }).collect()
+}
+
+struct UnusedVarFinder<'a> {
+    /// The variables declared in each enclosing block in the order they were declared, each with
+    /// whether it has been read yet
+    ///
+    /// A variable may be declared more than once in the same block, so each declaration is tracked
+    /// separately instead of by name.
+    scopes: Vec<Vec<(ir::Ident<'a>, bool)>>,
+    /// The variables that went out of scope without being read, in the order they were declared
+    unused: Vec<ir::Ident<'a>>,
+}
+
+impl<'a> Visitor<'a> for UnusedVarFinder<'a> {
+    fn visit_block(&mut self, block: &ir::Block<'a>) {
+        self.scopes.push(Vec::new());
+
+        visit::walk_block(self, block);
+
+        let scope = self.scopes.pop().unwrap();
+        self.unused.extend(scope.into_iter()
+            .filter(|&(name, is_read)| !is_read && !name.starts_with('_'))
+            .map(|(name, _)| name));
+    }
+
+    fn visit_stmt(&mut self, stmt: &ir::Stmt<'a>, _line: Option<usize>) {
+        match stmt {
+            ir::Stmt::VarDecl(ir::VarDecl {ident, ty: _, expr}) => {
+                // The variable is not in scope until after its initializer is evaluated
+                self.visit_expr(expr);
+                self.scopes.last_mut().unwrap().push((*ident, false));
+            },
+            _ => visit::walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &ir::Expr<'a>) {
+        match expr {
+            &ir::Expr::Var(name, _) => self.read(name),
+            // Overwriting a variable does not read it, but assigning to a field reads the
+            // variable that contains the field (see `visit::walk_lvalue`)
+            _ => visit::walk_expr(self, expr),
+        }
+    }
+}
+
+impl<'a> UnusedVarFinder<'a> {
+    /// Marks the most recent declaration of the variable with the given name as read
+    ///
+    /// Function parameters are not tracked, so reading one does nothing.
+    fn read(&mut self, name: ir::Ident<'a>) {
+        let binding = self.scopes.iter_mut().rev()
+            .find_map(|scope| scope.iter_mut().rev().find(|(var_name, _)| *var_name == name));
+        if let Some((_, is_read)) = binding {
+            *is_read = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tycheck::check_source;
+
+    /// Returns the names of the unused variables in the given program
+    fn unused_vars(source: &'static str) -> Vec<String> {
+        let warnings = check_source(source).unwrap().warnings;
+
+        warnings.into_iter().filter_map(|warning| match warning {
+            Warning::UnusedVariable {name, func_name: _} => Some(name),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn used_variable() {
+        let source = "fn value() -> int { let x = 1; let y = x; y }";
+        assert!(unused_vars(source).is_empty());
+    }
+
+    #[test]
+    fn unused_variable() {
+        let source = "fn main() { let x = 1; let _ignored = 2; }";
+        assert_eq!(unused_vars(source), vec!["x"]);
+    }
+
+    #[test]
+    fn reassigned_but_never_read() {
+        let source = "fn main() { let x = 1; x = 2; let y = 3; x = y; }";
+        assert_eq!(unused_vars(source), vec!["x"]);
+    }
+
+    #[test]
+    fn shadowed_in_same_block() {
+        // Only the first `x` is never read
+        let source = "fn value() -> int { let x = 1; let x = 2; x }";
+        assert_eq!(unused_vars(source), vec!["x"]);
+
+        // The initializer reads the previous `x` before the new one is declared
+        let source = "fn value() -> int { let x = 1; let x = x; x }";
+        assert!(unused_vars(source).is_empty());
+    }
+
+    #[test]
+    fn used_in_nested_block() {
+        let source = "fn main() { let x = true; if true { let y = x; } }";
+        assert_eq!(unused_vars(source), vec!["y"]);
+    }
+}
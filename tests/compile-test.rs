@@ -97,6 +97,17 @@ fn run_pass() -> io::Result<()> {
                 if output.stdout != expected_stdout.as_bytes() {
                     panic!("Output for '{}' did not match '{}'", entry_path.display(), stdout_file.display());
                 }
+
+                // Programs that are expected to fail at runtime check their error output too
+                let stderr_file = entry_path.with_extension("stderr");
+                if stderr_file.exists() {
+                    let expected_stderr = fs::read_to_string(&stderr_file)
+                        .unwrap_or_else(|err| panic!("Failed to open '{}': {}", stderr_file.display(), err));
+
+                    if output.stderr != expected_stderr.as_bytes() {
+                        panic!("Error output for '{}' did not match '{}'", entry_path.display(), stderr_file.display());
+                    }
+                }
             },
             Err(_) => panic!("Compile failed for '{}'", entry_path.display()),
         }